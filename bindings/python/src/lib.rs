@@ -0,0 +1,166 @@
+use pmsx003::aqi::{self, NowCast, NOWCAST_HOURS};
+use pmsx003::capture::Replay;
+use pmsx003::OutputFrame;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+/// Measurements of a single frame
+#[pyclass(name = "Frame", module = "pmsx003", frozen, get_all, eq)]
+#[derive(Clone, PartialEq)]
+struct PyFrame {
+    pm1_0: u16,
+    pm2_5: u16,
+    pm10: u16,
+    pm1_0_atm: u16,
+    pm2_5_atm: u16,
+    pm10_atm: u16,
+    beyond_0_3: u16,
+    beyond_0_5: u16,
+    beyond_1_0: u16,
+    beyond_2_5: u16,
+    beyond_5_0: u16,
+    beyond_10_0: u16,
+}
+
+#[pymethods]
+impl PyFrame {
+    fn __repr__(&self) -> String {
+        format!("{:?}", OutputFrame::from(self))
+    }
+}
+
+impl From<OutputFrame> for PyFrame {
+    fn from(frame: OutputFrame) -> Self {
+        Self {
+            pm1_0: frame.pm1_0,
+            pm2_5: frame.pm2_5,
+            pm10: frame.pm10,
+            pm1_0_atm: frame.pm1_0_atm,
+            pm2_5_atm: frame.pm2_5_atm,
+            pm10_atm: frame.pm10_atm,
+            beyond_0_3: frame.beyond_0_3,
+            beyond_0_5: frame.beyond_0_5,
+            beyond_1_0: frame.beyond_1_0,
+            beyond_2_5: frame.beyond_2_5,
+            beyond_5_0: frame.beyond_5_0,
+            beyond_10_0: frame.beyond_10_0,
+        }
+    }
+}
+
+impl From<&PyFrame> for OutputFrame {
+    fn from(frame: &PyFrame) -> Self {
+        OutputFrame::builder()
+            .pm1_0(frame.pm1_0)
+            .pm2_5(frame.pm2_5)
+            .pm10(frame.pm10)
+            .pm1_0_atm(frame.pm1_0_atm)
+            .pm2_5_atm(frame.pm2_5_atm)
+            .pm10_atm(frame.pm10_atm)
+            .beyond_0_3(frame.beyond_0_3)
+            .beyond_0_5(frame.beyond_0_5)
+            .beyond_1_0(frame.beyond_1_0)
+            .beyond_2_5(frame.beyond_2_5)
+            .beyond_5_0(frame.beyond_5_0)
+            .beyond_10_0(frame.beyond_10_0)
+            .build_with_checksum()
+    }
+}
+
+fn to_py_err(e: impl core::fmt::Debug) -> PyErr {
+    PyValueError::new_err(format!("{:?}", e))
+}
+
+/// Parses a single 32-byte frame
+#[pyfunction]
+fn parse_frame(data: &[u8]) -> PyResult<PyFrame> {
+    let buffer: &[u8; 32] = data
+        .try_into()
+        .map_err(|_| PyValueError::new_err("a frame is 32 bytes long"))?;
+    OutputFrame::from_buffer::<()>(buffer).map(PyFrame::from).map_err(to_py_err)
+}
+
+/// Decodes every frame of a raw serial capture.
+/// Corrupted frames are skipped unless `strict` is set, in which case they raise `ValueError`.
+#[pyfunction]
+#[pyo3(signature = (data, strict = false))]
+fn decode_capture(data: &[u8], strict: bool) -> PyResult<Vec<PyFrame>> {
+    let mut frames = Vec::new();
+    for frame in Replay::new(data) {
+        match frame {
+            Ok(frame) => frames.push(frame.into()),
+            Err(e) if strict => return Err(to_py_err(e)),
+            Err(_) => {}
+        }
+    }
+    Ok(frames)
+}
+
+/// AQI value with its category and pollutant
+#[pyclass(name = "Aqi", module = "pmsx003", frozen, get_all, eq)]
+#[derive(Clone, PartialEq)]
+struct PyAqi {
+    value: u16,
+    category: String,
+    pollutant: String,
+}
+
+#[pymethods]
+impl PyAqi {
+    fn __repr__(&self) -> String {
+        format!("Aqi({}, {}, {})", self.value, self.category, self.pollutant)
+    }
+}
+
+impl From<aqi::Aqi> for PyAqi {
+    fn from(aqi: aqi::Aqi) -> Self {
+        Self {
+            value: aqi.value,
+            category: format!("{:?}", aqi.category),
+            pollutant: format!("{:?}", aqi.pollutant),
+        }
+    }
+}
+
+/// AQI of a PM2.5 concentration in μg/m³
+#[pyfunction]
+fn aqi_pm2_5(concentration: f32) -> PyAqi {
+    aqi::pm2_5(concentration).into()
+}
+
+/// AQI of a PM10 concentration in μg/m³
+#[pyfunction]
+fn aqi_pm10(concentration: f32) -> PyAqi {
+    aqi::pm10(concentration).into()
+}
+
+/// AQI of a frame, from its atmospheric concentrations
+#[pyfunction(name = "aqi")]
+fn aqi_frame(frame: &PyFrame) -> PyAqi {
+    aqi::frame(&frame.into()).into()
+}
+
+/// NowCast concentration of hourly averages, oldest first. `None` marks a missing hour.
+#[pyfunction]
+fn nowcast(hours: Vec<Option<f32>>) -> Option<f32> {
+    let mut nowcast = NowCast::new();
+    let skip = hours.len().saturating_sub(NOWCAST_HOURS);
+    for hour in hours.into_iter().skip(skip) {
+        nowcast.push_hour(hour);
+    }
+    nowcast.value()
+}
+
+#[pymodule]
+#[pyo3(name = "pmsx003")]
+fn module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyFrame>()?;
+    m.add_class::<PyAqi>()?;
+    m.add_function(wrap_pyfunction!(parse_frame, m)?)?;
+    m.add_function(wrap_pyfunction!(decode_capture, m)?)?;
+    m.add_function(wrap_pyfunction!(aqi_pm2_5, m)?)?;
+    m.add_function(wrap_pyfunction!(aqi_pm10, m)?)?;
+    m.add_function(wrap_pyfunction!(aqi_frame, m)?)?;
+    m.add_function(wrap_pyfunction!(nowcast, m)?)?;
+    Ok(())
+}
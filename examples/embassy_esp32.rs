@@ -0,0 +1,57 @@
+//! Embassy example for the ESP32-C3 with read timeouts
+//!
+//! A sensor that stops answering, e.g. because its cable came loose, makes `read()` fail with
+//! `Error::Timeout` instead of stalling the task forever.
+//!
+//! Requires the `embassy` feature and the esp-hal, esp-hal-embassy, embassy-executor,
+//! esp-backtrace and esp-println dev-dependencies:
+//! `cargo run --example embassy_esp32 --features embassy --target riscv32imc-unknown-none-elf`
+
+#![cfg_attr(target_arch = "riscv32", no_std)]
+#![cfg_attr(target_arch = "riscv32", no_main)]
+
+#[cfg(target_arch = "riscv32")]
+mod app {
+    use embassy_executor::Spawner;
+    use embassy_time::{Duration, Timer};
+    use esp_backtrace as _;
+    use esp_hal::timer::timg::TimerGroup;
+    use esp_hal::uart::{Config, Uart};
+    use esp_println::println;
+    use pmsx003::asynch::PmsX003SensorAsync;
+    use pmsx003::Error;
+
+    #[esp_hal_embassy::main]
+    async fn main(_spawner: Spawner) {
+        let peripherals = esp_hal::init(esp_hal::Config::default());
+        let timg0 = TimerGroup::new(peripherals.TIMG0);
+        esp_hal_embassy::init(timg0.timer0);
+
+        // PMS5003 uses 9600 baud, 8N1
+        let uart = Uart::new(peripherals.UART1, Config::default().with_baudrate(9600))
+            .unwrap()
+            .with_tx(peripherals.GPIO21) // connects to PMS5003 RXD
+            .with_rx(peripherals.GPIO20) // connects to PMS5003 TXD
+            .into_async();
+
+        let mut sensor = PmsX003SensorAsync::new(uart);
+        sensor.set_timeout(Duration::from_secs(3));
+
+        loop {
+            match sensor.read().await {
+                Ok(frame) => println!("PM1.0: {} PM2.5: {} PM10: {} μg/m³", frame.pm1_0_atm, frame.pm2_5_atm, frame.pm10_atm),
+                Err(Error::Timeout) => {
+                    println!("Sensor not responding, waking it up");
+                    let _ = sensor.wake().await;
+                    Timer::after(Duration::from_secs(30)).await;
+                }
+                Err(e) => println!("Error reading sensor: {:?}", e),
+            }
+        }
+    }
+}
+
+#[cfg(not(target_arch = "riscv32"))]
+fn main() {
+    println!("This example only runs on the ESP32-C3");
+}
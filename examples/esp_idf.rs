@@ -0,0 +1,46 @@
+//! ESP-IDF (std) example using esp-idf-hal's UART driver
+//!
+//! Requires the esp-idf-hal and esp-idf-svc dev-dependencies and the ESP-IDF toolchain:
+//! `cargo run --example esp_idf --target xtensa-esp32-espidf`
+
+#[cfg(target_os = "espidf")]
+fn main() {
+    use esp_idf_hal::delay::FreeRtos;
+    use esp_idf_hal::gpio::AnyIOPin;
+    use esp_idf_hal::peripherals::Peripherals;
+    use esp_idf_hal::uart::{config::Config, UartDriver};
+    use esp_idf_hal::units::Hertz;
+    use pmsx003::transport::TimeoutUart;
+    use pmsx003::PmsX003Sensor;
+
+    esp_idf_svc::sys::link_patches();
+
+    let peripherals = Peripherals::take().unwrap();
+
+    // PMS5003 uses 9600 baud, 8N1
+    let config = Config::default().baudrate(Hertz(9600));
+    let uart = UartDriver::new(
+        peripherals.uart1,
+        peripherals.pins.gpio17, // TX pin - connects to PMS5003 RXD
+        peripherals.pins.gpio16, // RX pin - connects to PMS5003 TXD
+        Option::<AnyIOPin>::None,
+        Option::<AnyIOPin>::None,
+        &config,
+    )
+    .unwrap();
+
+    // UartDriver blocks forever on reads, give up after 2 seconds instead
+    let mut sensor = PmsX003Sensor::new(TimeoutUart::new(uart, FreeRtos, 2000));
+
+    loop {
+        match sensor.read() {
+            Ok(frame) => println!("{:?}", frame),
+            Err(e) => println!("Error reading sensor: {:?}", e),
+        }
+    }
+}
+
+#[cfg(not(target_os = "espidf"))]
+fn main() {
+    println!("This example only runs on ESP-IDF targets");
+}
@@ -0,0 +1,26 @@
+//! Reads the sensor in passive mode on a Raspberry Pi or any Linux host
+//!
+//! Wire the sensor's TXD to GPIO15 (RXD) and RXD to GPIO14 (TXD), and enable the UART with
+//! `raspi-config` without a login shell on it. USB-UART dongles show up as `/dev/ttyUSB0` instead.
+//!
+//! `cargo run --example linux_serial --features std -- /dev/serial0`
+
+use std::thread::sleep;
+use std::time::Duration;
+
+use pmsx003::serial::StdPms;
+
+fn main() {
+    let port = std::env::args().nth(1).unwrap_or_else(|| "/dev/serial0".into());
+    let mut sensor = StdPms::open(&port).unwrap_or_else(|e| panic!("Can't open {}: {}", port, e));
+
+    sensor.passive().expect("Switching to passive mode failed");
+
+    loop {
+        match sensor.request().and_then(|()| sensor.read()) {
+            Ok(frame) => println!("PM1.0: {} PM2.5: {} PM10: {} μg/m³", frame.pm1_0_atm, frame.pm2_5_atm, frame.pm10_atm),
+            Err(e) => eprintln!("Error reading sensor: {:?}", e),
+        }
+        sleep(Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,76 @@
+//! RTIC example feeding the parser from the UART RX interrupt
+//!
+//! The interrupt handler only pushes bytes into a [`FrameMailbox`] shared as an RTIC resource,
+//! while the idle task takes complete frames. Each lock is held for a single byte or frame.
+//!
+//! Requires rtic, cortex-m, panic-halt and stm32f4xx-hal (feature `stm32f401`)
+//! dev-dependencies: `cargo run --example rtic_stm32 --target thumbv7em-none-eabihf`
+//!
+//! [`FrameMailbox`]: pmsx003::parser::FrameMailbox
+
+#![cfg_attr(target_os = "none", no_std)]
+#![cfg_attr(target_os = "none", no_main)]
+
+#[cfg(target_os = "none")]
+#[rtic::app(device = stm32f4xx_hal::pac, dispatchers = [EXTI0])]
+mod app {
+    use panic_halt as _;
+    use pmsx003::parser::FrameMailbox;
+    use stm32f4xx_hal::pac::USART2;
+    use stm32f4xx_hal::prelude::*;
+    use stm32f4xx_hal::serial::{Config, Event, Rx, Serial};
+
+    #[shared]
+    struct Shared {
+        mailbox: FrameMailbox,
+    }
+
+    #[local]
+    struct Local {
+        rx: Rx<USART2>,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> (Shared, Local) {
+        let dp = cx.device;
+        let rcc = dp.RCC.constrain();
+        let clocks = rcc.cfgr.freeze();
+        let gpioa = dp.GPIOA.split();
+
+        // PMS5003 uses 9600 baud, 8N1. PA2 connects to the sensor's RXD, PA3 to its TXD.
+        let mut serial: Serial<USART2> = Serial::new(
+            dp.USART2,
+            (gpioa.pa2, gpioa.pa3),
+            Config::default().baudrate(9600.bps()),
+            &clocks,
+        )
+        .unwrap();
+        serial.listen(Event::RxNotEmpty);
+        let (_tx, rx) = serial.split();
+
+        (Shared { mailbox: FrameMailbox::new() }, Local { rx })
+    }
+
+    #[idle(shared = [mailbox])]
+    fn idle(mut cx: idle::Context) -> ! {
+        loop {
+            if let Some(frame) = cx.shared.mailbox.lock(|mailbox| mailbox.take()) {
+                // Hand the frame over to the application
+                let _pm2_5 = frame.pm2_5_atm;
+            }
+            cortex_m::asm::wfi();
+        }
+    }
+
+    #[task(binds = USART2, shared = [mailbox], local = [rx])]
+    fn usart2(mut cx: usart2::Context) {
+        while let Ok(byte) = cx.local.rx.read() {
+            cx.shared.mailbox.lock(|mailbox| mailbox.push(byte));
+        }
+    }
+}
+
+#[cfg(not(target_os = "none"))]
+fn main() {
+    println!("This example only runs on STM32F4 targets");
+}
@@ -0,0 +1,48 @@
+//! Prints frames received on a serial port, for bench-testing a sensor with a USB-UART dongle
+//!
+//! `cargo run --example serial_cli --features std -- /dev/ttyUSB0`
+//! `cargo run --example serial_cli --features std -- COM3 --passive`
+
+use std::process::ExitCode;
+use std::thread::sleep;
+use std::time::Duration;
+
+use pmsx003::serial::SerialPortUart;
+use pmsx003::PmsX003Sensor;
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let Some(port) = args.next() else {
+        eprintln!("Usage: serial_cli <port> [--passive]");
+        return ExitCode::FAILURE;
+    };
+    let passive = args.any(|arg| arg == "--passive");
+
+    let uart = match SerialPortUart::open(&port) {
+        Ok(uart) => uart,
+        Err(e) => {
+            eprintln!("Can't open {}: {}", port, e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut sensor = PmsX003Sensor::new(uart);
+
+    if passive && let Err(e) = sensor.passive() {
+        eprintln!("Switching to passive mode failed: {:?}", e);
+    }
+
+    loop {
+        if passive {
+            sleep(Duration::from_secs(1));
+            if let Err(e) = sensor.request() {
+                eprintln!("Request failed: {:?}", e);
+                continue;
+            }
+        }
+
+        match sensor.read() {
+            Ok(frame) => println!("{:?}", frame),
+            Err(e) => eprintln!("Error reading sensor: {:?}", e),
+        }
+    }
+}
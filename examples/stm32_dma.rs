@@ -0,0 +1,96 @@
+//! STM32F4 example receiving frames through circular DMA
+//!
+//! USART2 RX (PA3) is served by DMA1 stream 5 channel 4 into a circular buffer. The
+//! half-transfer and transfer-complete interrupts feed the buffer to `CircularDma`, so the CPU
+//! only wakes up twice per buffer instead of once per byte.
+//!
+//! Requires cortex-m, cortex-m-rt, panic-halt and stm32f4xx-hal (feature `stm32f401`)
+//! dev-dependencies: `cargo run --example stm32_dma --target thumbv7em-none-eabihf`
+
+#![cfg_attr(target_os = "none", no_std)]
+#![cfg_attr(target_os = "none", no_main)]
+
+#[cfg(target_os = "none")]
+mod app {
+    use core::cell::RefCell;
+    use core::ptr::addr_of;
+
+    use cortex_m::interrupt::Mutex;
+    use cortex_m_rt::entry;
+    use panic_halt as _;
+    use pmsx003::dma::CircularDma;
+    use pmsx003::OutputFrame;
+    use stm32f4xx_hal::pac::{self, interrupt};
+
+    // Two frames, so one half is parsed while the DMA fills the other
+    const BUFFER_SIZE: usize = 64;
+
+    static mut BUFFER: [u8; BUFFER_SIZE] = [0; BUFFER_SIZE];
+    static RECEIVER: Mutex<RefCell<CircularDma>> = Mutex::new(RefCell::new(CircularDma::new()));
+    static LATEST: Mutex<RefCell<Option<OutputFrame>>> = Mutex::new(RefCell::new(None));
+
+    #[entry]
+    fn main() -> ! {
+        let dp = pac::Peripherals::take().unwrap();
+
+        dp.RCC.ahb1enr().modify(|_, w| w.gpioaen().set_bit().dma1en().set_bit());
+        dp.RCC.apb1enr().modify(|_, w| w.usart2en().set_bit());
+
+        // PA3 as USART2 RX, alternate function 7
+        dp.GPIOA.moder().modify(|_, w| w.moder3().alternate());
+        dp.GPIOA.afrl().modify(|_, w| w.afrl3().af7());
+
+        // 9600 baud from the 16MHz HSI
+        dp.USART2.brr().write(|w| unsafe { w.bits(16_000_000 / 9600) });
+        dp.USART2.cr3().write(|w| w.dmar().set_bit());
+        dp.USART2.cr1().write(|w| w.ue().set_bit().re().set_bit());
+
+        let stream = dp.DMA1.st(5);
+        stream.par().write(|w| unsafe { w.bits(dp.USART2.dr().as_ptr() as u32) });
+        stream.m0ar().write(|w| unsafe { w.bits(addr_of!(BUFFER) as u32) });
+        stream.ndtr().write(|w| unsafe { w.bits(BUFFER_SIZE as u32) });
+        stream.cr().write(|w| unsafe {
+            w.chsel().bits(4).minc().set_bit().circ().set_bit().htie().set_bit().tcie().set_bit().en().set_bit()
+        });
+
+        unsafe { cortex_m::peripheral::NVIC::unmask(pac::Interrupt::DMA1_STREAM5) };
+
+        loop {
+            cortex_m::asm::wfi();
+            if let Some(frame) = cortex_m::interrupt::free(|cs| LATEST.borrow(cs).take()) {
+                // Hand the frame over to the application
+                let _pm2_5 = frame.pm2_5_atm;
+            }
+        }
+    }
+
+    #[interrupt]
+    fn DMA1_STREAM5() {
+        let dma = unsafe { &*pac::DMA1::ptr() };
+        let flags = dma.hisr().read();
+        dma.hifcr().write(|w| w.chtif5().set_bit().ctcif5().set_bit());
+
+        // SAFETY: the DMA is writing the other half of the buffer
+        let buffer = unsafe { &*addr_of!(BUFFER) };
+
+        cortex_m::interrupt::free(|cs| {
+            let mut receiver = RECEIVER.borrow(cs).borrow_mut();
+            let on_frame = |frame| {
+                if let Ok(frame) = frame {
+                    LATEST.borrow(cs).replace(Some(frame));
+                }
+            };
+
+            if flags.htif5().bit_is_set() {
+                receiver.on_half_transfer(buffer, on_frame);
+            } else if flags.tcif5().bit_is_set() {
+                receiver.on_transfer_complete(buffer, on_frame);
+            }
+        });
+    }
+}
+
+#[cfg(not(target_os = "none"))]
+fn main() {
+    println!("This example only runs on STM32F4 targets");
+}
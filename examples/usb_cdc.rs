@@ -0,0 +1,80 @@
+//! Raspberry Pi Pico example reading the sensor over a USB CDC-ACM port
+//!
+//! The Pico enumerates as a usbd-serial port and the sensor's bytes arrive through it, e.g.
+//! relayed by a USB-UART bridge on the host. USB only moves data while the device is polled and
+//! delivers it in packet-sized chunks, so the port is wrapped in `PolledUart`, which polls until
+//! data is ready and lets the driver reassemble frames.
+//!
+//! Requires cortex-m-rt, panic-halt, rp2040-hal, rp2040-boot2, usb-device and usbd-serial
+//! dev-dependencies: `cargo run --example usb_cdc --target thumbv6m-none-eabi`
+
+#![cfg_attr(target_os = "none", no_std)]
+#![cfg_attr(target_os = "none", no_main)]
+
+#[cfg(target_os = "none")]
+mod app {
+    use cortex_m_rt::entry;
+    use panic_halt as _;
+    use pmsx003::transport::PolledUart;
+    use pmsx003::PmsX003Sensor;
+    use rp2040_hal::clocks::init_clocks_and_plls;
+    use rp2040_hal::{pac, usb::UsbBus, Watchdog};
+    use usb_device::class_prelude::UsbBusAllocator;
+    use usb_device::prelude::*;
+    use usbd_serial::SerialPort;
+
+    #[unsafe(link_section = ".boot2")]
+    #[used]
+    static BOOT2: [u8; 256] = rp2040_boot2::BOOT_LOADER_GENERIC_03H;
+
+    const XTAL_FREQ_HZ: u32 = 12_000_000;
+
+    #[entry]
+    fn main() -> ! {
+        let mut pac = pac::Peripherals::take().unwrap();
+        let mut watchdog = Watchdog::new(pac.WATCHDOG);
+        let clocks = init_clocks_and_plls(
+            XTAL_FREQ_HZ,
+            pac.XOSC,
+            pac.CLOCKS,
+            pac.PLL_SYS,
+            pac.PLL_USB,
+            &mut pac.RESETS,
+            &mut watchdog,
+        )
+        .ok()
+        .unwrap();
+
+        let usb_bus = UsbBusAllocator::new(UsbBus::new(
+            pac.USBCTRL_REGS,
+            pac.USBCTRL_DPRAM,
+            clocks.usb_clock,
+            true,
+            &mut pac.RESETS,
+        ));
+        let serial = SerialPort::new(&usb_bus);
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_bus, UsbVidPid(0x16c0, 0x27dd))
+            .strings(&[StringDescriptors::default().product("PMS logger")])
+            .unwrap()
+            .device_class(usbd_serial::USB_CLASS_CDC)
+            .build();
+
+        // Reads keep the USB stack serviced while they wait for the next chunk
+        let uart = PolledUart::new(serial, |serial: &mut SerialPort<_>| {
+            usb_dev.poll(&mut [serial]);
+        });
+        let mut sensor = PmsX003Sensor::new(uart);
+
+        loop {
+            if let Ok(frame) = sensor.read() {
+                // Hand the frame over to the application
+                let _pm2_5 = frame.pm2_5_atm;
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "none"))]
+fn main() {
+    println!("This example only runs on the RP2040");
+}
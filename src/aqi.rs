@@ -0,0 +1,172 @@
+//! US EPA Air Quality Index computation
+//!
+//! Breakpoints follow the 2024 revision of the EPA AQI for PM2.5 and the
+//! unchanged PM10 table. NowCast follows the EPA method for particulate matter.
+
+use core::cmp::Ordering;
+
+use crate::OutputFrame;
+
+/// Pollutant an index value was computed for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Pollutant {
+    Pm2_5,
+    Pm10,
+}
+
+/// Health category of an AQI value
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AqiCategory {
+    Good,
+    Moderate,
+    UnhealthyForSensitiveGroups,
+    Unhealthy,
+    VeryUnhealthy,
+    Hazardous,
+}
+
+/// AQI value together with its category and the pollutant it was derived from.
+/// Ordered by health severity, so the greater value is the worse air quality.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Aqi {
+    pub value: u16,
+    pub category: AqiCategory,
+    pub pollutant: Pollutant,
+}
+
+struct Breakpoint {
+    c_lo: f32,
+    c_hi: f32,
+    i_lo: u16,
+    i_hi: u16,
+    category: AqiCategory,
+}
+
+const fn bp(c_lo: f32, c_hi: f32, i_lo: u16, i_hi: u16, category: AqiCategory) -> Breakpoint {
+    Breakpoint { c_lo, c_hi, i_lo, i_hi, category }
+}
+
+const PM2_5_BREAKPOINTS: [Breakpoint; 6] = [
+    bp(0.0, 9.0, 0, 50, AqiCategory::Good),
+    bp(9.1, 35.4, 51, 100, AqiCategory::Moderate),
+    bp(35.5, 55.4, 101, 150, AqiCategory::UnhealthyForSensitiveGroups),
+    bp(55.5, 125.4, 151, 200, AqiCategory::Unhealthy),
+    bp(125.5, 225.4, 201, 300, AqiCategory::VeryUnhealthy),
+    bp(225.5, 325.4, 301, 500, AqiCategory::Hazardous),
+];
+
+const PM10_BREAKPOINTS: [Breakpoint; 6] = [
+    bp(0.0, 54.0, 0, 50, AqiCategory::Good),
+    bp(55.0, 154.0, 51, 100, AqiCategory::Moderate),
+    bp(155.0, 254.0, 101, 150, AqiCategory::UnhealthyForSensitiveGroups),
+    bp(255.0, 354.0, 151, 200, AqiCategory::Unhealthy),
+    bp(355.0, 424.0, 201, 300, AqiCategory::VeryUnhealthy),
+    bp(425.0, 604.0, 301, 500, AqiCategory::Hazardous),
+];
+
+/// Computes the AQI for a PM2.5 concentration in μg/m³
+pub fn pm2_5(concentration: f32) -> Aqi {
+    // EPA truncates PM2.5 to one decimal place
+    let c = truncate(concentration * 10.0) / 10.0;
+    index(c, &PM2_5_BREAKPOINTS, Pollutant::Pm2_5)
+}
+
+/// Computes the AQI for a PM10 concentration in μg/m³
+pub fn pm10(concentration: f32) -> Aqi {
+    // EPA truncates PM10 to an integer
+    let c = truncate(concentration);
+    index(c, &PM10_BREAKPOINTS, Pollutant::Pm10)
+}
+
+/// Returns the index of the pollutant with the worse air quality
+pub fn worst(a: Aqi, b: Aqi) -> Aqi {
+    if b.value > a.value { b } else { a }
+}
+
+/// Computes the AQI of a frame from its atmospheric concentrations, the worst pollutant wins
+pub fn frame(frame: &OutputFrame) -> Aqi {
+    worst(pm2_5(frame.pm2_5_atm as f32), pm10(frame.pm10_atm as f32))
+}
+
+/// Compares two frames by health severity
+pub fn cmp_severity(a: &OutputFrame, b: &OutputFrame) -> Ordering {
+    frame(a).cmp(&frame(b))
+}
+
+/// Returns the frame with the worst air quality, e.g. among recent readings
+pub fn max_severity(frames: &[OutputFrame]) -> Option<&OutputFrame> {
+    frames.iter().max_by(|a, b| cmp_severity(a, b))
+}
+
+fn truncate(value: f32) -> f32 {
+    if value <= 0.0 { 0.0 } else { value as u32 as f32 }
+}
+
+fn index(c: f32, breakpoints: &[Breakpoint], pollutant: Pollutant) -> Aqi {
+    let bp = breakpoints
+        .iter()
+        .find(|bp| c <= bp.c_hi)
+        .unwrap_or(&breakpoints[breakpoints.len() - 1]);
+
+    // Values beyond the table are clamped to the top of the scale
+    let c = c.clamp(bp.c_lo, bp.c_hi);
+
+    let value = (bp.i_hi - bp.i_lo) as f32 / (bp.c_hi - bp.c_lo) * (c - bp.c_lo) + bp.i_lo as f32;
+
+    Aqi {
+        value: (value + 0.5) as u16,
+        category: bp.category,
+        pollutant,
+    }
+}
+
+/// Number of hourly averages used by NowCast
+pub const NOWCAST_HOURS: usize = 12;
+
+/// EPA NowCast over the last 12 hourly averages
+#[derive(Clone, Debug, Default)]
+pub struct NowCast {
+    // Most recent hour first
+    hours: [Option<f32>; NOWCAST_HOURS],
+}
+
+impl NowCast {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pushes the average concentration of the hour that just ended
+    pub fn push_hour(&mut self, average: Option<f32>) {
+        self.hours.copy_within(0..NOWCAST_HOURS - 1, 1);
+        self.hours[0] = average;
+    }
+
+    /// NowCast concentration. Requires at least two of the three most recent hours.
+    pub fn value(&self) -> Option<f32> {
+        if self.hours[..3].iter().filter(|h| h.is_some()).count() < 2 {
+            return None;
+        }
+
+        let (min, max) = self
+            .hours
+            .iter()
+            .flatten()
+            .fold((f32::MAX, f32::MIN), |(min, max), c| (min.min(*c), max.max(*c)));
+
+        let weight = if max > 0.0 { min / max } else { 1.0 };
+        let weight = weight.max(0.5);
+
+        let mut factor = 1.0;
+        let mut sum = 0.0;
+        let mut weights = 0.0;
+        for hour in self.hours.iter() {
+            if let Some(c) = hour {
+                sum += factor * c;
+                weights += factor;
+            }
+            factor *= weight;
+        }
+
+        Some(sum / weights)
+    }
+}
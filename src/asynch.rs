@@ -0,0 +1,556 @@
+//! Async support built on embedded-io-async
+
+use core::future::Future;
+
+use embassy_futures::join::join_array;
+use embassy_futures::select::{select, Either};
+use embassy_sync::blocking_mutex::raw::RawMutex;
+use embassy_sync::channel::Channel;
+use embassy_sync::mutex::Mutex;
+use embassy_sync::signal::Signal;
+use embedded_hal_async::delay::DelayNs;
+#[cfg(feature = "pmsa003i")]
+use embedded_hal_async::i2c::I2c;
+use embedded_io_async::{Read, ReadExactError, Write};
+#[cfg(feature = "embassy")]
+use embassy_time::{with_timeout, Duration};
+use futures_core::Stream;
+
+use crate::codec::{PlantowerCodec, MAX_FRAME_SIZE};
+use crate::protocol;
+use crate::{Command, Error, OutputFrame, Packet, ResponseFrame, SyncStats, ACK_FRAME_BUDGET, COMMAND_QUEUE_SIZE};
+#[cfg(feature = "pmsa003i")]
+use crate::OUTPUT_FRAME_SIZE;
+
+const DEFAULT_RESPONSE_TIMEOUT_MS: u32 = 1000;
+// Timeout of each sensor of a `SensorArray`, long enough for a frame in active mode
+const DEFAULT_READ_TIMEOUT_MS: u32 = 3000;
+
+/// Default timeout of [`PmsX003SensorAsync`]. Active mode reports about every second,
+/// so a longer silence means the sensor is asleep, in passive mode or disconnected.
+#[cfg(feature = "embassy")]
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Reads the next packet, using the frame length field to tell data frames from responses.
+/// Frames with an unknown length are skipped.
+async fn read_packet<R: Read>(rx: &mut R, sync: &mut SyncStats) -> Result<Packet, Error<R::Error>> {
+    protocol::read_packet!(PlantowerCodec, *sync, |buf| rx.read_exact(buf).await)
+}
+
+/// Bytes of the packet being received, kept when a read is cancelled so the next one
+/// resumes the packet instead of losing it
+struct PartialPacket {
+    bytes: [u8; MAX_FRAME_SIZE],
+    len: usize,
+    // Bytes handed to the reader since the last rewind
+    pos: usize,
+}
+
+impl PartialPacket {
+    const fn new() -> Self {
+        Self {
+            bytes: [0; MAX_FRAME_SIZE],
+            len: 0,
+            pos: 0,
+        }
+    }
+
+    /// Fills `buf` with the kept bytes not handed out yet, then reads the rest from `rx`,
+    /// keeping it too. Relies on `rx.read()` being cancel-safe, as embedded-io-async asks of it.
+    async fn read_exact<R: Read>(&mut self, rx: &mut R, buf: &mut [u8]) -> Result<(), ReadExactError<R::Error>> {
+        let mut filled = buf.len().min(self.len - self.pos);
+        buf[..filled].copy_from_slice(&self.bytes[self.pos..self.pos + filled]);
+        self.pos += filled;
+
+        while filled < buf.len() {
+            let n = rx.read(&mut buf[filled..]).await.map_err(ReadExactError::Other)?;
+            if n == 0 {
+                return Err(ReadExactError::UnexpectedEof);
+            }
+            self.bytes[self.len..self.len + n].copy_from_slice(&buf[filled..filled + n]);
+            self.len += n;
+            self.pos = self.len;
+            filled += n;
+        }
+        Ok(())
+    }
+
+    /// Drops the bytes handed out but the last `held` ones, the others belonging to packets
+    /// that were dropped
+    fn consume(&mut self, held: usize) {
+        let start = self.pos - held;
+        self.bytes.copy_within(start..self.len, 0);
+        self.len -= start;
+        self.pos = held;
+    }
+
+    /// Hands out the kept bytes again, starting with the first one
+    fn rewind(&mut self) {
+        self.pos = 0;
+    }
+
+    fn clear(&mut self) {
+        self.len = 0;
+        self.pos = 0;
+    }
+}
+
+/// Async sensor interface, the counterpart of [`PmsX003Sensor`](crate::PmsX003Sensor)
+/// for executors such as Embassy. See [`split`] to read and send commands from separate tasks.
+///
+/// Reads are cancel-safe: a read dropped mid-frame, e.g. by `select` with a timeout,
+/// leaves the bytes received so far in the driver and the next read completes the frame.
+pub struct PmsX003SensorAsync<UART> {
+    uart: UART,
+    sync: SyncStats,
+    partial: PartialPacket,
+    faults_as_errors: bool,
+    #[cfg(feature = "embassy")]
+    timeout: Duration,
+}
+
+impl<UART: Read + Write> PmsX003SensorAsync<UART> {
+    /// Creates a new sensor instance
+    /// * `uart` - UART implementing embedded-io-async Read + Write traits
+    pub fn new(uart: UART) -> Self {
+        Self {
+            uart,
+            sync: SyncStats::default(),
+            partial: PartialPacket::new(),
+            faults_as_errors: false,
+            #[cfg(feature = "embassy")]
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Sets whether `read()` fails with `Error::SensorFault` on frames reporting a failure, rather
+    /// than leaving it to `OutputFrame::fault()`. Defaults to false.
+    pub fn set_faults_as_errors(&mut self, enabled: bool) {
+        self.faults_as_errors = enabled;
+    }
+
+    /// Sets how long reads and command exchanges wait for the sensor before failing with
+    /// `Error::Timeout`. Defaults to `DEFAULT_TIMEOUT`.
+    #[cfg(feature = "embassy")]
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Resync statistics of the last read or command
+    pub fn sync_stats(&self) -> SyncStats {
+        self.sync
+    }
+
+    /// Reads sensor status. Waits until status is available, skipping command responses.
+    pub async fn read(&mut self) -> Result<OutputFrame, Error<UART::Error>> {
+        #[cfg(feature = "embassy")]
+        return with_timeout(self.timeout, self.read_frame()).await.unwrap_or(Err(Error::Timeout));
+        #[cfg(not(feature = "embassy"))]
+        self.read_frame().await
+    }
+
+    async fn read_frame(&mut self) -> Result<OutputFrame, Error<UART::Error>> {
+        self.sync = SyncStats::default();
+        loop {
+            if let Packet::Frame(frame) = self.read_packet().await? {
+                return match frame.fault() {
+                    Some(faults) if self.faults_as_errors => Err(Error::SensorFault(faults)),
+                    _ => Ok(frame),
+                };
+            }
+        }
+    }
+
+    /// Reads the next packet, resuming the one a cancelled read left off
+    async fn read_packet(&mut self) -> Result<Packet, Error<UART::Error>> {
+        let partial = &mut self.partial;
+        let uart = &mut self.uart;
+        partial.rewind();
+        let result = async {
+            protocol::read_packet!(PlantowerCodec, self.sync, |buf| partial.read_exact(uart, buf).await, |held| partial.consume(held))
+        }
+        .await;
+        self.partial.clear();
+        result
+    }
+
+    /// Frames reported in active mode as an endless stream. Pin it before polling, e.g. with `pin!`.
+    pub fn frames(&mut self) -> impl Stream<Item = Result<OutputFrame, Error<UART::Error>>> + '_ {
+        futures_util::stream::unfold(self, |sensor| async move { Some((sensor.read().await, sensor)) })
+    }
+
+    /// Sleep mode. Data frames received before the response are skipped.
+    pub async fn sleep(&mut self) -> Result<(), Error<UART::Error>> {
+        self.execute(Command::Sleep).await
+    }
+
+    pub async fn wake(&mut self) -> Result<(), Error<UART::Error>> {
+        self.execute(Command::Wake).await
+    }
+
+    /// Passive mode - sensor reports air quality on request
+    #[cfg(not(feature = "active-only"))]
+    pub async fn passive(&mut self) -> Result<(), Error<UART::Error>> {
+        self.execute(Command::SetPassive).await
+    }
+
+    /// Active mode - sensor reports air quality continuously
+    #[cfg(not(feature = "passive-only"))]
+    pub async fn active(&mut self) -> Result<(), Error<UART::Error>> {
+        self.execute(Command::SetActive).await
+    }
+
+    /// Requests status in passive mode
+    #[cfg(not(feature = "active-only"))]
+    pub async fn request(&mut self) -> Result<(), Error<UART::Error>> {
+        self.execute(Command::Request).await
+    }
+
+    /// Requests status in passive mode and waits for the resulting frame,
+    /// skipping responses to earlier commands
+    #[cfg(not(feature = "active-only"))]
+    pub async fn request_and_read(&mut self) -> Result<OutputFrame, Error<UART::Error>> {
+        #[cfg(feature = "embassy")]
+        return with_timeout(self.timeout, self.request_frame()).await.unwrap_or(Err(Error::Timeout));
+        #[cfg(not(feature = "embassy"))]
+        self.request_frame().await
+    }
+
+    #[cfg(not(feature = "active-only"))]
+    async fn request_frame(&mut self) -> Result<OutputFrame, Error<UART::Error>> {
+        self.exchange(Command::Request).await?;
+        self.read_frame().await
+    }
+
+    /// Returns the wrapped UART
+    pub fn release(self) -> UART {
+        self.uart
+    }
+
+    async fn execute(&mut self, command: Command) -> Result<(), Error<UART::Error>> {
+        #[cfg(feature = "embassy")]
+        return with_timeout(self.timeout, self.exchange(command)).await.unwrap_or(Err(Error::Timeout));
+        #[cfg(not(feature = "embassy"))]
+        self.exchange(command).await
+    }
+
+    async fn exchange(&mut self, command: Command) -> Result<(), Error<UART::Error>> {
+        self.uart.write_all(&command.encode()).await.map_err(Error::Write)?;
+        if !command.expects_response() {
+            return Ok(());
+        }
+
+        self.sync = SyncStats::default();
+        let response = protocol::wait_for_ack!(
+            command.encode()[2],
+            ACK_FRAME_BUDGET,
+            self.read_packet().await
+        )?;
+        if response.acknowledges(command) {
+            Ok(())
+        } else {
+            Err(Error::IncorrectResponse(Some(response)))
+        }
+    }
+}
+
+/// State shared between a [`CommandHandle`] and its [`Runner`].
+/// Usually placed in a `static` so both halves can be moved into separate tasks.
+/// * `E` - error type of the serial halves
+pub struct SplitState<M: RawMutex, E> {
+    // Serializes commands issued from several handles, guarding the sequence number of the last one
+    lock: Mutex<M, u32>,
+    // Commands tagged with their sequence number, so results of abandoned ones aren't taken
+    // for the outcome of later ones
+    commands: Channel<M, (u32, Command), COMMAND_QUEUE_SIZE>,
+    results: Signal<M, (u32, Result<(), Error<E>>)>,
+    responses: Signal<M, ResponseFrame>,
+    frames: Signal<M, OutputFrame>,
+}
+
+impl<M: RawMutex, E> SplitState<M, E> {
+    pub const fn new() -> Self {
+        Self {
+            lock: Mutex::new(0),
+            commands: Channel::new(),
+            results: Signal::new(),
+            responses: Signal::new(),
+            frames: Signal::new(),
+        }
+    }
+}
+
+impl<M: RawMutex, E> Default for SplitState<M, E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Splits the sensor into a cheap command handle and a runner owning the serial halves.
+/// The runner must be driven by a background task for the handle to make progress.
+/// * `rx` - UART receiver implementing embedded-io-async Read
+/// * `tx` - UART transmitter implementing embedded-io-async Write
+/// * `delay` - delay used to time out command responses
+/// * `state` - storage shared by both halves
+pub fn split<'a, M, E, RX, TX, D>(
+    rx: RX,
+    tx: TX,
+    delay: D,
+    state: &'a SplitState<M, E>,
+) -> (CommandHandle<'a, M, E>, Runner<'a, M, RX, TX, D>)
+where
+    M: RawMutex,
+    RX: Read<Error = E>,
+    TX: Write<Error = E>,
+    D: DelayNs,
+{
+    let runner = Runner {
+        rx,
+        tx,
+        delay,
+        response_timeout_ms: DEFAULT_RESPONSE_TIMEOUT_MS,
+        state,
+    };
+    (CommandHandle { state }, runner)
+}
+
+/// Handle used by application tasks to control the sensor and receive frames
+pub struct CommandHandle<'a, M: RawMutex, E> {
+    state: &'a SplitState<M, E>,
+}
+
+impl<M: RawMutex, E> Clone for CommandHandle<'_, M, E> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M: RawMutex, E> Copy for CommandHandle<'_, M, E> {}
+
+impl<M: RawMutex, E> CommandHandle<'_, M, E> {
+    /// Queues a command and waits for the runner to report its outcome. Dropping the future
+    /// midway leaves the command to the runner, whose result is then ignored.
+    async fn execute(&self, command: Command) -> Result<(), Error<E>> {
+        let mut sequence = self.state.lock.lock().await;
+        *sequence = sequence.wrapping_add(1);
+        self.state.commands.send((*sequence, command)).await;
+        loop {
+            let (done, result) = self.state.results.wait().await;
+            if done == *sequence {
+                return result;
+            }
+        }
+    }
+
+    /// Sleep mode. Data frames received before the response are still delivered to readers.
+    pub async fn sleep(&self) -> Result<(), Error<E>> {
+        self.execute(Command::Sleep).await
+    }
+
+    /// Wakes the sensor. The sensor doesn't acknowledge this command.
+    pub async fn wake(&self) -> Result<(), Error<E>> {
+        self.execute(Command::Wake).await
+    }
+
+    /// Passive mode - sensor reports air quality on request
+    #[cfg(not(feature = "active-only"))]
+    pub async fn passive(&self) -> Result<(), Error<E>> {
+        self.execute(Command::SetPassive).await
+    }
+
+    /// Active mode - sensor reports air quality continuously
+    #[cfg(not(feature = "passive-only"))]
+    pub async fn active(&self) -> Result<(), Error<E>> {
+        self.execute(Command::SetActive).await
+    }
+
+    /// Requests status in passive mode
+    #[cfg(not(feature = "active-only"))]
+    pub async fn request(&self) -> Result<(), Error<E>> {
+        self.execute(Command::Request).await
+    }
+
+    /// Waits for the next frame received by the runner
+    pub async fn read(&self) -> OutputFrame {
+        self.state.frames.wait().await
+    }
+
+    /// Requests status in passive mode and waits for the resulting frame
+    #[cfg(not(feature = "active-only"))]
+    pub async fn request_read(&self) -> Result<OutputFrame, Error<E>> {
+        self.state.frames.reset();
+        self.request().await?;
+        Ok(self.read().await)
+    }
+}
+
+/// Group of sensors read concurrently, e.g. one sensor per room
+pub struct SensorArray<'a, M: RawMutex, E, D, const N: usize> {
+    sensors: [CommandHandle<'a, M, E>; N],
+    delay: D,
+    timeout_ms: u32,
+}
+
+impl<'a, M: RawMutex, E, D: DelayNs, const N: usize> SensorArray<'a, M, E, D, N> {
+    /// Creates a new array from the handles of split sensors, each driven by its own runner
+    /// * `delay` - delay used to time out sensors that don't report
+    pub fn new(sensors: [CommandHandle<'a, M, E>; N], delay: D) -> Self {
+        Self {
+            sensors,
+            delay,
+            timeout_ms: DEFAULT_READ_TIMEOUT_MS,
+        }
+    }
+
+    /// Sets how long to wait for the frame of each sensor before reporting `Error::Timeout` for it.
+    /// Defaults to 3 seconds, as active mode reports about every second.
+    pub fn set_timeout(&mut self, timeout_ms: u32) {
+        self.timeout_ms = timeout_ms;
+    }
+
+    /// Waits for the next frame of every sensor in active mode, all sensors at once.
+    /// Returns one result per sensor, in the order given to [`SensorArray::new`].
+    pub async fn read_all(&mut self) -> [Result<OutputFrame, Error<E>>; N] {
+        self.join_with_timeout(|sensor| async move { Ok(sensor.read().await) }).await
+    }
+
+    /// Requests and waits for a frame from every sensor in passive mode, all sensors at once.
+    /// Returns one result per sensor, in the order given to [`SensorArray::new`].
+    #[cfg(not(feature = "active-only"))]
+    pub async fn request_read_all(&mut self) -> [Result<OutputFrame, Error<E>>; N] {
+        self.join_with_timeout(|sensor| async move { sensor.request_read().await }).await
+    }
+
+    /// Handle of the sensor at `index`
+    pub fn sensor(&self, index: usize) -> Option<&CommandHandle<'a, M, E>> {
+        self.sensors.get(index)
+    }
+
+    /// Runs `read` for every sensor at once, keeping the results of those done in time
+    async fn join_with_timeout<F, Fut>(&mut self, mut read: F) -> [Result<OutputFrame, Error<E>>; N]
+    where
+        F: FnMut(CommandHandle<'a, M, E>) -> Fut,
+        Fut: Future<Output = Result<OutputFrame, Error<E>>>,
+    {
+        let mut results: [Option<Result<OutputFrame, Error<E>>>; N] = [const { None }; N];
+        let mut slots = results.iter_mut();
+        let reads = self.sensors.map(|sensor| {
+            let slot = slots.next().unwrap();
+            let read = read(sensor);
+            async move { *slot = Some(read.await) }
+        });
+        select(join_array(reads), self.delay.delay_ms(self.timeout_ms)).await;
+        results.map(|result| result.unwrap_or(Err(Error::Timeout)))
+    }
+}
+
+/// Background half of a split sensor, owning the serial halves
+pub struct Runner<'a, M: RawMutex, RX: Read, TX, D> {
+    rx: RX,
+    tx: TX,
+    delay: D,
+    response_timeout_ms: u32,
+    state: &'a SplitState<M, RX::Error>,
+}
+
+impl<M, RX, TX, D> Runner<'_, M, RX, TX, D>
+where
+    M: RawMutex,
+    RX: Read,
+    TX: Write<Error = RX::Error>,
+    D: DelayNs,
+{
+    /// Sets how long to wait for a command response before reporting `Error::NoResponse`
+    pub fn set_response_timeout(&mut self, timeout_ms: u32) {
+        self.response_timeout_ms = timeout_ms;
+    }
+
+    /// Receives frames and transmits queued commands. Only returns on a receive error,
+    /// transmit errors are reported to the command issuer.
+    pub async fn run(&mut self) -> Error<RX::Error> {
+        let state = self.state;
+        let rx = &mut self.rx;
+        let tx = &mut self.tx;
+        let delay = &mut self.delay;
+        let timeout_ms = self.response_timeout_ms;
+
+        let receive = async {
+            let mut sync = SyncStats::default();
+            loop {
+                match read_packet(rx, &mut sync).await {
+                    Ok(Packet::Frame(frame)) => state.frames.signal(frame),
+                    Ok(Packet::Response(response)) => state.responses.signal(response),
+                    // Corrupted frames are dropped, the next one will resync
+                    Err(Error::ChecksumError) => {}
+                    Err(e) => return e,
+                }
+            }
+        };
+
+        let transmit = async {
+            loop {
+                let (sequence, command) = state.commands.receive().await;
+
+                // Drain responses to earlier commands so they can't acknowledge this one
+                state.responses.reset();
+
+                let result = match tx.write_all(&command.encode()).await {
+                    Err(e) => Err(Error::Write(e)),
+                    Ok(()) if !command.expects_response() => Ok(()),
+                    Ok(()) => match select(state.responses.wait(), delay.delay_ms(timeout_ms)).await {
+                        Either::First(response) if response.acknowledges(command) => Ok(()),
+                        Either::First(response) => Err(Error::IncorrectResponse(Some(response))),
+                        Either::Second(()) => Err(Error::NoResponse),
+                    },
+                };
+                state.results.signal((sequence, result));
+            }
+        };
+
+        match select(receive, transmit).await {
+            Either::First(e) => e,
+            Either::Second(never) => never,
+        }
+    }
+}
+
+#[cfg(feature = "pmsa003i")]
+pub use crate::i2c::PMSA003I_ADDRESS;
+
+/// Async driver for the PMSA003I, which reports the usual 32-byte frame over I2C.
+/// See [`I2cPmsA003i`](crate::i2c::I2cPmsA003i) for the blocking one.
+#[cfg(feature = "pmsa003i")]
+pub struct I2cPmsA003iAsync<I2C> {
+    i2c: I2C,
+    address: u8,
+}
+
+#[cfg(feature = "pmsa003i")]
+impl<I2C: I2c> I2cPmsA003iAsync<I2C> {
+    /// Creates a new sensor instance using the default address
+    /// * `i2c` - I2C bus implementing embedded-hal-async I2c trait
+    pub fn new(i2c: I2C) -> Self {
+        Self::new_with_address(i2c, PMSA003I_ADDRESS)
+    }
+
+    /// Creates a new sensor instance at a custom address
+    pub fn new_with_address(i2c: I2C, address: u8) -> Self {
+        Self { i2c, address }
+    }
+
+    /// Reads the latest frame. Bus errors are reported as `Error::Read`.
+    pub async fn read(&mut self) -> Result<OutputFrame, Error<I2C::Error>> {
+        let mut buffer = [0u8; OUTPUT_FRAME_SIZE];
+        self.i2c
+            .read(self.address, &mut buffer)
+            .await
+            .map_err(|e| Error::Read(ReadExactError::Other(e)))?;
+
+        OutputFrame::from_buffer(&buffer)
+    }
+
+    /// Returns the wrapped bus
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+}
@@ -0,0 +1,221 @@
+//! I2C/SPI-to-UART bridges, for MCUs out of UARTs
+
+use embedded_hal::i2c::I2c;
+use embedded_hal::spi::{Operation, SpiDevice};
+use embedded_io::{ErrorKind, ErrorType, Read, ReadReady, Write};
+
+/// Baud rate the bridge is configured for
+pub const BAUD_RATE: u32 = 9600;
+
+// SC16IS75x registers
+const RHR_THR: u8 = 0x00;
+const DLL: u8 = 0x00;
+const DLH: u8 = 0x01;
+const FCR: u8 = 0x02;
+const LCR: u8 = 0x03;
+const TXLVL: u8 = 0x08;
+const RXLVL: u8 = 0x09;
+
+const LCR_DIVISOR_LATCH: u8 = 0x80;
+const LCR_8N1: u8 = 0x03;
+// Enables and resets both FIFOs
+const FCR_FIFO_RESET: u8 = 0x07;
+
+// Bytes moved per bus transaction, bounded by the 64-byte FIFOs
+const BURST_SIZE: usize = 16;
+// TXLVL reports free space, so it reads this when the transmit FIFO is empty
+const FIFO_SIZE: usize = 64;
+
+/// Error of a [`Sc16is75x`] bridge, wrapping the bus error
+#[derive(Debug)]
+pub struct BridgeError<E>(pub E);
+
+impl<E: core::fmt::Debug> embedded_io::Error for BridgeError<E> {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+/// Register access of a bridge chip over a particular bus
+pub trait BridgeBus {
+    type Error: core::fmt::Debug;
+
+    /// Writes `data` to register `reg` of `channel`. FIFO registers take several bytes, possibly
+    /// over several transactions.
+    fn write_register(&mut self, channel: u8, reg: u8, data: &[u8]) -> Result<(), Self::Error>;
+
+    /// Reads `buf.len()` bytes from register `reg` of `channel`
+    fn read_register(&mut self, channel: u8, reg: u8, buf: &mut [u8]) -> Result<(), Self::Error>;
+}
+
+/// Bridge attached over I2C
+pub struct I2cBridgeBus<I2C> {
+    i2c: I2C,
+    address: u8,
+}
+
+impl<I2C> I2cBridgeBus<I2C> {
+    /// Returns the wrapped bus
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+}
+
+impl<I2C: I2c> BridgeBus for I2cBridgeBus<I2C> {
+    type Error = I2C::Error;
+
+    /// Writes at most `BURST_SIZE` bytes per transaction
+    fn write_register(&mut self, channel: u8, reg: u8, data: &[u8]) -> Result<(), Self::Error> {
+        let mut buffer = [0u8; BURST_SIZE + 1];
+        buffer[0] = subaddress(channel, reg);
+        for chunk in data.chunks(BURST_SIZE) {
+            buffer[1..=chunk.len()].copy_from_slice(chunk);
+            self.i2c.write(self.address, &buffer[..=chunk.len()])?;
+        }
+        Ok(())
+    }
+
+    fn read_register(&mut self, channel: u8, reg: u8, buf: &mut [u8]) -> Result<(), Self::Error> {
+        self.i2c.write_read(self.address, &[subaddress(channel, reg)], buf)
+    }
+}
+
+/// Bridge attached over SPI
+pub struct SpiBridgeBus<SPI> {
+    spi: SPI,
+}
+
+impl<SPI> SpiBridgeBus<SPI> {
+    /// Returns the wrapped device
+    pub fn release(self) -> SPI {
+        self.spi
+    }
+}
+
+impl<SPI: SpiDevice> BridgeBus for SpiBridgeBus<SPI> {
+    type Error = SPI::Error;
+
+    fn write_register(&mut self, channel: u8, reg: u8, data: &[u8]) -> Result<(), Self::Error> {
+        self.spi
+            .transaction(&mut [Operation::Write(&[subaddress(channel, reg)]), Operation::Write(data)])
+    }
+
+    fn read_register(&mut self, channel: u8, reg: u8, buf: &mut [u8]) -> Result<(), Self::Error> {
+        // The top bit selects a read
+        self.spi
+            .transaction(&mut [Operation::Write(&[0x80 | subaddress(channel, reg)]), Operation::Read(buf)])
+    }
+}
+
+fn subaddress(channel: u8, reg: u8) -> u8 {
+    (reg << 3) | (channel << 1)
+}
+
+/// One channel of an SC16IS740/750/752/760/762 bridge, implementing embedded-io so it can be
+/// passed to [`PmsX003Sensor::new`](crate::PmsX003Sensor::new). Wrap it in a
+/// [`TimeoutUart`](crate::transport::TimeoutUart) to give up on a silent sensor.
+pub struct Sc16is75x<BUS> {
+    bus: BUS,
+    channel: u8,
+}
+
+impl<I2C: I2c> Sc16is75x<I2cBridgeBus<I2C>> {
+    /// Configures a channel of a bridge attached over I2C for the sensor, at 9600 8N1
+    /// * `address` - 7-bit I2C address set by the A0/A1 pins
+    /// * `channel` - 0 for channel A, 1 for channel B of dual-channel chips
+    /// * `crystal_hz` - frequency of the bridge's crystal, e.g. 14_745_600
+    pub fn new_i2c(i2c: I2C, address: u8, channel: u8, crystal_hz: u32) -> Result<Self, BridgeError<I2C::Error>> {
+        Self::new(I2cBridgeBus { i2c, address }, channel, crystal_hz)
+    }
+}
+
+impl<SPI: SpiDevice> Sc16is75x<SpiBridgeBus<SPI>> {
+    /// Configures a channel of a bridge attached over SPI for the sensor, at 9600 8N1
+    /// * `channel` - 0 for channel A, 1 for channel B of dual-channel chips
+    /// * `crystal_hz` - frequency of the bridge's crystal, e.g. 14_745_600
+    pub fn new_spi(spi: SPI, channel: u8, crystal_hz: u32) -> Result<Self, BridgeError<SPI::Error>> {
+        Self::new(SpiBridgeBus { spi }, channel, crystal_hz)
+    }
+}
+
+impl<BUS: BridgeBus> Sc16is75x<BUS> {
+    /// Configures a channel for the sensor over any bus, at 9600 8N1
+    pub fn new(bus: BUS, channel: u8, crystal_hz: u32) -> Result<Self, BridgeError<BUS::Error>> {
+        let mut bridge = Self { bus, channel };
+        let divisor = (crystal_hz / (16 * BAUD_RATE)) as u16;
+        let [dlh, dll] = divisor.to_be_bytes();
+        bridge.write(LCR, LCR_DIVISOR_LATCH)?;
+        bridge.write(DLL, dll)?;
+        bridge.write(DLH, dlh)?;
+        bridge.write(LCR, LCR_8N1)?;
+        bridge.write(FCR, FCR_FIFO_RESET)?;
+        Ok(bridge)
+    }
+
+    /// Returns the wrapped bus
+    pub fn release(self) -> BUS {
+        self.bus
+    }
+
+    fn write(&mut self, reg: u8, value: u8) -> Result<(), BridgeError<BUS::Error>> {
+        self.bus.write_register(self.channel, reg, &[value]).map_err(BridgeError)
+    }
+
+    fn level(&mut self, reg: u8) -> Result<usize, BridgeError<BUS::Error>> {
+        let mut level = [0u8];
+        self.bus.read_register(self.channel, reg, &mut level).map_err(BridgeError)?;
+        Ok(level[0] as usize)
+    }
+}
+
+impl<BUS: BridgeBus> ErrorType for Sc16is75x<BUS> {
+    type Error = BridgeError<BUS::Error>;
+}
+
+impl<BUS: BridgeBus> Read for Sc16is75x<BUS> {
+    /// Polls the receive FIFO until it holds data, then reads as much of it as fits
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let available = loop {
+            match self.level(RXLVL)? {
+                0 => continue,
+                n => break n,
+            }
+        };
+        let n = available.min(buf.len()).min(BURST_SIZE);
+        self.bus.read_register(self.channel, RHR_THR, &mut buf[..n]).map_err(BridgeError)?;
+        Ok(n)
+    }
+}
+
+impl<BUS: BridgeBus> ReadReady for Sc16is75x<BUS> {
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.level(RXLVL)? > 0)
+    }
+}
+
+impl<BUS: BridgeBus> Write for Sc16is75x<BUS> {
+    /// Waits for room in the transmit FIFO, then queues as much of `buf` as fits
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let space = loop {
+            match self.level(TXLVL)? {
+                0 => continue,
+                n => break n,
+            }
+        };
+        let n = space.min(buf.len()).min(BURST_SIZE);
+        self.bus.write_register(self.channel, RHR_THR, &buf[..n]).map_err(BridgeError)?;
+        Ok(n)
+    }
+
+    /// Polls the transmit FIFO until it's empty. The last byte may still be shifting out.
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        while self.level(TXLVL)? < FIFO_SIZE {}
+        Ok(())
+    }
+}
@@ -0,0 +1,269 @@
+//! C API for firmware written in C (ESP-IDF components, Zephyr modules, ...)
+//!
+//! Headers are generated with `cbindgen --config cbindgen.toml --output pmsx003.h`.
+//! The sensor is driven through read/write callbacks supplied by the C code, and its state
+//! lives in caller-provided [`PmsSensor`] storage so no allocator is needed.
+
+use core::ffi::c_void;
+use core::mem::{align_of, size_of, MaybeUninit};
+
+use embedded_io::{ErrorKind, ErrorType, Read, Write};
+
+use crate::{Error, OutputFrame, PmsX003Sensor, OUTPUT_FRAME_SIZE};
+
+/// Reads up to `len` bytes into `buf`, returning the number of bytes read or a negative error
+pub type PmsReadCallback = unsafe extern "C" fn(user_data: *mut c_void, buf: *mut u8, len: usize) -> i32;
+
+/// Writes up to `len` bytes from `buf`, returning the number of bytes written or a negative error
+pub type PmsWriteCallback = unsafe extern "C" fn(user_data: *mut c_void, buf: *const u8, len: usize) -> i32;
+
+/// Result of every C API call
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PmsStatus {
+    Ok = 0,
+    ReadFailed = -1,
+    WriteFailed = -2,
+    ChecksumError = -3,
+    IncorrectResponse = -4,
+    NoResponse = -5,
+    FrameLengthMismatch = -6,
+    OutOfRange = -7,
+    InvalidArgument = -8,
+    Timeout = -9,
+    UnsupportedByVariant = -10,
+    SensorFault = -11,
+}
+
+impl<E> From<Error<E>> for PmsStatus {
+    fn from(e: Error<E>) -> Self {
+        match e {
+            Error::Read(_) => PmsStatus::ReadFailed,
+            Error::Write(_) => PmsStatus::WriteFailed,
+            Error::ChecksumError => PmsStatus::ChecksumError,
+            Error::IncorrectResponse(_) => PmsStatus::IncorrectResponse,
+            Error::NoResponse => PmsStatus::NoResponse,
+            Error::FrameLengthMismatch { .. } => PmsStatus::FrameLengthMismatch,
+            Error::OutOfRange => PmsStatus::OutOfRange,
+            Error::Timeout => PmsStatus::Timeout,
+            Error::UnsupportedByVariant(_) => PmsStatus::UnsupportedByVariant,
+            Error::SensorFault(_) => PmsStatus::SensorFault,
+        }
+    }
+}
+
+/// Measurements of a frame, see [`OutputFrame`]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PmsFrame {
+    pub pm1_0: u16,
+    pub pm2_5: u16,
+    pub pm10: u16,
+    pub pm1_0_atm: u16,
+    pub pm2_5_atm: u16,
+    pub pm10_atm: u16,
+    pub beyond_0_3: u16,
+    pub beyond_0_5: u16,
+    pub beyond_1_0: u16,
+    pub beyond_2_5: u16,
+    pub beyond_5_0: u16,
+    pub beyond_10_0: u16,
+    pub reserved: u16,
+}
+
+impl From<&OutputFrame> for PmsFrame {
+    fn from(frame: &OutputFrame) -> Self {
+        Self {
+            pm1_0: frame.pm1_0,
+            pm2_5: frame.pm2_5,
+            pm10: frame.pm10,
+            pm1_0_atm: frame.pm1_0_atm,
+            pm2_5_atm: frame.pm2_5_atm,
+            pm10_atm: frame.pm10_atm,
+            beyond_0_3: frame.beyond_0_3,
+            beyond_0_5: frame.beyond_0_5,
+            beyond_1_0: frame.beyond_1_0,
+            beyond_2_5: frame.beyond_2_5,
+            beyond_5_0: frame.beyond_5_0,
+            beyond_10_0: frame.beyond_10_0,
+            reserved: frame.reserved(),
+        }
+    }
+}
+
+/// Negative value returned by a callback
+#[derive(Clone, Copy, Debug)]
+pub struct CallbackError(pub i32);
+
+impl embedded_io::Error for CallbackError {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+/// Serial port implemented by C callbacks
+pub struct CallbackUart {
+    read: PmsReadCallback,
+    write: PmsWriteCallback,
+    user_data: *mut c_void,
+}
+
+impl ErrorType for CallbackUart {
+    type Error = CallbackError;
+}
+
+impl Read for CallbackUart {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        // SAFETY: the callback contract is documented on `pms_init`
+        match unsafe { (self.read)(self.user_data, buf.as_mut_ptr(), buf.len()) } {
+            n if n < 0 => Err(CallbackError(n)),
+            n => Ok(n as usize),
+        }
+    }
+}
+
+impl Write for CallbackUart {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        // SAFETY: the callback contract is documented on `pms_init`
+        match unsafe { (self.write)(self.user_data, buf.as_ptr(), buf.len()) } {
+            n if n < 0 => Err(CallbackError(n)),
+            n => Ok(n as usize),
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// Size in bytes of the storage reserved for a sensor
+pub const PMS_SENSOR_STORAGE_SIZE: usize = 256;
+
+/// Opaque sensor state, allocated by the caller and initialized with `pms_init`
+#[repr(C, align(8))]
+pub struct PmsSensor {
+    storage: [u8; PMS_SENSOR_STORAGE_SIZE],
+}
+
+const _: () = assert!(size_of::<PmsX003Sensor<CallbackUart>>() <= PMS_SENSOR_STORAGE_SIZE);
+const _: () = assert!(align_of::<PmsX003Sensor<CallbackUart>>() <= align_of::<PmsSensor>());
+
+unsafe fn sensor<'a>(sensor: *mut PmsSensor) -> Option<&'a mut PmsX003Sensor<CallbackUart>> {
+    // SAFETY: non-null pointers were initialized by `pms_init`, storage is large and aligned enough
+    unsafe { sensor.cast::<PmsX003Sensor<CallbackUart>>().as_mut() }
+}
+
+fn status<E>(result: Result<(), Error<E>>) -> PmsStatus {
+    match result {
+        Ok(()) => PmsStatus::Ok,
+        Err(e) => e.into(),
+    }
+}
+
+/// Initializes a sensor driven by the given callbacks.
+///
+/// # Safety
+/// `sensor` must point to writable storage. The callbacks must honor the buffer bounds they are
+/// given and stay valid, together with `user_data`, for as long as the sensor is used.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pms_init(
+    sensor: *mut PmsSensor,
+    read: PmsReadCallback,
+    write: PmsWriteCallback,
+    user_data: *mut c_void,
+) -> PmsStatus {
+    if sensor.is_null() {
+        return PmsStatus::InvalidArgument;
+    }
+    let uart = CallbackUart { read, write, user_data };
+    // SAFETY: checked for null above, size and alignment are asserted at compile time
+    unsafe {
+        sensor
+            .cast::<MaybeUninit<PmsX003Sensor<CallbackUart>>>()
+            .write(MaybeUninit::new(PmsX003Sensor::new(uart)));
+    }
+    PmsStatus::Ok
+}
+
+/// Reads the next frame. Blocks until one is available.
+///
+/// # Safety
+/// `sensor` must have been initialized with `pms_init`, `frame` must point to writable memory.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pms_read(sensor: *mut PmsSensor, frame: *mut PmsFrame) -> PmsStatus {
+    // SAFETY: guaranteed by the caller
+    let (Some(sensor), Some(out)) = (unsafe { self::sensor(sensor) }, unsafe { frame.as_mut() }) else {
+        return PmsStatus::InvalidArgument;
+    };
+    match sensor.read() {
+        Ok(frame) => {
+            *out = PmsFrame::from(&frame);
+            PmsStatus::Ok
+        }
+        Err(e) => e.into(),
+    }
+}
+
+macro_rules! command_fn {
+    ($(#[$doc:meta])* $name:ident => $method:ident) => {
+        $(#[$doc])*
+        ///
+        /// # Safety
+        /// `sensor` must have been initialized with `pms_init`.
+        #[unsafe(no_mangle)]
+        pub unsafe extern "C" fn $name(sensor: *mut PmsSensor) -> PmsStatus {
+            // SAFETY: guaranteed by the caller
+            match unsafe { self::sensor(sensor) } {
+                Some(sensor) => status(sensor.$method()),
+                None => PmsStatus::InvalidArgument,
+            }
+        }
+    };
+}
+
+command_fn!(
+    /// Puts the sensor to sleep
+    pms_sleep => sleep
+);
+command_fn!(
+    /// Wakes the sensor up
+    pms_wake => wake
+);
+command_fn!(
+    /// Passive mode - sensor reports air quality on request
+    #[cfg(not(feature = "active-only"))]
+    pms_passive => passive
+);
+command_fn!(
+    /// Active mode - sensor reports air quality continuously
+    #[cfg(not(feature = "passive-only"))]
+    pms_active => active
+);
+command_fn!(
+    /// Requests status in passive mode
+    #[cfg(not(feature = "active-only"))]
+    pms_request => request
+);
+
+/// Parses a 32-byte frame received by other means, e.g. DMA.
+///
+/// # Safety
+/// `buf` must point to `len` readable bytes, `frame` must point to writable memory.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pms_parse_buffer(buf: *const u8, len: usize, frame: *mut PmsFrame) -> PmsStatus {
+    if buf.is_null() || frame.is_null() || len < OUTPUT_FRAME_SIZE {
+        return PmsStatus::InvalidArgument;
+    }
+    let mut buffer = [0u8; OUTPUT_FRAME_SIZE];
+    // SAFETY: checked for null and length above
+    buffer.copy_from_slice(unsafe { core::slice::from_raw_parts(buf, OUTPUT_FRAME_SIZE) });
+
+    match OutputFrame::from_buffer::<()>(&buffer) {
+        Ok(parsed) => {
+            // SAFETY: checked for null above
+            unsafe { *frame = PmsFrame::from(&parsed) };
+            PmsStatus::Ok
+        }
+        Err(e) => e.into(),
+    }
+}
@@ -0,0 +1,56 @@
+//! Decoding of raw serial captures, e.g. dumps recorded with a logic analyzer or `cat /dev/ttyUSB0`
+
+use crate::parser::parse_frame;
+use crate::{OutputFrame, ParseError, MN1, MN2, OUTPUT_FRAME_SIZE, OUTPUT_LENGTH, RESPONSE_FRAME_SIZE, RESPONSE_LENGTH};
+
+/// Iterates over the data frames of a capture, decoding them exactly like the driver does.
+/// Command responses and unknown packets are skipped, corrupted frames are reported as errors.
+#[derive(Clone, Debug)]
+pub struct Replay<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Replay<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    /// Bytes not consumed yet, e.g. a truncated trailing frame
+    pub fn remaining(&self) -> &'a [u8] {
+        self.data
+    }
+}
+
+impl Iterator for Replay<'_> {
+    type Item = Result<OutputFrame, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let start = self.data.windows(2).position(|w| w == [MN1, MN2])?;
+            let packet = &self.data[start..];
+            if packet.len() < 4 {
+                return None;
+            }
+
+            let size = match u16::from_be_bytes([packet[2], packet[3]]) {
+                OUTPUT_LENGTH => OUTPUT_FRAME_SIZE,
+                RESPONSE_LENGTH => RESPONSE_FRAME_SIZE,
+                _ => {
+                    self.data = &packet[2..];
+                    continue;
+                }
+            };
+            if packet.len() < size {
+                self.data = packet;
+                return None;
+            }
+            self.data = &packet[size..];
+
+            if size == OUTPUT_FRAME_SIZE {
+                let mut buffer = [0u8; OUTPUT_FRAME_SIZE];
+                buffer.copy_from_slice(&packet[..size]);
+                return Some(parse_frame(&buffer));
+            }
+        }
+    }
+}
@@ -0,0 +1,204 @@
+//! Data frame formats, so sensors with a different frame layout can be supported
+//! by implementing [`FrameCodec`] rather than changing the driver
+
+#[cfg(any(feature = "pms3003", feature = "pms5003st"))]
+use crate::variant::PmsVariant;
+use crate::{verify_checksum, Command, OutputFrame, OUTPUT_LENGTH};
+#[cfg(any(feature = "pms1003", feature = "pms3003"))]
+use crate::CHECKSUM_SIZE;
+
+/// Largest frame the driver can receive, header and checksum included
+pub const MAX_FRAME_SIZE: usize = 64;
+
+/// Layout of the data frames sent by a sensor model. Every frame starts with the `MN1`, `MN2`
+/// header followed by the big-endian frame length, counting the bytes after it.
+pub trait FrameCodec {
+    /// Value of the frame length field. Frames longer than `MAX_FRAME_SIZE` can't be received.
+    fn frame_length(&self) -> u16;
+
+    /// Whether the checksum of a complete frame is valid.
+    /// Defaults to the 16-bit sum of all preceding bytes, sent big-endian in the last two bytes.
+    fn checksum_valid(&self, frame: &[u8]) -> bool {
+        verify_checksum(frame)
+    }
+
+    /// Maps the fields of a complete frame with a valid checksum
+    fn decode(&self, frame: &[u8]) -> OutputFrame;
+
+    /// Whether the sensor model understands a command. The driver fails unsupported commands with
+    /// `Error::UnsupportedByVariant` rather than waiting for a response that never comes.
+    /// Defaults to every command.
+    fn supports(&self, _command: Command) -> bool {
+        true
+    }
+}
+
+/// 32-byte frames of the PMS5003, PMS7003, PMSA003 and most other models
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PlantowerCodec;
+
+impl FrameCodec for PlantowerCodec {
+    fn frame_length(&self) -> u16 {
+        OUTPUT_LENGTH
+    }
+
+    fn decode(&self, frame: &[u8]) -> OutputFrame {
+        OutputFrame::decode(frame)
+    }
+}
+
+/// 24-byte frames of the PMS3003, which has no particle counts and no passive mode.
+/// Only the concentrations of the decoded frame are set.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg(feature = "pms3003")]
+pub struct Pms3003Codec;
+
+#[cfg(feature = "pms3003")]
+impl FrameCodec for Pms3003Codec {
+    fn frame_length(&self) -> u16 {
+        (Self::FRAME_SIZE - 4) as u16
+    }
+
+    fn decode(&self, frame: &[u8]) -> OutputFrame {
+        decode_concentrations(frame)
+    }
+
+    fn supports(&self, command: Command) -> bool {
+        supported_by_early_models(command)
+    }
+}
+
+/// 32-byte frames of the PMS1003, also sold as G1. Its count bins hold unreliable values or
+/// nothing depending on the firmware, so only the concentrations of the decoded frame are set.
+/// Like the PMS3003 it has no passive mode.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg(feature = "pms1003")]
+pub struct Pms1003Codec;
+
+#[cfg(feature = "pms1003")]
+impl FrameCodec for Pms1003Codec {
+    fn frame_length(&self) -> u16 {
+        OUTPUT_LENGTH
+    }
+
+    fn decode(&self, frame: &[u8]) -> OutputFrame {
+        decode_concentrations(frame)
+    }
+
+    fn supports(&self, command: Command) -> bool {
+        supported_by_early_models(command)
+    }
+}
+
+/// Header, concentrations and checksum of a frame of any size, the rest left at zero
+#[cfg(any(feature = "pms1003", feature = "pms3003"))]
+fn decode_concentrations(frame: &[u8]) -> OutputFrame {
+    let word = |offset: usize| u16::from_be_bytes([frame[offset], frame[offset + 1]]);
+    OutputFrame {
+        start1: frame[0],
+        start2: frame[1],
+        frame_length: word(2),
+        pm1_0: word(4),
+        pm2_5: word(6),
+        pm10: word(8),
+        pm1_0_atm: word(10),
+        pm2_5_atm: word(12),
+        pm10_atm: word(14),
+        check: word(frame.len() - CHECKSUM_SIZE),
+        ..OutputFrame::default()
+    }
+}
+
+/// Early models ignore mode changes and requests, whichever of them the enabled features keep
+#[cfg(any(feature = "pms1003", feature = "pms3003"))]
+fn supported_by_early_models(command: Command) -> bool {
+    !matches!(command.encode()[2], 0xe1 | 0xe2)
+}
+
+/// 32-byte frames of the PMS5003T, reporting temperature and humidity instead of the >5.0μm and
+/// >10μm counts, which are left at zero. See [`OutputFrameT`](crate::variant::OutputFrameT).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg(feature = "pms5003")]
+pub struct Pms5003tCodec;
+
+#[cfg(feature = "pms5003")]
+impl FrameCodec for Pms5003tCodec {
+    fn frame_length(&self) -> u16 {
+        OUTPUT_LENGTH
+    }
+
+    fn decode(&self, frame: &[u8]) -> OutputFrame {
+        OutputFrame {
+            beyond_5_0: 0,
+            beyond_10_0: 0,
+            ..OutputFrame::decode(frame)
+        }
+    }
+}
+
+/// 32-byte frames of the PMS5003S, reporting formaldehyde in place of the version and error code,
+/// which are left at zero. See [`OutputFrameS`](crate::variant::OutputFrameS).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg(feature = "pms5003")]
+pub struct Pms5003sCodec;
+
+#[cfg(feature = "pms5003")]
+impl FrameCodec for Pms5003sCodec {
+    fn frame_length(&self) -> u16 {
+        OUTPUT_LENGTH
+    }
+
+    fn decode(&self, frame: &[u8]) -> OutputFrame {
+        OutputFrame {
+            version: 0,
+            error_code: 0,
+            ..OutputFrame::decode(frame)
+        }
+    }
+}
+
+/// 32-byte frames of the PMS9003M and PMS9103M. Their error code byte tells whether the readings
+/// are settled rather than reporting faults, so it's left at zero.
+/// See [`OutputFrameM`](crate::variant::OutputFrameM).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg(feature = "pms9003m")]
+pub struct Pms9003mCodec;
+
+#[cfg(feature = "pms9003m")]
+impl FrameCodec for Pms9003mCodec {
+    fn frame_length(&self) -> u16 {
+        OUTPUT_LENGTH
+    }
+
+    fn decode(&self, frame: &[u8]) -> OutputFrame {
+        OutputFrame {
+            error_code: 0,
+            ..OutputFrame::decode(frame)
+        }
+    }
+}
+
+/// 40-byte frames of the PMS5003ST, adding formaldehyde, temperature and humidity after the
+/// particle counts. See [`OutputFrameSt`](crate::variant::OutputFrameSt).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg(feature = "pms5003st")]
+pub struct Pms5003stCodec;
+
+#[cfg(feature = "pms5003st")]
+impl FrameCodec for Pms5003stCodec {
+    fn frame_length(&self) -> u16 {
+        (Self::FRAME_SIZE - 4) as u16
+    }
+
+    fn decode(&self, frame: &[u8]) -> OutputFrame {
+        // The particle readings are laid out like a 32-byte frame, the version and checksum
+        // follow the extra readings
+        let word = |offset: usize| u16::from_be_bytes([frame[offset], frame[offset + 1]]);
+        OutputFrame {
+            version: frame[Self::FRAME_SIZE - 4],
+            error_code: frame[Self::FRAME_SIZE - 3],
+            check: word(Self::FRAME_SIZE - 2),
+            ..OutputFrame::decode(frame)
+        }
+    }
+}
@@ -0,0 +1,47 @@
+//! Suppression of repeated frames
+
+use crate::OutputFrame;
+
+/// Drops consecutive identical frames, letting one through every `keepalive` repeats
+/// so consumers can tell a stable reading from a dead sensor
+#[derive(Clone, Debug)]
+pub struct Deduplicator {
+    last: Option<OutputFrame>,
+    suppressed: u16,
+    keepalive: u16,
+}
+
+impl Deduplicator {
+    /// Creates a new deduplicator
+    /// * `keepalive` - number of suppressed repeats after which a frame is emitted anyway, 0 to never emit repeats
+    pub fn new(keepalive: u16) -> Self {
+        Self {
+            last: None,
+            suppressed: 0,
+            keepalive,
+        }
+    }
+
+    /// Returns the frame if it should be emitted
+    pub fn filter(&mut self, frame: OutputFrame) -> Option<OutputFrame> {
+        let repeat = self.last.as_ref() == Some(&frame);
+        if repeat && (self.keepalive == 0 || self.suppressed < self.keepalive) {
+            self.suppressed = self.suppressed.saturating_add(1);
+            return None;
+        }
+
+        self.suppressed = 0;
+        self.last = Some(frame.clone());
+        Some(frame)
+    }
+
+    /// Number of repeats suppressed since the last emitted frame, stopping at `u16::MAX`
+    pub fn suppressed(&self) -> u16 {
+        self.suppressed
+    }
+
+    pub fn reset(&mut self) {
+        self.last = None;
+        self.suppressed = 0;
+    }
+}
@@ -0,0 +1,65 @@
+//! Reception through a circular DMA buffer
+//!
+//! At 9600 baud a byte arrives roughly every millisecond, so byte interrupts keep the CPU busy
+//! for little gain. Instead the UART can write into a circular buffer by DMA, and the
+//! half-transfer and transfer-complete interrupts hand each filled half to [`CircularDma`].
+//! With the idle-line interrupt enabled as well, frames are delivered as soon as they end.
+
+use crate::parser::FrameParser;
+use crate::{OutputFrame, ParseError};
+
+/// Tracks how much of a circular DMA buffer has been parsed.
+/// Bytes overwritten by the DMA before being handed over are lost, so the buffer should hold
+/// at least two frames.
+#[derive(Clone, Debug, Default)]
+pub struct CircularDma {
+    parser: FrameParser,
+    position: usize,
+}
+
+impl CircularDma {
+    pub const fn new() -> Self {
+        Self {
+            parser: FrameParser::new(),
+            position: 0,
+        }
+    }
+
+    /// Half-transfer interrupt: the first half of `buffer` was filled
+    pub fn on_half_transfer<F>(&mut self, buffer: &[u8], on_frame: F)
+    where
+        F: FnMut(Result<OutputFrame, ParseError>),
+    {
+        self.advance(buffer, buffer.len() / 2, on_frame);
+    }
+
+    /// Transfer-complete interrupt: the second half of `buffer` was filled
+    pub fn on_transfer_complete<F>(&mut self, buffer: &[u8], on_frame: F)
+    where
+        F: FnMut(Result<OutputFrame, ParseError>),
+    {
+        self.advance(buffer, buffer.len(), on_frame);
+    }
+
+    /// Idle-line interrupt, or polling from a main loop
+    /// * `remaining` - transfers left before the buffer wraps, i.e. the DMA's NDTR register
+    pub fn on_idle<F>(&mut self, buffer: &[u8], remaining: usize, on_frame: F)
+    where
+        F: FnMut(Result<OutputFrame, ParseError>),
+    {
+        self.advance(buffer, buffer.len() - remaining.min(buffer.len()), on_frame);
+    }
+
+    fn advance<F>(&mut self, buffer: &[u8], end: usize, mut on_frame: F)
+    where
+        F: FnMut(Result<OutputFrame, ParseError>),
+    {
+        if end < self.position {
+            self.parser.push_slice(&buffer[self.position..], &mut on_frame);
+            self.parser.push_slice(&buffer[..end], &mut on_frame);
+        } else {
+            self.parser.push_slice(&buffer[self.position..end], &mut on_frame);
+        }
+        self.position = end % buffer.len();
+    }
+}
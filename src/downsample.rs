@@ -0,0 +1,119 @@
+//! Reduction of frames to fixed-interval aggregates, e.g. for logging or uplinks
+
+use crate::{Field, OutputFrame};
+
+pub const ONE_MINUTE_MS: u64 = 60_000;
+pub const TEN_MINUTES_MS: u64 = 10 * ONE_MINUTE_MS;
+
+const FIELDS: usize = Field::ALL.len();
+
+/// Statistics of a single field over an interval
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FieldStats {
+    pub mean: f32,
+    pub min: u16,
+    pub max: u16,
+}
+
+/// Statistics of all measurement fields over an interval
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aggregate {
+    /// Start of the interval, aligned to a multiple of the interval length
+    pub start_ms: u64,
+    /// Number of frames the statistics were computed from
+    pub count: u32,
+    stats: [FieldStats; FIELDS],
+}
+
+impl Aggregate {
+    pub fn get(&self, field: Field) -> FieldStats {
+        self.stats[field as usize]
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Accumulator {
+    interval: u64,
+    count: u32,
+    sum: [u32; FIELDS],
+    min: [u16; FIELDS],
+    max: [u16; FIELDS],
+}
+
+impl Accumulator {
+    fn new(interval: u64) -> Self {
+        Self {
+            interval,
+            count: 0,
+            sum: [0; FIELDS],
+            min: [u16::MAX; FIELDS],
+            max: [0; FIELDS],
+        }
+    }
+
+    fn add(&mut self, frame: &OutputFrame) {
+        self.count += 1;
+        for (i, field) in Field::ALL.iter().enumerate() {
+            let value = frame[*field];
+            self.sum[i] = self.sum[i].saturating_add(value as u32);
+            self.min[i] = self.min[i].min(value);
+            self.max[i] = self.max[i].max(value);
+        }
+    }
+
+    fn finish(&self, interval_ms: u64) -> Aggregate {
+        let mut stats = [FieldStats { mean: 0.0, min: 0, max: 0 }; FIELDS];
+        for (i, stat) in stats.iter_mut().enumerate() {
+            *stat = FieldStats {
+                mean: self.sum[i] as f32 / self.count as f32,
+                min: self.min[i],
+                max: self.max[i],
+            };
+        }
+        Aggregate {
+            start_ms: self.interval * interval_ms,
+            count: self.count,
+            stats,
+        }
+    }
+}
+
+/// Turns frames into one [`Aggregate`] per interval, using constant memory
+#[derive(Clone, Debug)]
+pub struct Downsampler {
+    interval_ms: u64,
+    current: Option<Accumulator>,
+}
+
+impl Downsampler {
+    /// Creates a new downsampler
+    /// * `interval_ms` - interval length, e.g. `ONE_MINUTE_MS`
+    pub fn new(interval_ms: u64) -> Self {
+        Self {
+            interval_ms: interval_ms.max(1),
+            current: None,
+        }
+    }
+
+    /// Feeds a frame, returning the aggregate of the previous interval once it's over.
+    /// Intervals without frames produce no aggregate.
+    /// * `now_ms` - timestamp in milliseconds, intervals are aligned to multiples of their length
+    pub fn push(&mut self, now_ms: u64, frame: &OutputFrame) -> Option<Aggregate> {
+        let interval = now_ms / self.interval_ms;
+        let finished = match self.current {
+            Some(current) if current.interval != interval => {
+                self.current = None;
+                Some(current.finish(self.interval_ms))
+            }
+            _ => None,
+        };
+
+        self.current.get_or_insert_with(|| Accumulator::new(interval)).add(frame);
+        finished
+    }
+
+    /// Returns the aggregate of the partial current interval and starts over
+    pub fn flush(&mut self) -> Option<Aggregate> {
+        self.current.take().map(|current| current.finish(self.interval_ms))
+    }
+}
@@ -0,0 +1,70 @@
+//! Object-safe driver interface, so applications can hold `&mut dyn PmSensor` and pick
+//! the backend at runtime, e.g. a real sensor or an emulated one on a test bench
+
+use embedded_io::{ErrorType, Read, Write};
+
+use crate::codec::FrameCodec;
+use crate::transport::{DynUart, ErasedUart};
+use crate::{Error, OutputFrame, PmsX003Sensor, SyncStats};
+
+/// Driver over a borrowed serial port of any type, with errors reduced to `ErrorKind`.
+/// Lets firmware with several UART types keep its sensors in one array.
+pub type ErasedPmsSensor<'a> = PmsX003Sensor<ErasedUart<'a>>;
+
+impl<'a> ErasedPmsSensor<'a> {
+    /// Creates a new sensor instance
+    /// * `uart` - any serial port implementing embedded-io Read + Write traits
+    pub fn new_erased(uart: &'a mut dyn DynUart) -> Self {
+        PmsX003Sensor::new(ErasedUart::new(uart))
+    }
+}
+
+/// Health information of a driver, e.g. to log along with a failed read
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Diagnostics {
+    /// Resync statistics of the last read
+    pub sync: SyncStats,
+    /// Commands queued or awaiting their response
+    pub pending_commands: usize,
+}
+
+/// Blocking particulate matter sensor
+pub trait PmSensor {
+    type Error;
+
+    /// Reads the next frame reported by the sensor
+    fn read_latest(&mut self) -> Result<OutputFrame, Self::Error>;
+
+    fn sleep(&mut self) -> Result<(), Self::Error>;
+
+    fn wake(&mut self) -> Result<(), Self::Error>;
+
+    fn diagnostics(&self) -> Diagnostics;
+}
+
+impl<UART, C> PmSensor for PmsX003Sensor<UART, C>
+where
+    UART: Read + Write + ErrorType,
+    C: FrameCodec,
+{
+    type Error = Error<UART::Error>;
+
+    fn read_latest(&mut self) -> Result<OutputFrame, Self::Error> {
+        self.read()
+    }
+
+    fn sleep(&mut self) -> Result<(), Self::Error> {
+        PmsX003Sensor::sleep(self)
+    }
+
+    fn wake(&mut self) -> Result<(), Self::Error> {
+        PmsX003Sensor::wake(self)
+    }
+
+    fn diagnostics(&self) -> Diagnostics {
+        Diagnostics {
+            sync: self.sync_stats(),
+            pending_commands: self.pending_commands(),
+        }
+    }
+}
@@ -0,0 +1,107 @@
+//! Compatibility with serial drivers still implementing embedded-hal 0.2
+
+use core::fmt::Debug;
+
+use embedded_hal_02::serial;
+use embedded_hal_nb::serial::{ErrorKind, ErrorType, Read, Write};
+
+use crate::transport::NbUart;
+use crate::PmsX003Sensor;
+
+/// Error of an embedded-hal 0.2 serial half, which carries no `ErrorKind`
+#[derive(Debug)]
+pub struct Eh02Error<E>(pub E);
+
+impl<E: Debug> embedded_hal_nb::serial::Error for Eh02Error<E> {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+/// Adapter implementing the embedded-hal-nb serial Read trait for an embedded-hal 0.2 receiver
+pub struct Eh02Rx<S> {
+    inner: S,
+}
+
+impl<S> Eh02Rx<S> {
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+
+    /// Returns the wrapped half
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S> ErrorType for Eh02Rx<S>
+where
+    S: serial::Read<u8>,
+    S::Error: Debug,
+{
+    type Error = Eh02Error<S::Error>;
+}
+
+impl<S> Read<u8> for Eh02Rx<S>
+where
+    S: serial::Read<u8>,
+    S::Error: Debug,
+{
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        self.inner.read().map_err(|e| e.map(Eh02Error))
+    }
+}
+
+/// Adapter implementing the embedded-hal-nb serial Write trait for an embedded-hal 0.2 transmitter
+pub struct Eh02Tx<S> {
+    inner: S,
+}
+
+impl<S> Eh02Tx<S> {
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+
+    /// Returns the wrapped half
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S> ErrorType for Eh02Tx<S>
+where
+    S: serial::Write<u8>,
+    S::Error: Debug,
+{
+    type Error = Eh02Error<S::Error>;
+}
+
+impl<S> Write<u8> for Eh02Tx<S>
+where
+    S: serial::Write<u8>,
+    S::Error: Debug,
+{
+    fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
+        self.inner.write(word).map_err(|e| e.map(Eh02Error))
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        self.inner.flush().map_err(|e| e.map(Eh02Error))
+    }
+}
+
+impl<TX, RX> PmsX003Sensor<NbUart<Eh02Tx<TX>, Eh02Rx<RX>>>
+where
+    TX: serial::Write<u8>,
+    TX::Error: Debug,
+    RX: serial::Read<u8>,
+    RX::Error: Debug,
+{
+    /// Creates a new sensor instance from the halves of an embedded-hal 0.2 serial port,
+    /// e.g. the result of `serial.split()`
+    /// * `tx` - transmitter implementing embedded-hal 0.2 serial Write trait
+    /// * `rx` - receiver implementing embedded-hal 0.2 serial Read trait
+    pub fn new_eh02(tx: TX, rx: RX) -> Self {
+        Self::new_tx_rx(Eh02Tx::new(tx), Eh02Rx::new(rx))
+    }
+}
@@ -0,0 +1,137 @@
+//! Detection of pollution events such as cooking or smoke
+
+use crate::filter::Ewma;
+use crate::OutputFrame;
+
+/// Tuning of an [`EventDetector`]
+#[derive(Clone, Copy, Debug)]
+pub struct EventConfig {
+    /// Rise above the baseline in μg/m³ starting an event
+    pub threshold: f32,
+    /// An event ends once the excess over the baseline decayed below this fraction of the peak excess
+    pub end_fraction: f32,
+    /// EWMA weight of the baseline, which follows slow changes and is frozen during events
+    pub baseline_alpha: f32,
+    /// Events not decaying within this time are discarded as a change of the baseline
+    pub max_duration_ms: u64,
+}
+
+impl Default for EventConfig {
+    fn default() -> Self {
+        Self {
+            threshold: 15.0,
+            end_fraction: 0.2,
+            // Time constant of a few minutes at the usual reporting rate
+            baseline_alpha: 0.005,
+            max_duration_ms: 3 * 60 * 60 * 1000,
+        }
+    }
+}
+
+/// Rise of the concentration which decayed back to the baseline
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Event {
+    pub start_ms: u64,
+    pub peak_ms: u64,
+    pub end_ms: u64,
+    /// Peak PM2.5 concentration in μg/m³
+    pub peak: f32,
+    /// Concentration before the event in μg/m³
+    pub baseline: f32,
+}
+
+impl Event {
+    pub fn duration_ms(&self) -> u64 {
+        self.end_ms - self.start_ms
+    }
+}
+
+/// Change reported by [`EventDetector::update`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Detection {
+    Started { start_ms: u64, baseline: f32 },
+    Ended(Event),
+    /// The concentration didn't decay in time and became the new baseline
+    Discarded { start_ms: u64 },
+}
+
+/// Detects rapid PM2.5 rises followed by a decay back to the baseline
+#[derive(Clone, Debug)]
+pub struct EventDetector {
+    config: EventConfig,
+    baseline: Ewma,
+    active: Option<Event>,
+}
+
+impl EventDetector {
+    pub fn new(config: EventConfig) -> Self {
+        Self {
+            config,
+            baseline: Ewma::new(config.baseline_alpha),
+            active: None,
+        }
+    }
+
+    /// Feeds a PM2.5 concentration in μg/m³
+    /// * `now_ms` - timestamp in milliseconds, from any monotonic clock
+    pub fn update(&mut self, now_ms: u64, pm2_5: f32) -> Option<Detection> {
+        let Some(mut event) = self.active else {
+            let baseline = self.baseline.value().unwrap_or(pm2_5);
+            if pm2_5 - baseline < self.config.threshold {
+                self.baseline.update(pm2_5);
+                return None;
+            }
+            self.active = Some(Event {
+                start_ms: now_ms,
+                peak_ms: now_ms,
+                end_ms: now_ms,
+                peak: pm2_5,
+                baseline,
+            });
+            return Some(Detection::Started { start_ms: now_ms, baseline });
+        };
+
+        if pm2_5 > event.peak {
+            event.peak = pm2_5;
+            event.peak_ms = now_ms;
+        }
+        event.end_ms = now_ms;
+
+        if pm2_5 - event.baseline <= (event.peak - event.baseline) * self.config.end_fraction {
+            self.active = None;
+            self.baseline.update(pm2_5);
+            return Some(Detection::Ended(event));
+        }
+
+        if event.duration_ms() > self.config.max_duration_ms {
+            self.active = None;
+            self.baseline.reset();
+            self.baseline.update(pm2_5);
+            return Some(Detection::Discarded { start_ms: event.start_ms });
+        }
+
+        self.active = Some(event);
+        None
+    }
+
+    /// Feeds the atmospheric PM2.5 concentration of a frame
+    pub fn update_frame(&mut self, now_ms: u64, frame: &OutputFrame) -> Option<Detection> {
+        self.update(now_ms, frame.pm2_5_atm as f32)
+    }
+
+    /// Event in progress, with its peak so far
+    pub fn active(&self) -> Option<&Event> {
+        self.active.as_ref()
+    }
+
+    /// Current baseline concentration in μg/m³
+    pub fn baseline(&self) -> Option<f32> {
+        self.baseline.value()
+    }
+}
+
+impl Default for EventDetector {
+    fn default() -> Self {
+        Self::new(EventConfig::default())
+    }
+}
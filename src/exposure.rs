@@ -0,0 +1,283 @@
+//! Personal exposure to particulate matter over time
+
+use crate::OutputFrame;
+
+const MS_PER_HOUR: u64 = 3_600_000;
+const MS_PER_DAY: i64 = 86_400_000;
+// Readings further apart are treated as a gap in the data rather than a constant concentration
+const DEFAULT_MAX_GAP_MS: u64 = 5 * 60 * 1000;
+
+/// Time-weighted PM2.5 exposure in μg·h/m³, reset at midnight
+#[derive(Clone, Debug)]
+pub struct ExposureDose {
+    dose: f32,
+    previous_day: Option<f32>,
+    last: Option<(u64, f32)>,
+    day: Option<i64>,
+    utc_offset_ms: i64,
+    max_gap_ms: u64,
+}
+
+impl ExposureDose {
+    /// Creates a new accumulator whose days start at midnight UTC
+    pub fn new() -> Self {
+        Self {
+            dose: 0.0,
+            previous_day: None,
+            last: None,
+            day: None,
+            utc_offset_ms: 0,
+            max_gap_ms: DEFAULT_MAX_GAP_MS,
+        }
+    }
+
+    /// Moves the daily reset to local midnight
+    /// * `offset_minutes` - offset of local time to UTC, e.g. 60 for CET
+    pub fn set_utc_offset(&mut self, offset_minutes: i16) {
+        self.utc_offset_ms = offset_minutes as i64 * 60_000;
+    }
+
+    /// Sets the longest interval between readings still counted towards the dose. Defaults to 5 minutes.
+    pub fn set_max_gap(&mut self, max_gap_ms: u64) {
+        self.max_gap_ms = max_gap_ms;
+    }
+
+    /// Feeds a PM2.5 concentration in μg/m³ and returns today's dose
+    /// * `now_ms` - Unix time in milliseconds
+    pub fn update(&mut self, now_ms: u64, pm2_5: f32) -> f32 {
+        let day = (now_ms as i64 + self.utc_offset_ms).div_euclid(MS_PER_DAY);
+        if self.day.is_some_and(|current| current != day) {
+            self.previous_day = Some(self.dose);
+            self.dose = 0.0;
+        }
+        self.day = Some(day);
+
+        if let Some((last_ms, last_pm2_5)) = self.last {
+            let elapsed = now_ms.saturating_sub(last_ms);
+            if elapsed <= self.max_gap_ms {
+                // Trapezoidal rule between consecutive readings
+                self.dose += (last_pm2_5 + pm2_5) / 2.0 * elapsed as f32 / MS_PER_HOUR as f32;
+            }
+        }
+        self.last = Some((now_ms, pm2_5));
+        self.dose
+    }
+
+    /// Feeds the atmospheric PM2.5 concentration of a frame and returns today's dose
+    pub fn update_frame(&mut self, now_ms: u64, frame: &OutputFrame) -> f32 {
+        self.update(now_ms, frame.pm2_5_atm as f32)
+    }
+
+    /// Dose accumulated since midnight
+    pub fn dose(&self) -> f32 {
+        self.dose
+    }
+
+    /// Total dose of the previous day, once a day has ended
+    pub fn previous_day(&self) -> Option<f32> {
+        self.previous_day
+    }
+
+    pub fn reset(&mut self) {
+        let (utc_offset_ms, max_gap_ms) = (self.utc_offset_ms, self.max_gap_ms);
+        *self = Self::new();
+        self.utc_offset_ms = utc_offset_ms;
+        self.max_gap_ms = max_gap_ms;
+    }
+}
+
+impl Default for ExposureDose {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+const HOURS_PER_DAY: usize = 24;
+// Share of hourly averages required for a valid daily average, as in the EPA data completeness rule
+const MIN_HOURS_PER_DAY: u16 = 18;
+
+/// Limits for average concentrations in μg/m³
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Guideline {
+    pub pm2_5_24h: f32,
+    pub pm2_5_annual: f32,
+    pub pm10_24h: f32,
+    pub pm10_annual: f32,
+}
+
+impl Guideline {
+    /// WHO 2021 air quality guidelines
+    pub const WHO_2021: Guideline = Guideline {
+        pm2_5_24h: 15.0,
+        pm2_5_annual: 5.0,
+        pm10_24h: 45.0,
+        pm10_annual: 15.0,
+    };
+
+    /// US EPA national ambient air quality standards as revised in 2024. There's no annual PM10 standard.
+    pub const EPA_2024: Guideline = Guideline {
+        pm2_5_24h: 35.0,
+        pm2_5_annual: 9.0,
+        pm10_24h: 150.0,
+        pm10_annual: f32::INFINITY,
+    };
+}
+
+impl Default for Guideline {
+    fn default() -> Self {
+        Self::WHO_2021
+    }
+}
+
+/// Which limits are currently exceeded. Averages without enough data never exceed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Exceedance {
+    pub pm2_5_24h: bool,
+    pub pm2_5_annual: bool,
+    pub pm10_24h: bool,
+    pub pm10_annual: bool,
+}
+
+impl Exceedance {
+    pub fn any(&self) -> bool {
+        self.pm2_5_24h || self.pm2_5_annual || self.pm10_24h || self.pm10_annual
+    }
+}
+
+/// Number of calendar days whose average exceeded the 24-hour limit
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ExceedanceDays {
+    pub pm2_5: u16,
+    pub pm10: u16,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Mean {
+    pm2_5: f32,
+    pm10: f32,
+    count: u32,
+}
+
+impl Mean {
+    fn add(&mut self, pm2_5: f32, pm10: f32) {
+        self.pm2_5 += pm2_5;
+        self.pm10 += pm10;
+        self.count += 1;
+    }
+
+    fn value(&self) -> Option<(f32, f32)> {
+        (self.count > 0).then(|| (self.pm2_5 / self.count as f32, self.pm10 / self.count as f32))
+    }
+}
+
+/// Compares rolling 24-hour and long-term averages against a [`Guideline`].
+/// Readings are reduced to hourly averages, so memory use doesn't depend on the reporting rate.
+#[derive(Clone, Debug)]
+pub struct ExceedanceTracker {
+    guideline: Guideline,
+    current_hour: Option<u64>,
+    hour: Mean,
+    // Hourly averages of the last 24 hours, indexed by hour of day
+    hours: [Option<(f32, f32)>; HOURS_PER_DAY],
+    day: Mean,
+    annual: Mean,
+    days: ExceedanceDays,
+}
+
+impl ExceedanceTracker {
+    pub fn new(guideline: Guideline) -> Self {
+        Self {
+            guideline,
+            current_hour: None,
+            hour: Mean::default(),
+            hours: [None; HOURS_PER_DAY],
+            day: Mean::default(),
+            annual: Mean::default(),
+            days: ExceedanceDays::default(),
+        }
+    }
+
+    /// Feeds concentrations in μg/m³ and returns the current exceedance state
+    /// * `now_ms` - Unix time in milliseconds, days are counted in UTC
+    pub fn update(&mut self, now_ms: u64, pm2_5: f32, pm10: f32) -> Exceedance {
+        let hour = now_ms / MS_PER_HOUR;
+        match self.current_hour {
+            Some(current) if current != hour => self.close_hours(current, hour),
+            _ => {}
+        }
+        self.current_hour = Some(hour);
+        self.hour.add(pm2_5, pm10);
+        self.state()
+    }
+
+    /// Feeds the atmospheric concentrations of a frame
+    pub fn update_frame(&mut self, now_ms: u64, frame: &OutputFrame) -> Exceedance {
+        self.update(now_ms, frame.pm2_5_atm as f32, frame.pm10_atm as f32)
+    }
+
+    fn close_hours(&mut self, current: u64, next: u64) {
+        let mean = self.hour.value();
+        self.hour = Mean::default();
+        self.hours[(current % HOURS_PER_DAY as u64) as usize] = mean;
+        if let Some((pm2_5, pm10)) = mean {
+            self.day.add(pm2_5, pm10);
+            self.annual.add(pm2_5, pm10);
+        }
+
+        // Hours without readings, capped at a day since older ones would be overwritten anyway
+        for hour in (current + 1..next).take(HOURS_PER_DAY) {
+            self.hours[(hour % HOURS_PER_DAY as u64) as usize] = None;
+        }
+
+        if current / HOURS_PER_DAY as u64 != next / HOURS_PER_DAY as u64 {
+            if self.day.count >= MIN_HOURS_PER_DAY as u32
+                && let Some((pm2_5, pm10)) = self.day.value()
+            {
+                self.days.pm2_5 += (pm2_5 > self.guideline.pm2_5_24h) as u16;
+                self.days.pm10 += (pm10 > self.guideline.pm10_24h) as u16;
+            }
+            self.day = Mean::default();
+        }
+    }
+
+    /// Average of the hourly averages over the last 24 hours, once 18 of them are available
+    pub fn rolling_24h(&self) -> Option<(f32, f32)> {
+        let mut mean = Mean::default();
+        for (pm2_5, pm10) in self.hours.iter().flatten() {
+            mean.add(*pm2_5, *pm10);
+        }
+        if mean.count < MIN_HOURS_PER_DAY as u32 {
+            return None;
+        }
+        mean.value()
+    }
+
+    /// Average of all hourly averages since the tracker was created or `reset_annual()` was called
+    pub fn annual(&self) -> Option<(f32, f32)> {
+        self.annual.value()
+    }
+
+    pub fn state(&self) -> Exceedance {
+        let mut state = Exceedance::default();
+        if let Some((pm2_5, pm10)) = self.rolling_24h() {
+            state.pm2_5_24h = pm2_5 > self.guideline.pm2_5_24h;
+            state.pm10_24h = pm10 > self.guideline.pm10_24h;
+        }
+        if let Some((pm2_5, pm10)) = self.annual() {
+            state.pm2_5_annual = pm2_5 > self.guideline.pm2_5_annual;
+            state.pm10_annual = pm10 > self.guideline.pm10_annual;
+        }
+        state
+    }
+
+    /// Days which exceeded the 24-hour limits since the tracker was created or `reset_annual()` was called
+    pub fn exceedance_days(&self) -> ExceedanceDays {
+        self.days
+    }
+
+    /// Starts a new long-term period, e.g. at the turn of the year
+    pub fn reset_annual(&mut self) {
+        self.annual = Mean::default();
+        self.days = ExceedanceDays::default();
+    }
+}
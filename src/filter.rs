@@ -0,0 +1,79 @@
+//! Smoothing filters for sensor readings
+
+/// Exponentially weighted moving average
+#[derive(Clone, Copy, Debug)]
+pub struct Ewma {
+    alpha: f32,
+    value: Option<f32>,
+}
+
+impl Ewma {
+    /// Creates a new filter
+    /// * `alpha` - weight of the newest sample, between 0 (frozen) and 1 (no smoothing)
+    pub fn new(alpha: f32) -> Self {
+        Self { alpha: alpha.clamp(0.0, 1.0), value: None }
+    }
+
+    /// Feeds a sample and returns the smoothed value
+    pub fn update(&mut self, sample: f32) -> f32 {
+        let value = match self.value {
+            Some(value) => value + self.alpha * (sample - value),
+            None => sample,
+        };
+        self.value = Some(value);
+        value
+    }
+
+    /// Current smoothed value, if any sample was fed
+    pub fn value(&self) -> Option<f32> {
+        self.value
+    }
+
+    pub fn reset(&mut self) {
+        self.value = None;
+    }
+}
+
+/// Tuned smoothing levels for readings arriving about once a second
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Smoothing {
+    /// Follows changes within a few seconds, keeping some jitter
+    Fast,
+    /// Settles within about 10 seconds
+    #[default]
+    Balanced,
+    /// Steady display values, settling within about a minute
+    Smooth,
+}
+
+impl Smoothing {
+    /// EWMA weight of the preset
+    pub fn alpha(&self) -> f32 {
+        match self {
+            Smoothing::Fast => 0.5,
+            Smoothing::Balanced => 0.2,
+            Smoothing::Smooth => 0.05,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Smoothing::Fast => "fast",
+            Smoothing::Balanced => "balanced",
+            Smoothing::Smooth => "smooth",
+        }
+    }
+
+    /// Parses a preset name as returned by `name()`, e.g. from a configuration file
+    pub fn from_name(name: &str) -> Option<Smoothing> {
+        [Smoothing::Fast, Smoothing::Balanced, Smoothing::Smooth]
+            .into_iter()
+            .find(|preset| preset.name() == name)
+    }
+}
+
+impl From<Smoothing> for Ewma {
+    fn from(preset: Smoothing) -> Self {
+        Ewma::new(preset.alpha())
+    }
+}
@@ -0,0 +1,112 @@
+//! Combination of two co-located sensors into one reading
+
+use crate::health::DivergenceMonitor;
+use crate::OutputFrame;
+
+// Sensors agree if they differ by at most this much in μg/m³...
+const MAX_ABS_DIFFERENCE: f32 = 5.0;
+// ...or by at most this share of their mean, as in the EPA correction of PurpleAir data
+const MAX_RELATIVE_DIFFERENCE: f32 = 0.7;
+
+/// One of the two fused sensors
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unit {
+    A,
+    B,
+}
+
+/// Where a fused reading came from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Source {
+    /// Both sensors agreed and were averaged
+    Both,
+    /// Only this sensor was used, because the other one was missing or diverged
+    Single(Unit),
+}
+
+/// Fused atmospheric concentrations in μg/m³
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fused {
+    pub pm2_5: f32,
+    pub pm10: f32,
+    pub source: Source,
+}
+
+/// Cross-validates two sensors: averages them while their PM2.5 agrees, and otherwise
+/// picks the healthier one. Health is judged from each sensor's CF=1/ATM consistency
+/// first, then from continuity with the previous fused value.
+#[derive(Clone, Debug, Default)]
+pub struct DualSensorFusion {
+    health: [DivergenceMonitor; 2],
+    last: Option<f32>,
+    diverged: bool,
+}
+
+impl DualSensorFusion {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fuses the latest frames of both sensors, `None` for a sensor which didn't report
+    pub fn update(&mut self, a: Option<&OutputFrame>, b: Option<&OutputFrame>) -> Option<Fused> {
+        let a_faulty = a.map(|frame| self.health[0].update(frame));
+        let b_faulty = b.map(|frame| self.health[1].update(frame));
+
+        let fused = match (a, b) {
+            (None, None) => return None,
+            (Some(a), None) => single(a, Unit::A),
+            (None, Some(b)) => single(b, Unit::B),
+            (Some(a), Some(b)) => {
+                let (pm2_5_a, pm2_5_b) = (a.pm2_5_atm as f32, b.pm2_5_atm as f32);
+                let difference = (pm2_5_a - pm2_5_b).abs();
+                let mean = (pm2_5_a + pm2_5_b) / 2.0;
+                self.diverged = difference > MAX_ABS_DIFFERENCE && difference > mean * MAX_RELATIVE_DIFFERENCE;
+
+                if !self.diverged {
+                    Fused {
+                        pm2_5: mean,
+                        pm10: (a.pm10_atm as f32 + b.pm10_atm as f32) / 2.0,
+                        source: Source::Both,
+                    }
+                } else {
+                    let unit = match (a_faulty, b_faulty) {
+                        (Some(false), Some(true)) => Unit::A,
+                        (Some(true), Some(false)) => Unit::B,
+                        _ => match self.last {
+                            Some(last) if (pm2_5_b - last).abs() < (pm2_5_a - last).abs() => Unit::B,
+                            _ => Unit::A,
+                        },
+                    };
+                    match unit {
+                        Unit::A => single(a, Unit::A),
+                        Unit::B => single(b, Unit::B),
+                    }
+                }
+            }
+        };
+
+        self.last = Some(fused.pm2_5);
+        Some(fused)
+    }
+
+    /// Whether the sensors disagreed on the last update where both reported
+    pub fn is_diverged(&self) -> bool {
+        self.diverged
+    }
+
+    /// Whether the CF=1/ATM consistency monitor of a sensor flags a fault
+    pub fn is_faulty(&self, unit: Unit) -> bool {
+        match unit {
+            Unit::A => self.health[0].is_faulty(),
+            Unit::B => self.health[1].is_faulty(),
+        }
+    }
+}
+
+fn single(frame: &OutputFrame, unit: Unit) -> Fused {
+    Fused {
+        pm2_5: frame.pm2_5_atm as f32,
+        pm10: frame.pm10_atm as f32,
+        source: Source::Single(unit),
+    }
+}
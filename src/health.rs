@@ -0,0 +1,299 @@
+//! Indicators of sensor degradation
+
+use crate::OutputFrame;
+
+// Below this CF=1 concentration the sensor reports identical CF=1 and atmospheric values
+const CF1_EQUAL_BELOW: u16 = 30;
+// Slack for rounding in the sensor's own conversion
+const CF1_TOLERANCE: u16 = 2;
+// Range of atmospheric to CF=1 ratios seen on healthy sensors at higher concentrations
+const MIN_ATM_RATIO: f32 = 0.5;
+const MAX_ATM_RATIO: f32 = 1.0;
+
+/// Watches the relationship between CF=1 and atmospheric values, which are derived from the
+/// same measurement and should follow a fixed curve. Sustained deviations are an early sign of
+/// a contaminated optical chamber or failing firmware.
+#[derive(Clone, Debug)]
+pub struct DivergenceMonitor {
+    threshold: u16,
+    score: u16,
+    faulty: bool,
+}
+
+impl DivergenceMonitor {
+    /// Creates a new monitor
+    /// * `threshold` - net number of anomalous frames before a fault is flagged
+    pub fn new(threshold: u16) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            score: 0,
+            faulty: false,
+        }
+    }
+
+    /// Checks a single frame, without history
+    pub fn is_anomalous(frame: &OutputFrame) -> bool {
+        !consistent(frame.pm2_5, frame.pm2_5_atm) || !consistent(frame.pm10, frame.pm10_atm)
+    }
+
+    /// Feeds a frame and returns whether a fault is flagged.
+    /// Anomalous frames raise a score and consistent ones lower it; the fault clears once the score is back to zero.
+    pub fn update(&mut self, frame: &OutputFrame) -> bool {
+        if Self::is_anomalous(frame) {
+            self.score = self.score.saturating_add(1).min(self.threshold);
+        } else {
+            self.score = self.score.saturating_sub(1);
+        }
+
+        if self.score >= self.threshold {
+            self.faulty = true;
+        } else if self.score == 0 {
+            self.faulty = false;
+        }
+        self.faulty
+    }
+
+    pub fn is_faulty(&self) -> bool {
+        self.faulty
+    }
+
+    pub fn reset(&mut self) {
+        self.score = 0;
+        self.faulty = false;
+    }
+}
+
+impl Default for DivergenceMonitor {
+    fn default() -> Self {
+        // About a minute in active mode
+        Self::new(60)
+    }
+}
+
+fn consistent(cf1: u16, atm: u16) -> bool {
+    if atm > cf1.saturating_add(CF1_TOLERANCE) {
+        return false;
+    }
+    if cf1 <= CF1_EQUAL_BELOW {
+        return atm.saturating_add(CF1_TOLERANCE) >= cf1;
+    }
+    let ratio = atm as f32 / cf1 as f32;
+    (MIN_ATM_RATIO..=MAX_ATM_RATIO).contains(&ratio)
+}
+
+const MS_PER_HOUR: u64 = 3_600_000;
+const HOURS_PER_DAY: u64 = 24;
+// Days of daily floors kept, compared as the oldest week against the latest one
+const DRIFT_DAYS: usize = 28;
+const DRIFT_WEEK: usize = 7;
+// Hourly averages required for a day's floor to be meaningful
+const MIN_HOURS_PER_DAY: u8 = 12;
+
+/// Tracks the clean-air floor of PM2.5 over weeks and flags when it creeps upwards,
+/// which happens as dust accumulates in the optical chamber.
+///
+/// Each day's floor is its lowest hourly average. The lowest floor of the latest week is
+/// compared to the lowest floor of the oldest week in a 4-week window, so a single
+/// polluted week doesn't count as drift.
+#[derive(Clone, Debug)]
+pub struct DriftDetector {
+    threshold: f32,
+    current_hour: Option<u64>,
+    hour_sum: f32,
+    hour_count: u32,
+    day_floor: Option<f32>,
+    day_hours: u8,
+    floors: heapless::HistoryBuf<f32, DRIFT_DAYS>,
+}
+
+impl DriftDetector {
+    /// Creates a new detector
+    /// * `threshold` - rise of the floor in μg/m³ considered drift
+    pub fn new(threshold: f32) -> Self {
+        Self {
+            threshold,
+            current_hour: None,
+            hour_sum: 0.0,
+            hour_count: 0,
+            day_floor: None,
+            day_hours: 0,
+            floors: heapless::HistoryBuf::new(),
+        }
+    }
+
+    /// Feeds a PM2.5 concentration in μg/m³
+    /// * `now_ms` - Unix time in milliseconds, days are counted in UTC
+    pub fn update(&mut self, now_ms: u64, pm2_5: f32) {
+        let hour = now_ms / MS_PER_HOUR;
+        if let Some(current) = self.current_hour
+            && current != hour
+        {
+            self.close_hour();
+            if current / HOURS_PER_DAY != hour / HOURS_PER_DAY {
+                self.close_day();
+            }
+        }
+        self.current_hour = Some(hour);
+        self.hour_sum += pm2_5;
+        self.hour_count += 1;
+    }
+
+    /// Feeds the atmospheric PM2.5 concentration of a frame
+    pub fn update_frame(&mut self, now_ms: u64, frame: &OutputFrame) {
+        self.update(now_ms, frame.pm2_5_atm as f32)
+    }
+
+    fn close_hour(&mut self) {
+        if self.hour_count > 0 {
+            let mean = self.hour_sum / self.hour_count as f32;
+            self.day_floor = Some(self.day_floor.map_or(mean, |floor| floor.min(mean)));
+            self.day_hours += 1;
+        }
+        self.hour_sum = 0.0;
+        self.hour_count = 0;
+    }
+
+    fn close_day(&mut self) {
+        if self.day_hours >= MIN_HOURS_PER_DAY
+            && let Some(floor) = self.day_floor
+        {
+            self.floors.write(floor);
+        }
+        self.day_floor = None;
+        self.day_hours = 0;
+    }
+
+    /// Rise of the floor in μg/m³ between the oldest and the latest week, once two weeks of floors are available
+    pub fn drift(&self) -> Option<f32> {
+        let len = self.floors.len();
+        if len < 2 * DRIFT_WEEK {
+            return None;
+        }
+        let lowest = |floors: &mut dyn Iterator<Item = &f32>| floors.fold(f32::MAX, |min, f| min.min(*f));
+        let oldest = lowest(&mut self.floors.oldest_ordered().take(DRIFT_WEEK));
+        let latest = lowest(&mut self.floors.oldest_ordered().skip(len - DRIFT_WEEK));
+        Some(latest - oldest)
+    }
+
+    /// Whether the floor rose by at least the threshold, a hint to clean or replace the sensor
+    pub fn is_drifting(&self) -> bool {
+        self.drift().is_some_and(|drift| drift >= self.threshold)
+    }
+
+    /// Forgets the floors, e.g. after the sensor was cleaned or replaced
+    pub fn reset(&mut self) {
+        *self = Self::new(self.threshold);
+    }
+}
+
+impl Default for DriftDetector {
+    fn default() -> Self {
+        Self::new(3.0)
+    }
+}
+
+/// Rated lifetime of Plantower sensors, given as an MTTF of at least 3 years of operation
+pub const RATED_LIFETIME_HOURS: u32 = 3 * 365 * 24;
+// Share of the rated lifetime after which replacement should be planned
+const INSPECT_PERCENT: u32 = 80;
+
+const RUNTIME_BLOB_VERSION: u8 = 1;
+/// Size of the blob produced by [`RuntimeTracker::to_bytes`]
+pub const RUNTIME_BLOB_SIZE: usize = 8;
+
+/// Maintenance advice derived from the accumulated runtime
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MaintenanceHint {
+    Ok,
+    /// Most of the rated lifetime is used up, plan a replacement
+    PlanReplacement,
+    /// The rated lifetime is exceeded, readings may be unreliable
+    Replace,
+}
+
+/// Accumulates the hours the sensor has been running, i.e. awake with its fan spinning.
+/// The total survives restarts by storing `to_bytes()` in non-volatile memory.
+#[derive(Clone, Debug, Default)]
+pub struct RuntimeTracker {
+    total_s: u32,
+    // Start of the current run and milliseconds already added to the total
+    running_since: Option<(u64, u64)>,
+}
+
+impl RuntimeTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restores a tracker from a blob produced by `to_bytes()`. Returns `None` if the blob is corrupted.
+    pub fn from_bytes(blob: &[u8; RUNTIME_BLOB_SIZE]) -> Option<Self> {
+        if blob[0] != RUNTIME_BLOB_VERSION || crate::checksum(&blob[..6]).to_be_bytes() != blob[6..] {
+            return None;
+        }
+        Some(Self {
+            total_s: u32::from_be_bytes([blob[2], blob[3], blob[4], blob[5]]),
+            running_since: None,
+        })
+    }
+
+    /// Serializes the accumulated runtime, including the current run up to the last call to `tick()`
+    pub fn to_bytes(&self) -> [u8; RUNTIME_BLOB_SIZE] {
+        let mut blob = [0u8; RUNTIME_BLOB_SIZE];
+        blob[0] = RUNTIME_BLOB_VERSION;
+        blob[2..6].copy_from_slice(&self.total_s.to_be_bytes());
+        let sum = crate::checksum(&blob[..6]);
+        blob[6..].copy_from_slice(&sum.to_be_bytes());
+        blob
+    }
+
+    /// Call when the sensor is woken up
+    /// * `now_ms` - timestamp in milliseconds, from any monotonic clock
+    pub fn start(&mut self, now_ms: u64) {
+        if self.running_since.is_none() {
+            self.running_since = Some((now_ms, 0));
+        }
+    }
+
+    /// Call when the sensor is put to sleep
+    pub fn stop(&mut self, now_ms: u64) {
+        self.tick(now_ms);
+        self.running_since = None;
+    }
+
+    /// Adds the current run to the total, e.g. before persisting it periodically
+    pub fn tick(&mut self, now_ms: u64) {
+        if let Some((since, counted_ms)) = self.running_since.as_mut() {
+            let elapsed_ms = now_ms.saturating_sub(*since);
+            // A clock stepped back after a tick counts as no new time
+            let new_s = elapsed_ms.saturating_sub(*counted_ms) / 1000;
+            self.total_s = self.total_s.saturating_add(new_s as u32);
+            *counted_ms += new_s * 1000;
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running_since.is_some()
+    }
+
+    /// Runtime in hours, as of the last call to `tick()` or `stop()`
+    pub fn hours(&self) -> f32 {
+        self.total_s as f32 / 3600.0
+    }
+
+    /// Maintenance advice against Plantower's rated lifetime
+    pub fn hint(&self) -> MaintenanceHint {
+        self.hint_for(RATED_LIFETIME_HOURS)
+    }
+
+    /// Maintenance advice against a custom lifetime, e.g. shortened for dusty environments
+    pub fn hint_for(&self, lifetime_hours: u32) -> MaintenanceHint {
+        let hours = self.total_s / 3600;
+        if hours >= lifetime_hours {
+            MaintenanceHint::Replace
+        } else if hours as u64 * 100 >= lifetime_hours as u64 * INSPECT_PERCENT as u64 {
+            MaintenanceHint::PlanReplacement
+        } else {
+            MaintenanceHint::Ok
+        }
+    }
+}
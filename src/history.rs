@@ -0,0 +1,80 @@
+//! Bounded history of recent frames and statistics over it
+
+use heapless::HistoryBuf;
+
+use crate::{Field, OutputFrame};
+
+/// The last `N` frames, oldest ones overwritten first
+#[derive(Clone, Debug)]
+pub struct History<const N: usize> {
+    frames: HistoryBuf<OutputFrame, N>,
+}
+
+impl<const N: usize> History<N> {
+    pub const fn new() -> Self {
+        Self { frames: HistoryBuf::new() }
+    }
+
+    pub fn push(&mut self, frame: OutputFrame) {
+        self.frames.write(frame);
+    }
+
+    /// Most recently pushed frame
+    pub fn latest(&self) -> Option<&OutputFrame> {
+        self.frames.recent()
+    }
+
+    /// Frames from oldest to newest
+    pub fn iter(&self) -> impl Iterator<Item = &OutputFrame> {
+        self.frames.oldest_ordered()
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    /// Value of `field` below which `percent` percent of the stored values fall, using the nearest-rank method.
+    /// Selection runs on a copy of the values on the stack, without sorting them.
+    pub fn percentile(&self, field: Field, percent: u8) -> Option<u16> {
+        if self.frames.is_empty() {
+            return None;
+        }
+
+        let mut values = [0u16; N];
+        let len = self.frames.len();
+        for (value, frame) in values.iter_mut().zip(self.frames.as_slice()) {
+            *value = frame[field];
+        }
+
+        let percent = percent.min(100) as usize;
+        let rank = (percent * len).div_ceil(100).max(1);
+        Some(*values[..len].select_nth_unstable(rank - 1).1)
+    }
+
+    /// Median of `field`
+    pub fn p50(&self, field: Field) -> Option<u16> {
+        self.percentile(field, 50)
+    }
+
+    pub fn p95(&self, field: Field) -> Option<u16> {
+        self.percentile(field, 95)
+    }
+
+    pub fn p99(&self, field: Field) -> Option<u16> {
+        self.percentile(field, 99)
+    }
+}
+
+impl<const N: usize> Default for History<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,134 @@
+//! Honeywell HPMA115S0, whose UART protocol frames its auto-send readings like Plantower
+//! sensors but uses its own commands and acknowledgements
+
+use embedded_io::{Read, Write};
+
+use crate::codec::PlantowerCodec;
+use crate::{protocol, Error, OutputFrame, Packet, SyncStats};
+
+const HEAD_SEND: u8 = 0x68;
+const HEAD_RESPONSE: u8 = 0x40;
+const ACK: [u8; 2] = [0xa5, 0xa5];
+const NACK: [u8; 2] = [0x96, 0x96];
+const READ_MEASUREMENT: u8 = 0x04;
+// Bytes searched for an acknowledgement, leaving room for an auto-send frame sent meanwhile
+const ACK_SEARCH_BYTES: usize = 2 * 32;
+
+/// Command understood by the HPMA115S0
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HpmaCommand {
+    /// Turns the fan and laser on
+    StartMeasurement,
+    /// Turns the fan and laser off
+    StopMeasurement,
+    /// Sends readings only when asked with `read_measurement()`
+    StopAutoSend,
+    /// Sends a reading every second
+    EnableAutoSend,
+}
+
+impl HpmaCommand {
+    /// Encodes the command into a frame ready to be sent
+    pub fn encode(&self) -> [u8; 4] {
+        let cmd = match self {
+            HpmaCommand::StartMeasurement => 0x01,
+            HpmaCommand::StopMeasurement => 0x02,
+            HpmaCommand::StopAutoSend => 0x20,
+            HpmaCommand::EnableAutoSend => 0x40,
+        };
+        encode(cmd)
+    }
+}
+
+/// Header, length and command followed by the checksum, which brings the sum of all bytes to zero
+fn encode(cmd: u8) -> [u8; 4] {
+    let mut frame = [HEAD_SEND, 0x01, cmd, 0];
+    frame[3] = checksum(&frame[..3]);
+    frame
+}
+
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |sum, b| sum.wrapping_sub(*b))
+}
+
+/// Driver for the HPMA115S0, reporting PM2.5 and PM10 in an [`OutputFrame`] so it can share the
+/// processing of Plantower readings. Both the standard and atmospheric fields hold the sensor's
+/// single reading, everything else is left at zero.
+pub struct Hpma115s0<UART> {
+    uart: UART,
+    sync: SyncStats,
+}
+
+impl<UART: Read + Write> Hpma115s0<UART> {
+    /// Creates a new sensor instance
+    /// * `uart` - UART implementing embedded-io Read + Write traits, at 9600 8N1
+    pub fn new(uart: UART) -> Self {
+        Self { uart, sync: SyncStats::default() }
+    }
+
+    /// Returns the wrapped UART
+    pub fn release(self) -> UART {
+        self.uart
+    }
+
+    /// Reads the next auto-send frame. Blocks until one is available.
+    pub fn read(&mut self) -> Result<OutputFrame, Error<UART::Error>> {
+        self.sync = SyncStats::default();
+        loop {
+            if let Packet::Frame(frame) = protocol::read_packet!(PlantowerCodec, self.sync, |buf| self.uart.read_exact(buf))? {
+                return Ok(reading(frame.pm2_5, frame.pm10));
+            }
+        }
+    }
+
+    /// Asks for a reading, e.g. once auto-send is stopped
+    pub fn read_measurement(&mut self) -> Result<OutputFrame, Error<UART::Error>> {
+        self.uart.write_all(&encode(READ_MEASUREMENT)).map_err(Error::Write)?;
+
+        // Slide over the received bytes until they end with the response header
+        let mut response = [0u8; 8];
+        for _ in 0..ACK_SEARCH_BYTES {
+            response.copy_within(1..3, 0);
+            self.uart.read_exact(&mut response[2..3]).map_err(Error::Read)?;
+            if response[1..3] == NACK {
+                return Err(Error::IncorrectResponse(None));
+            }
+            if response[..3] == [HEAD_RESPONSE, 0x05, READ_MEASUREMENT] {
+                self.uart.read_exact(&mut response[3..]).map_err(Error::Read)?;
+                if checksum(&response) != 0 {
+                    return Err(Error::ChecksumError);
+                }
+                let word = |offset: usize| u16::from_be_bytes([response[offset], response[offset + 1]]);
+                return Ok(reading(word(3), word(5)));
+            }
+        }
+        Err(Error::NoResponse)
+    }
+
+    /// Sends a command and waits for its acknowledgement.
+    /// A negative acknowledgement fails with `Error::IncorrectResponse`.
+    pub fn send(&mut self, command: HpmaCommand) -> Result<(), Error<UART::Error>> {
+        self.uart.write_all(&command.encode()).map_err(Error::Write)?;
+
+        let mut previous = 0;
+        for _ in 0..ACK_SEARCH_BYTES {
+            let mut byte = [0u8];
+            self.uart.read_exact(&mut byte).map_err(Error::Read)?;
+            match [previous, byte[0]] {
+                ACK => return Ok(()),
+                NACK => return Err(Error::IncorrectResponse(None)),
+                _ => previous = byte[0],
+            }
+        }
+        Err(Error::NoResponse)
+    }
+
+    /// Resync statistics of the last `read()` call
+    pub fn sync_stats(&self) -> SyncStats {
+        self.sync
+    }
+}
+
+fn reading(pm2_5: u16, pm10: u16) -> OutputFrame {
+    OutputFrame::builder().pm2_5(pm2_5).pm10(pm10).pm2_5_atm(pm2_5).pm10_atm(pm10).build()
+}
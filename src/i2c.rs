@@ -0,0 +1,43 @@
+//! PMSA003I, which reports the usual 32-byte frame over I2C instead of a UART
+
+use embedded_hal::i2c::I2c;
+use embedded_io::ReadExactError;
+
+use crate::{Error, OutputFrame, OUTPUT_FRAME_SIZE};
+
+/// Default I2C address of the PMSA003I
+pub const PMSA003I_ADDRESS: u8 = 0x12;
+
+/// Driver for the PMSA003I, e.g. on the Adafruit breakout
+pub struct I2cPmsA003i<I2C> {
+    i2c: I2C,
+    address: u8,
+}
+
+impl<I2C: I2c> I2cPmsA003i<I2C> {
+    /// Creates a new sensor instance using the default address
+    /// * `i2c` - I2C bus implementing embedded-hal I2c trait
+    pub fn new(i2c: I2C) -> Self {
+        Self::new_with_address(i2c, PMSA003I_ADDRESS)
+    }
+
+    /// Creates a new sensor instance at a custom address
+    pub fn new_with_address(i2c: I2C, address: u8) -> Self {
+        Self { i2c, address }
+    }
+
+    /// Reads the latest frame. Bus errors are reported as `Error::Read`.
+    pub fn read(&mut self) -> Result<OutputFrame, Error<I2C::Error>> {
+        let mut buffer = [0u8; OUTPUT_FRAME_SIZE];
+        self.i2c
+            .read(self.address, &mut buffer)
+            .map_err(|e| Error::Read(ReadExactError::Other(e)))?;
+
+        OutputFrame::from_buffer(&buffer)
+    }
+
+    /// Returns the wrapped bus
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+}
@@ -1,32 +1,329 @@
-#![no_std]
+#![cfg_attr(not(feature = "std"), no_std)]
 
-use embedded_io::{Read, Write, ErrorType, ReadExactError};
+use embedded_io::{ErrorKind, Read, ReadReady, Write, ErrorType, ReadExactError};
+
+use crate::codec::{FrameCodec, PlantowerCodec, MAX_FRAME_SIZE};
+use crate::parser::FrameParser;
+
+pub mod aqi;
+pub mod bridge;
+#[cfg(feature = "async")]
+pub mod asynch;
+#[cfg(feature = "capi")]
+pub mod capi;
+pub mod capture;
+pub mod codec;
+pub mod dedup;
+pub mod dma;
+pub mod downsample;
+pub mod driver;
+#[cfg(feature = "eh02")]
+pub mod eh02;
+pub mod events;
+pub mod exposure;
+pub mod filter;
+pub mod fusion;
+pub mod health;
+pub mod history;
+#[cfg(feature = "hpma")]
+pub mod hpma;
+#[cfg(feature = "pmsa003i")]
+pub mod i2c;
+#[cfg(all(feature = "std", target_os = "linux"))]
+pub mod linux_gpio;
+#[cfg(feature = "nb")]
+pub mod nb_reader;
+pub mod parser;
+pub mod pins;
+pub mod protocol;
+#[cfg(feature = "std")]
+pub mod serial;
+#[cfg(feature = "sim")]
+pub mod sim;
+#[cfg(feature = "soft-serial")]
+pub mod soft_serial;
+#[cfg(feature = "async")]
+pub mod stream;
+#[cfg(feature = "tokio")]
+pub mod tokio_uart;
+pub mod transport;
+pub mod variant;
 
 const CMD_FRAME_SIZE: usize = 7;
 const OUTPUT_FRAME_SIZE: usize = 32;
 const RESPONSE_FRAME_SIZE: usize = 8;
 const CHECKSUM_SIZE: usize = 2;
+const OUTPUT_LENGTH: u16 = (OUTPUT_FRAME_SIZE - 4) as u16;
+const RESPONSE_LENGTH: u16 = (RESPONSE_FRAME_SIZE - 4) as u16;
+const COMMAND_QUEUE_SIZE: usize = 4;
+// Size of reads with `ReadStrategy::Chunked`, dividing both packet sizes
+const CHUNK_SIZE: usize = RESPONSE_FRAME_SIZE;
+// Upper end of the sensor's maximum range in μg/m³
+const MAX_CONCENTRATION: u16 = 1000;
+// Upper end of the range where the datasheet guarantees consistency, in μg/m³
+const EFFECTIVE_RANGE: u16 = 500;
+// Data frames tolerated while waiting for the response to a queued command
+const ACK_FRAME_BUDGET: u8 = 3;
 
-type Response = [u8; RESPONSE_FRAME_SIZE];
+#[cfg(all(feature = "passive-only", feature = "active-only"))]
+compile_error!("features `passive-only` and `active-only` are mutually exclusive");
 
 pub const MN1: u8 = 0x42;
 pub const MN2: u8 = 0x4D;
-const PASSIVE_MODE_RESPONSE: Response = [MN1, MN1, 0x00, 0x04, 0xE1, 0x00, 0x01, 0x74];
-const ACTIVE_MODE_RESPONSE: Response = [MN1, MN2, 0x00, 0x04, 0xE1, 0x01, 0x01, 0x75];
-const SLEEP_RESPONSE: Response = [MN1, MN2, 0x00, 0x04, 0xE4, 0x00, 0x01, 0x77];
 
 #[derive(Debug)]
 pub enum Error<E> {
     Read(ReadExactError<E>),
     Write(E),
     ChecksumError,
-    IncorrectResponse,
+    /// A packet that isn't the expected one. Carries the response when a valid one arrived for
+    /// another command or with other data, `None` when the bytes weren't a packet at all.
+    /// The raw bytes stay available from `PmsX003Sensor::last_packet()`.
+    IncorrectResponse(Option<ResponseFrame>),
     NoResponse,
+    FrameLengthMismatch { expected: u16, got: u16 },
+    OutOfRange,
+    /// The sensor stayed silent for longer than the configured timeout
+    Timeout,
+    /// The command isn't understood by the sensor model, see [`FrameCodec::supports`]
+    UnsupportedByVariant(Command),
+    /// The frame reports a failure, only with `PmsX003Sensor::set_faults_as_errors`. The frame
+    /// is still available from `PmsX003Sensor::last_packet()`.
+    SensorFault(SensorFaults),
+}
+
+impl<E: embedded_io::Error> Error<E> {
+    /// Kind of the UART failure behind a `Read` or `Write` error, `None` for protocol errors.
+    /// HALs usually report FIFO overruns, framing and noise errors as `ErrorKind::Other` or
+    /// `ErrorKind::InvalidData`, and a closed port, i.e. end of file, counts as `ErrorKind::NotConnected`.
+    pub fn transport_kind(&self) -> Option<ErrorKind> {
+        match self {
+            Error::Read(ReadExactError::UnexpectedEof) => Some(ErrorKind::NotConnected),
+            Error::Read(ReadExactError::Other(e)) | Error::Write(e) => Some(e.kind()),
+            _ => None,
+        }
+    }
+}
+
+/// Error of parsing bytes received outside the driver, e.g. with [`parser::FrameParser`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    ChecksumError,
+}
+
+impl<E> From<ParseError> for Error<E> {
+    fn from(e: ParseError) -> Self {
+        match e {
+            ParseError::ChecksumError => Error::ChecksumError,
+        }
+    }
+}
+
+/// Failure of a [`PmsX003Sensor::transaction`]
+#[derive(Debug)]
+pub struct TransactionError<E> {
+    /// Number of leading commands that took effect before the failure
+    pub completed: usize,
+    /// Command that failed
+    pub command: Command,
+    pub error: Error<E>,
+}
+
+/// How thoroughly received frames are validated
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ParseMode {
+    /// Header and checksum only, keeping every frame that wasn't corrupted in transit
+    #[default]
+    Lenient,
+    /// Header, frame length, checksum and plausibility of the reported values
+    Strict,
+}
+
+/// How the driver pulls bytes from the UART
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReadStrategy {
+    /// Reads byte by byte while looking for the start of a frame
+    #[default]
+    ByteSync,
+    /// Only reads fixed 8-byte chunks, buffering bytes past the end of a packet.
+    /// Suits DMA-based UARTs such as the nRF52 UARTE, where every read sets up an EasyDMA
+    /// transfer. Responses are 8 bytes and frames 32, so chunks stay aligned to packets once
+    /// synchronized; until then a read may wait for the next packet to complete a chunk.
+    Chunked,
+}
+
+/// Commands understood by the sensor
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    Sleep,
+    Wake,
+    #[cfg(not(feature = "active-only"))]
+    SetPassive,
+    #[cfg(not(feature = "passive-only"))]
+    SetActive,
+    #[cfg(not(feature = "active-only"))]
+    Request,
+}
+
+impl Command {
+    /// Encodes the command into a frame ready to be sent
+    pub fn encode(&self) -> [u8; CMD_FRAME_SIZE] {
+        match self {
+            Command::Sleep => create_command(0xe4, 0),
+            Command::Wake => create_command(0xe4, 1),
+            #[cfg(not(feature = "active-only"))]
+            Command::SetPassive => create_command(0xe1, 0),
+            #[cfg(not(feature = "passive-only"))]
+            Command::SetActive => create_command(0xe1, 1),
+            #[cfg(not(feature = "active-only"))]
+            Command::Request => create_command(0xe2, 0),
+        }
+    }
+
+    /// Recognizes a command frame, e.g. one captured between a host and the sensor. Returns `None`
+    /// for invalid frames and commands the driver doesn't know.
+    pub fn decode(frame: &[u8; CMD_FRAME_SIZE]) -> Option<Self> {
+        let sum = checksum(&frame[..CMD_FRAME_SIZE - CHECKSUM_SIZE]);
+        if frame[..2] != [MN1, MN2] || sum.to_be_bytes() != frame[CMD_FRAME_SIZE - CHECKSUM_SIZE..] {
+            return None;
+        }
+        match (frame[2], u16::from_be_bytes([frame[3], frame[4]])) {
+            (0xe4, 0) => Some(Command::Sleep),
+            (0xe4, 1) => Some(Command::Wake),
+            #[cfg(not(feature = "active-only"))]
+            (0xe1, 0) => Some(Command::SetPassive),
+            #[cfg(not(feature = "passive-only"))]
+            (0xe1, 1) => Some(Command::SetActive),
+            #[cfg(not(feature = "active-only"))]
+            (0xe2, 0) => Some(Command::Request),
+            _ => None,
+        }
+    }
+
+    /// Whether the sensor acknowledges the command. Wake and request are answered with data frames only.
+    fn expects_response(&self) -> bool {
+        match self {
+            Command::Sleep => true,
+            #[cfg(not(feature = "active-only"))]
+            Command::SetPassive => true,
+            #[cfg(not(feature = "passive-only"))]
+            Command::SetActive => true,
+            _ => false,
+        }
+    }
+}
+
+/// Response sent by the sensor to acknowledge a command
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ResponseFrame {
+    /// Command byte being acknowledged
+    pub cmd: u8,
+    /// Echo of the command's data byte, e.g. the mode that was set
+    pub data: u8,
+}
+
+impl ResponseFrame {
+    /// Parses a response, validating its header, length and checksum
+    pub fn from_buffer<E>(buffer: &[u8; RESPONSE_FRAME_SIZE]) -> Result<Self, Error<E>> {
+        if buffer[0] != MN1 || buffer[1] != MN2 || u16::from_be_bytes([buffer[2], buffer[3]]) != RESPONSE_LENGTH {
+            return Err(Error::IncorrectResponse(None));
+        }
+
+        let sum = checksum(&buffer[..RESPONSE_FRAME_SIZE - CHECKSUM_SIZE]);
+        if sum != u16::from_be_bytes([buffer[6], buffer[7]]) {
+            return Err(Error::ChecksumError);
+        }
+
+        Ok(Self { cmd: buffer[4], data: buffer[5] })
+    }
+
+    /// Whether this is the response a genuine Plantower sensor sends for `command`
+    pub fn acknowledges(&self, command: Command) -> bool {
+        ProtocolProfile::PLANTOWER.expected_ack(command) == Some(*self)
+    }
+}
+
+/// Protocol details that differ between Plantower sensors and their clones
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProtocolProfile {
+    /// Response expected for `Command::Sleep`
+    pub sleep_ack: ResponseFrame,
+    /// Response expected for `Command::SetPassive`
+    pub passive_ack: ResponseFrame,
+    /// Response expected for `Command::SetActive`
+    pub active_ack: ResponseFrame,
+}
+
+impl ProtocolProfile {
+    /// Genuine Plantower sensors, echoing the command and data bytes
+    pub const PLANTOWER: ProtocolProfile = ProtocolProfile {
+        sleep_ack: ResponseFrame { cmd: 0xe4, data: 0 },
+        passive_ack: ResponseFrame { cmd: 0xe1, data: 0 },
+        active_ack: ResponseFrame { cmd: 0xe1, data: 1 },
+    };
+
+    /// Response expected for `command`, if the sensor acknowledges it
+    pub fn expected_ack(&self, command: Command) -> Option<ResponseFrame> {
+        match command {
+            Command::Sleep => Some(self.sleep_ack),
+            #[cfg(not(feature = "active-only"))]
+            Command::SetPassive => Some(self.passive_ack),
+            #[cfg(not(feature = "passive-only"))]
+            Command::SetActive => Some(self.active_ack),
+            _ => None,
+        }
+    }
+}
+
+impl Default for ProtocolProfile {
+    fn default() -> Self {
+        Self::PLANTOWER
+    }
+}
+
+/// What happened on the wire while looking for the last packet, e.g. to log along with a read error.
+/// Steady byte loss points at wiring or baud rate, unknown packets at firmware, sporadic loss at noise.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SyncStats {
+    /// Bytes dropped while searching for a header
+    pub discarded_bytes: u32,
+    /// Packets with a valid header that were skipped, e.g. because of an unknown frame length
+    pub skipped_packets: u16,
+    /// Whether the first header byte was seen without the second one following it
+    pub partial_header: bool,
+    /// Frames dropped because of an invalid checksum, see `PmsX003Sensor::set_checksum_retries`
+    pub corrupted_frames: u16,
+    /// UART errors recovered from, see `PmsX003Sensor::set_on_transport_error`
+    pub transport_errors: u16,
+}
+
+/// Anything the sensor can send: a data frame or a command response
+enum Packet {
+    Frame(OutputFrame),
+    Response(ResponseFrame),
 }
 
 /// Sensor interface
-pub struct PmsX003Sensor<UART> {
+pub struct PmsX003Sensor<UART, C = PlantowerCodec> {
     uart: UART,
+    codec: C,
+    warmup_discard: u8,
+    pending_discard: u8,
+    checksum_retries: u8,
+    queue: [Option<Command>; COMMAND_QUEUE_SIZE],
+    awaiting_ack: Option<(Command, u8)>,
+    failed_command: Option<Command>,
+    parse_mode: ParseMode,
+    read_strategy: ReadStrategy,
+    chunk: [u8; CHUNK_SIZE],
+    chunk_pos: usize,
+    packet: [u8; MAX_FRAME_SIZE],
+    packet_len: usize,
+    sync: SyncStats,
+    profile: ProtocolProfile,
+    poll_parser: FrameParser,
+    on_transport_error: Option<fn(&mut UART, ErrorKind) -> bool>,
+    faults_as_errors: bool,
 }
 
 impl<UART> PmsX003Sensor<UART>
@@ -34,98 +331,480 @@ where
     UART: Read + Write + ErrorType,
 {
     /// Creates a new sensor instance
-    /// * `uart` - UART implementing embedded-io Read + Write traits
+    /// * `uart` - UART implementing embedded-io Read + Write traits. A `&mut` borrow works too,
+    ///   handing the UART back to other peripherals once the sensor is dropped.
     pub fn new(uart: UART) -> Self {
-        Self { uart }
+        Self::with_codec(uart, PlantowerCodec)
+    }
+}
+
+#[cfg(feature = "nb")]
+impl<TX, RX> PmsX003Sensor<transport::NbUart<TX, RX>>
+where
+    TX: embedded_hal_nb::serial::Write<u8>,
+    RX: embedded_hal_nb::serial::Read<u8>,
+{
+    /// Creates a new sensor instance from separate UART halves
+    /// * `tx` - transmitter implementing embedded-hal-nb serial Write trait
+    /// * `rx` - receiver implementing embedded-hal-nb serial Read trait
+    pub fn new_tx_rx(tx: TX, rx: RX) -> Self {
+        Self::new(transport::NbUart::new(tx, rx))
+    }
+
+    /// Returns the wrapped UART halves
+    pub fn release_tx_rx(self) -> (TX, RX) {
+        self.release().release()
+    }
+}
+
+#[cfg(feature = "nb")]
+impl<RX: embedded_hal_nb::serial::Read<u8>> PmsX003Sensor<transport::NbUart<transport::NotConnected, RX>> {
+    /// Creates a new sensor instance with only the sensor's TXD pin wired.
+    /// The sensor stays in active mode, so only `read()` works; commands fail with `Error::Write`.
+    pub fn new_rx_only(rx: RX) -> Self {
+        Self::new_tx_rx(transport::NotConnected, rx)
+    }
+}
+
+#[cfg(feature = "nb")]
+impl<TX: embedded_hal_nb::serial::Write<u8>> PmsX003Sensor<transport::NbUart<TX, transport::NotConnected>> {
+    /// Creates a new sensor instance with only the sensor's RXD pin wired.
+    /// `wake()` and `request()` work, while commands acknowledged by the sensor fail with `Error::Read`.
+    pub fn new_tx_only(tx: TX) -> Self {
+        Self::new_tx_rx(tx, transport::NotConnected)
+    }
+}
+
+impl<UART, C> PmsX003Sensor<UART, C>
+where
+    UART: Read + Write + ErrorType,
+    C: FrameCodec,
+{
+    /// Creates a new instance for a sensor sending frames in another format
+    /// * `uart` - UART implementing embedded-io Read + Write traits
+    /// * `codec` - format of the sensor's data frames
+    pub fn with_codec(uart: UART, codec: C) -> Self {
+        Self {
+            uart,
+            codec,
+            warmup_discard: 0,
+            pending_discard: 0,
+            checksum_retries: 0,
+            queue: [None; COMMAND_QUEUE_SIZE],
+            awaiting_ack: None,
+            failed_command: None,
+            parse_mode: ParseMode::Lenient,
+            read_strategy: ReadStrategy::ByteSync,
+            chunk: [0; CHUNK_SIZE],
+            chunk_pos: CHUNK_SIZE,
+            packet: [0; MAX_FRAME_SIZE],
+            packet_len: 0,
+            sync: SyncStats::default(),
+            profile: ProtocolProfile::PLANTOWER,
+            poll_parser: FrameParser::new(),
+            on_transport_error: None,
+            faults_as_errors: false,
+        }
+    }
+
+    /// Sets how thoroughly `read()` validates frames. Defaults to `ParseMode::Lenient`.
+    pub fn set_parse_mode(&mut self, mode: ParseMode) {
+        self.parse_mode = mode;
+    }
+
+    /// Sets whether `read()` fails with `Error::SensorFault` on frames reporting a failure, rather
+    /// than leaving it to `OutputFrame::fault()`. Defaults to false.
+    pub fn set_faults_as_errors(&mut self, enabled: bool) {
+        self.faults_as_errors = enabled;
+    }
+
+    /// Sets how many frames `read()` drops after `wake()` while the fan spins up and readings settle.
+    /// Defaults to 0.
+    pub fn set_warmup_discard(&mut self, frames: u8) {
+        self.warmup_discard = frames;
+    }
+
+    /// Sets how many corrupted frames `read()` drops, resyncing on the next header, before failing
+    /// with `Error::ChecksumError`. Helps with software UARTs which drop or duplicate bytes.
+    /// Defaults to 0.
+    pub fn set_checksum_retries(&mut self, retries: u8) {
+        self.checksum_retries = retries;
+    }
+
+    /// Sets how `read()` pulls bytes from the UART. Defaults to `ReadStrategy::ByteSync`.
+    pub fn set_read_strategy(&mut self, strategy: ReadStrategy) {
+        self.read_strategy = strategy;
+        self.chunk_pos = CHUNK_SIZE;
     }
 
-    fn read_from_device<T: AsMut<[u8]>>(&mut self, mut buffer: T) -> Result<T, Error<UART::Error>> {
-        let buf = buffer.as_mut();
-        
-        // Find the magic numbers (0x42, 0x4D) at the start of a frame
-        let mut temp_buf = [0u8; 1];
+    /// Sets the responses expected for commands, e.g. for clones echoing different data bytes.
+    /// Defaults to `ProtocolProfile::PLANTOWER`.
+    pub fn set_protocol_profile(&mut self, profile: ProtocolProfile) {
+        self.profile = profile;
+    }
+
+    /// Sets a function called by `read()` when the UART fails, e.g. to clear an overrun flag or
+    /// reopen a port. Returning `true` drops the partial packet, see `reconnect()`, and carries on
+    /// reading; returning `false` fails the read. Defaults to none, failing every time.
+    pub fn set_on_transport_error(&mut self, hook: fn(&mut UART, ErrorKind) -> bool) {
+        self.on_transport_error = Some(hook);
+    }
+
+    /// Drops partially received data once the UART was recovered, e.g. after a FIFO overrun,
+    /// so the next read resyncs on a fresh header. Settings and queued commands are kept, and a
+    /// command still awaiting its response is reported by `take_failed_command()`.
+    pub fn reconnect(&mut self) {
+        self.chunk_pos = CHUNK_SIZE;
+        self.poll_parser = FrameParser::new();
+        if let Some((command, _)) = self.awaiting_ack.take() {
+            self.failed_command = Some(command);
+        }
+    }
+
+    /// Resync statistics of the last `read()`, `wait_for_ack()` or `read_raw_response()` call
+    pub fn sync_stats(&self) -> SyncStats {
+        self.sync
+    }
+
+    /// Returns the wrapped UART, e.g. to reconfigure or power it down before building a new
+    /// sensor. Queued commands and bytes buffered by `ReadStrategy::Chunked` are dropped.
+    pub fn release(self) -> UART {
+        self.uart
+    }
+
+    /// The wrapped UART, e.g. to inspect its configuration
+    pub fn inner(&self) -> &UART {
+        &self.uart
+    }
+
+    /// The wrapped UART, e.g. to change its settings or flush its FIFOs through the HAL.
+    /// Bytes read from it directly are lost to the driver, which resyncs on the next frame.
+    pub fn inner_mut(&mut self) -> &mut UART {
+        &mut self.uart
+    }
+
+    /// Raw bytes of the last packet received, header and checksum included, e.g. to relay frames
+    /// unchanged or to decode fields specific to a model
+    pub fn last_packet(&self) -> &[u8] {
+        &self.packet[..self.packet_len]
+    }
+
+    fn read_bytes(&mut self, buf: &mut [u8]) -> Result<(), ReadExactError<UART::Error>> {
+        if self.read_strategy == ReadStrategy::ByteSync {
+            self.uart.read_exact(buf)?;
+        } else {
+            let mut filled = 0;
+            while filled < buf.len() {
+                if self.chunk_pos == CHUNK_SIZE {
+                    self.uart.read_exact(&mut self.chunk)?;
+                    self.chunk_pos = 0;
+                }
+                let n = (buf.len() - filled).min(CHUNK_SIZE - self.chunk_pos);
+                buf[filled..filled + n].copy_from_slice(&self.chunk[self.chunk_pos..self.chunk_pos + n]);
+                self.chunk_pos += n;
+                filled += n;
+            }
+        }
+
+        if let Some(packet) = self.packet.get_mut(self.packet_len..self.packet_len + buf.len()) {
+            packet.copy_from_slice(buf);
+            self.packet_len += buf.len();
+        }
+        Ok(())
+    }
+
+    // Drops the bytes of `last_packet()` read before the last `held` ones, which were skipped
+    fn keep_packet_tail(&mut self, held: usize) {
+        self.packet.copy_within(self.packet_len - held..self.packet_len, 0);
+        self.packet_len = held;
+    }
+
+    /// Reads the next packet, using the frame length field to tell data frames from responses.
+    /// Frames with an unknown length are skipped.
+    fn read_packet(&mut self) -> Result<Packet, Error<UART::Error>> {
+        protocol::read_packet!(self.codec, self.sync, |buf| self.read_bytes(buf), |held| self.keep_packet_tail(held))
+    }
+
+    /// Reads sensor status. Blocks until status is available.
+    /// Queued commands are transmitted and their responses consumed along the way.
+    pub fn read(&mut self) -> Result<OutputFrame, Error<UART::Error>> {
+        self.sync = SyncStats::default();
+        let mut retries = self.checksum_retries;
         loop {
-            // Read first magic number
-            loop {
-                match self.uart.read_exact(&mut temp_buf) {
-                    Ok(()) => {
-                        if temp_buf[0] == MN1 {
-                            break;
+            let packet = match self.transmit_queued().and_then(|_| self.read_packet()) {
+                Err(e) if self.recover(&e) => continue,
+                // Corrupted frames count towards the warm-up as well
+                Err(Error::ChecksumError) if self.pending_discard > 0 => {
+                    self.sync.corrupted_frames = self.sync.corrupted_frames.saturating_add(1);
+                    self.pending_discard -= 1;
+                    continue;
+                }
+                Err(Error::ChecksumError) if retries > 0 => {
+                    self.sync.corrupted_frames = self.sync.corrupted_frames.saturating_add(1);
+                    retries -= 1;
+                    continue;
+                }
+                packet => packet?,
+            };
+
+            match packet {
+                Packet::Frame(frame) => {
+                    if let Some((command, budget)) = self.awaiting_ack.take() {
+                        if budget > 1 {
+                            self.awaiting_ack = Some((command, budget - 1));
+                        } else {
+                            self.failed_command = Some(command);
                         }
                     }
-                    Err(e) => return Err(Error::Read(e)),
+                    if self.pending_discard > 0 {
+                        self.pending_discard -= 1;
+                        continue;
+                    }
+                    frame.validate(self.parse_mode, self.codec.frame_length())?;
+                    self.check_fault(&frame)?;
+                    return Ok(frame);
                 }
-            }
-            
-            // Read second magic number
-            match self.uart.read_exact(&mut temp_buf) {
-                Ok(()) => {
-                    if temp_buf[0] == MN2 {
-                        // Found both magic numbers, set them in buffer and read the rest
-                        buf[0] = MN1;
-                        buf[1] = MN2;
-                        match self.uart.read_exact(&mut buf[2..]) {
-                            Ok(()) => break,
-                            Err(e) => return Err(Error::Read(e)),
+                Packet::Response(response) => {
+                    match self.awaiting_ack {
+                        Some((command, _)) if self.profile.expected_ack(command) == Some(response) => {
+                            self.awaiting_ack = None
+                        }
+                        Some((command, _)) => {
+                            self.awaiting_ack = None;
+                            self.failed_command = Some(command);
                         }
+                        // Stale response to an earlier command
+                        None => {}
                     }
-                    // If second byte wasn't MN2, continue looking for MN1
                 }
-                Err(e) => return Err(Error::Read(e)),
             }
         }
-        
-        Ok(buffer)
     }
 
-    /// Reads sensor status. Blocks until status is available.
-    pub fn read(&mut self) -> Result<OutputFrame, Error<UART::Error>> {
-        OutputFrame::from_buffer(&self.read_from_device([0_u8; OUTPUT_FRAME_SIZE])?)
+    /// Lets the transport error hook decide whether `read()` carries on after `e`
+    fn recover(&mut self, e: &Error<UART::Error>) -> bool {
+        let (Some(hook), Some(kind)) = (self.on_transport_error, e.transport_kind()) else {
+            return false;
+        };
+        if !hook(&mut self.uart, kind) {
+            return false;
+        }
+        self.sync.transport_errors = self.sync.transport_errors.saturating_add(1);
+        self.reconnect();
+        true
     }
 
-    /// Sleep mode. May fail because of incorrect response because of race condition between response and air quality status
+    fn check_fault(&self, frame: &OutputFrame) -> Result<(), Error<UART::Error>> {
+        match frame.fault() {
+            Some(fault) if self.faults_as_errors => Err(Error::SensorFault(fault)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Reads the bytes received so far without blocking, returning `Ok(None)` until a frame is complete.
+    /// Meant to be called from a superloop. Command responses are skipped, and frames are expected
+    /// in the standard 32-byte format whatever the codec.
+    pub fn poll_read(&mut self) -> Result<Option<OutputFrame>, Error<UART::Error>>
+    where
+        UART: ReadReady,
+    {
+        let mut byte = [0u8; 1];
+        while self.uart.read_ready().map_err(|e| Error::Read(ReadExactError::Other(e)))? {
+            if self.uart.read(&mut byte).map_err(|e| Error::Read(ReadExactError::Other(e)))? == 0 {
+                break;
+            }
+            let Some(frame) = self.poll_parser.push(byte[0]) else {
+                continue;
+            };
+            if self.pending_discard > 0 {
+                self.pending_discard -= 1;
+                continue;
+            }
+            let frame = frame?;
+            frame.validate(self.parse_mode, OUTPUT_LENGTH)?;
+            self.check_fault(&frame)?;
+            return Ok(Some(frame));
+        }
+        Ok(None)
+    }
+
+    /// Reads the first frame passing the checksum and plausibility checks, regardless of the parse mode.
+    /// * `max_frames` - rejected frames to skip before returning the last rejection
+    pub fn read_valid(&mut self, max_frames: u8) -> Result<OutputFrame, Error<UART::Error>> {
+        let mut rejected = 0;
+        loop {
+            let result = self.read().and_then(|frame| frame.validate(ParseMode::Strict, self.codec.frame_length()).map(|_| frame));
+            match result {
+                Err(Error::ChecksumError | Error::FrameLengthMismatch { .. } | Error::OutOfRange)
+                    if rejected < max_frames =>
+                {
+                    rejected += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Queues a command to be sent during subsequent `read()` calls, without stopping the read loop.
+    /// Returns the command back if the queue is full or the sensor model doesn't support it.
+    pub fn queue_command(&mut self, command: Command) -> Result<(), Command> {
+        if !self.codec.supports(command) {
+            return Err(command);
+        }
+        match self.queue.iter_mut().find(|slot| slot.is_none()) {
+            Some(slot) => {
+                *slot = Some(command);
+                Ok(())
+            }
+            None => Err(command),
+        }
+    }
+
+    /// Number of queued commands not yet sent or acknowledged
+    pub fn pending_commands(&self) -> usize {
+        self.queue.iter().flatten().count() + self.awaiting_ack.is_some() as usize
+    }
+
+    /// Returns the last queued command which got an incorrect or no response
+    pub fn take_failed_command(&mut self) -> Option<Command> {
+        self.failed_command.take()
+    }
+
+    /// Sends the next queued command unless a response is still awaited
+    fn transmit_queued(&mut self) -> Result<(), Error<UART::Error>> {
+        if self.awaiting_ack.is_some() {
+            return Ok(());
+        }
+        let Some(command) = self.queue[0] else {
+            return Ok(());
+        };
+        self.queue.copy_within(1.., 0);
+        self.queue[COMMAND_QUEUE_SIZE - 1] = None;
+
+        self.send_cmd(&command.encode())?;
+        if command.expects_response() {
+            self.awaiting_ack = Some((command, ACK_FRAME_BUDGET));
+        }
+        if command == Command::Wake {
+            self.pending_discard = self.warmup_discard;
+        }
+        Ok(())
+    }
+
+    /// Sleep mode. Data frames received before the response are skipped.
     pub fn sleep(&mut self) -> Result<(), Error<UART::Error>> {
-        self.send_cmd(&create_command(0xe4, 0))?;
-        self.receive_response(SLEEP_RESPONSE)
+        self.execute(Command::Sleep)
     }
 
     pub fn wake(&mut self) -> Result<(), Error<UART::Error>> {
-        self.send_cmd(&create_command(0xe4, 1))
+        self.execute(Command::Wake)
     }
 
     /// Passive mode - sensor reports air quality on request
+    #[cfg(not(feature = "active-only"))]
     pub fn passive(&mut self) -> Result<(), Error<UART::Error>> {
-        self.send_cmd(&create_command(0xe1, 0))?;
-        self.receive_response(PASSIVE_MODE_RESPONSE)
+        self.execute(Command::SetPassive)
     }
 
     /// Active mode - sensor reports air quality continuously
+    #[cfg(not(feature = "passive-only"))]
     pub fn active(&mut self) -> Result<(), Error<UART::Error>> {
-        self.send_cmd(&create_command(0xe1, 1))?;
-        self.receive_response(ACTIVE_MODE_RESPONSE)
+        self.execute(Command::SetActive)
     }
 
     /// Requests status in passive mode
+    #[cfg(not(feature = "active-only"))]
     pub fn request(&mut self) -> Result<(), Error<UART::Error>> {
-        self.send_cmd(&create_command(0xe2, 0))
+        self.execute(Command::Request)
+    }
+
+    /// Sends commands one after the other, e.g. wake, passive and request, each one once the
+    /// previous one was acknowledged. Stops at the first failure, which reports the commands
+    /// that took effect so the caller can restore the previous state.
+    pub fn transaction(&mut self, commands: &[Command]) -> Result<(), TransactionError<UART::Error>> {
+        for (completed, command) in commands.iter().enumerate() {
+            self.execute(*command).map_err(|error| TransactionError {
+                completed,
+                command: *command,
+                error,
+            })?;
+        }
+        Ok(())
+    }
+
+    fn execute(&mut self, command: Command) -> Result<(), Error<UART::Error>> {
+        if !self.codec.supports(command) {
+            return Err(Error::UnsupportedByVariant(command));
+        }
+        self.send_cmd(&command.encode())?;
+        if command == Command::Wake {
+            self.pending_discard = self.warmup_discard;
+        }
+        self.receive_response(command)
     }
 
     fn send_cmd(&mut self, cmd: &[u8]) -> Result<(), Error<UART::Error>> {
-        match self.uart.write_all(cmd) {
-            Ok(()) => Ok(()),
-            Err(_) => Err(Error::NoResponse), // Simplify for now
+        self.uart.write_all(cmd).map_err(Error::Write)
+    }
+
+    /// Sends a command frame as is, e.g. for commands of newer firmware the driver doesn't know.
+    /// Nothing is read back, see `read_raw_response()` and `wait_for_ack()`.
+    pub fn send_raw_command(&mut self, cmd: u8, data: u16) -> Result<(), Error<UART::Error>> {
+        self.send_cmd(&create_command(cmd, data))
+    }
+
+    /// Waits for the next response, whatever command it answers.
+    /// * `max_frames` - data frames to skip before giving up with `Error::NoResponse`
+    pub fn read_raw_response(&mut self, max_frames: u8) -> Result<ResponseFrame, Error<UART::Error>> {
+        self.sync = SyncStats::default();
+        let mut frames = 0;
+        loop {
+            match self.read_packet()? {
+                Packet::Response(response) => return Ok(response),
+                Packet::Frame(_) if frames < max_frames => frames += 1,
+                Packet::Frame(_) => return Err(Error::NoResponse),
+            }
         }
     }
 
-    fn receive_response(&mut self, expected_response: Response) -> Result<(), Error<UART::Error>> {
-        if self.read_from_device([0u8; RESPONSE_FRAME_SIZE])? != expected_response {
-            Err(Error::IncorrectResponse)
-        } else {
+    /// Waits for the response to a command, e.g. one sent with `send_raw_command()`.
+    /// Data frames received in the meantime are skipped.
+    /// * `cmd` - command byte the response must echo
+    /// * `max_frames` - data frames to skip before giving up with `Error::NoResponse`
+    pub fn wait_for_ack(&mut self, cmd: u8, max_frames: u8) -> Result<ResponseFrame, Error<UART::Error>> {
+        self.sync = SyncStats::default();
+        protocol::wait_for_ack!(cmd, max_frames, self.read_packet())
+    }
+
+    fn receive_response(&mut self, command: Command) -> Result<(), Error<UART::Error>> {
+        let Some(expected) = self.profile.expected_ack(command) else {
+            return Ok(());
+        };
+        let response = self.wait_for_ack(expected.cmd, ACK_FRAME_BUDGET)?;
+        if response == expected {
             Ok(())
+        } else {
+            Err(Error::IncorrectResponse(Some(response)))
         }
     }
 }
 
+/// 16-bit additive checksum used by every frame. Accumulates in u16 so results don't
+/// depend on the platform's usize width.
+pub fn checksum(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0u16, |sum, b| sum.wrapping_add(*b as u16))
+}
+
+/// Whether a complete frame, command or response ends with the checksum of its preceding bytes,
+/// sent big-endian
+pub fn verify_checksum(frame: &[u8]) -> bool {
+    let Some(data_len) = frame.len().checked_sub(CHECKSUM_SIZE) else {
+        return false;
+    };
+    let (data, check) = frame.split_at(data_len);
+    checksum(data).to_be_bytes() == check
+}
+
 fn create_command(cmd: u8, data: u16) -> [u8; CMD_FRAME_SIZE] {
     let mut buffer = [0_u8; CMD_FRAME_SIZE];
     let mut offset = 0usize;
@@ -144,11 +823,7 @@ fn create_command(cmd: u8, data: u16) -> [u8; CMD_FRAME_SIZE] {
     offset += 2;
 
     // Calculate checksum
-    let checksum = buffer
-        .iter()
-        .take(CMD_FRAME_SIZE - CHECKSUM_SIZE)
-        .map(|b| *b as u16)
-        .sum::<u16>();
+    let checksum = checksum(&buffer[..CMD_FRAME_SIZE - CHECKSUM_SIZE]);
 
     // Write checksum as big-endian u16
     let checksum_bytes = checksum.to_be_bytes();
@@ -158,16 +833,22 @@ fn create_command(cmd: u8, data: u16) -> [u8; CMD_FRAME_SIZE] {
 }
 
 /// Contains data reported by the sensor
-#[derive(Default, Debug)]
+#[derive(Clone, Default, PartialEq, Eq)]
 pub struct OutputFrame {
     pub start1: u8,
     pub start2: u8,
     pub frame_length: u16,
+    /// PM1.0 in μg/m³ with CF=1, the factory calibration on standard particles, see [`OutputFrame::standard`]
     pub pm1_0: u16,
+    /// PM2.5 in μg/m³ with CF=1
     pub pm2_5: u16,
+    /// PM10 in μg/m³ with CF=1
     pub pm10: u16,
+    /// PM1.0 in μg/m³ under atmospheric environment, the value to report, see [`OutputFrame::atmospheric`]
     pub pm1_0_atm: u16,
+    /// PM2.5 in μg/m³ under atmospheric environment
     pub pm2_5_atm: u16,
+    /// PM10 in μg/m³ under atmospheric environment
     pub pm10_atm: u16,
     pub beyond_0_3: u16,
     pub beyond_0_5: u16,
@@ -175,18 +856,105 @@ pub struct OutputFrame {
     pub beyond_2_5: u16,
     pub beyond_5_0: u16,
     pub beyond_10_0: u16,
-    pub reserved: u16,
+    /// Firmware version, in the high byte of the word the datasheet calls reserved
+    pub version: u8,
+    /// Self-test result of recent firmware, see [`OutputFrame::fault`]
+    pub error_code: u8,
     pub check: u16,
 }
 
+/// Mass concentrations of one of the two sets a frame reports, in μg/m³
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Concentrations {
+    pub pm1_0: u16,
+    pub pm2_5: u16,
+    pub pm10: u16,
+}
+
+/// Where readings fall in the sensor's range, see [`OutputFrame::range_flag`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RangeFlag {
+    /// Within the effective range of 0-500 μg/m³
+    Normal,
+    /// Between the effective range and the maximum range of 1000 μg/m³, where consistency
+    /// degrades, e.g. in wildfire smoke
+    Degraded,
+    /// Beyond the maximum range, so the sensor is no longer linear and readings are a lower bound
+    Saturated,
+}
+
+/// Failures reported by the sensor in the error code of its frames, one per set bit.
+/// Plantower doesn't document what the bits mean, so no meaning is assumed: every bit is
+/// reported as a [`SensorFault::Unknown`] carrying its mask.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SensorFaults(u8);
+
+impl SensorFaults {
+    /// Decodes an error code, `None` meaning the sensor is healthy
+    pub fn from_error_code(code: u8) -> Option<Self> {
+        if code == 0 { None } else { Some(Self(code)) }
+    }
+
+    /// The error code as reported
+    pub fn error_code(&self) -> u8 {
+        self.0
+    }
+
+    /// Whether the error code reports `fault`
+    pub fn contains(&self, fault: SensorFault) -> bool {
+        let SensorFault::Unknown(mask) = fault;
+        self.0 & mask != 0
+    }
+
+    /// Iterates over the reported faults, lowest bit first
+    pub fn iter(&self) -> impl Iterator<Item = SensorFault> + '_ {
+        (0..8).map(|bit| 1u8 << bit).filter(|mask| self.0 & mask != 0).map(SensorFault::Unknown)
+    }
+}
+
+/// Failure reported by one bit of the error code
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SensorFault {
+    /// Bit without a documented meaning, as a mask of the error code
+    Unknown(u8),
+}
+
 impl OutputFrame {
+    /// Parses a frame, validating its header, frame length and checksum
     pub fn from_buffer<E>(buffer: &[u8; OUTPUT_FRAME_SIZE]) -> Result<Self, Error<E>> {
-        let sum: usize = buffer
-            .iter()
-            .take(OUTPUT_FRAME_SIZE - CHECKSUM_SIZE)
-            .map(|e| *e as usize)
-            .sum();
+        Self::from_buffer_with_mode(buffer, ParseMode::Lenient)
+    }
+
+    /// Parses a frame, validating it according to `mode`
+    pub fn from_buffer_with_mode<E>(buffer: &[u8; OUTPUT_FRAME_SIZE], mode: ParseMode) -> Result<Self, Error<E>> {
+        if buffer[0] != MN1 || buffer[1] != MN2 {
+            return Err(Error::IncorrectResponse(None));
+        }
+
+        // Whatever the mode, so other packets that happen to checksum aren't taken for frames
+        let frame_length = u16::from_be_bytes([buffer[2], buffer[3]]);
+        if frame_length != OUTPUT_LENGTH {
+            return Err(Error::FrameLengthMismatch {
+                expected: OUTPUT_LENGTH,
+                got: frame_length,
+            });
+        }
 
+        let sum = checksum(&buffer[..OUTPUT_FRAME_SIZE - CHECKSUM_SIZE]);
+        let frame = Self::decode(buffer);
+
+        if sum != frame.check {
+            return Err(Error::ChecksumError);
+        }
+
+        frame.validate(mode, OUTPUT_LENGTH)?;
+
+        Ok(frame)
+    }
+
+    /// Reads the fields of a 32-byte frame without validating it
+    pub(crate) fn decode(buffer: &[u8]) -> Self {
         let mut frame = OutputFrame::default();
         let mut offset = 0usize;
 
@@ -223,16 +991,520 @@ impl OutputFrame {
         offset += 2;
         frame.beyond_10_0 = u16::from_be_bytes([buffer[offset], buffer[offset + 1]]);
         offset += 2;
-        frame.reserved = u16::from_be_bytes([buffer[offset], buffer[offset + 1]]);
-        offset += 2;
+        frame.version = buffer[offset];
+        offset += 1;
+        frame.error_code = buffer[offset];
+        offset += 1;
         frame.check = u16::from_be_bytes([buffer[offset], buffer[offset + 1]]);
 
-        if sum != frame.check as usize {
+        frame
+    }
+
+    /// Checks the frame length and plausibility of values in strict mode
+    fn validate<E>(&self, mode: ParseMode, frame_length: u16) -> Result<(), Error<E>> {
+        if mode == ParseMode::Lenient {
+            return Ok(());
+        }
+
+        if self.frame_length != frame_length {
+            return Err(Error::FrameLengthMismatch {
+                expected: frame_length,
+                got: self.frame_length,
+            });
+        }
+
+        let concentrations = [self.pm1_0, self.pm2_5, self.pm10, self.pm1_0_atm, self.pm2_5_atm, self.pm10_atm];
+        let counts = [
+            self.beyond_0_3,
+            self.beyond_0_5,
+            self.beyond_1_0,
+            self.beyond_2_5,
+            self.beyond_5_0,
+            self.beyond_10_0,
+        ];
+
+        // Larger size classes include the smaller ones, and counts of bigger particles include fewer
+        let plausible = concentrations.iter().all(|c| *c <= MAX_CONCENTRATION)
+            && self.pm1_0 <= self.pm2_5
+            && self.pm2_5 <= self.pm10
+            && self.pm1_0_atm <= self.pm2_5_atm
+            && self.pm2_5_atm <= self.pm10_atm
+            && counts.windows(2).all(|pair| pair[0] >= pair[1]);
+
+        if plausible { Ok(()) } else { Err(Error::OutOfRange) }
+    }
+
+    /// Finds and parses the first frame in a slice, e.g. the application's own receive buffer,
+    /// returning the frame and the number of bytes up to its end.
+    /// Same as [`parser::find_and_parse_frame`], whose errors tell how many bytes can be dropped.
+    pub fn parse_from_slice(buffer: &[u8]) -> Result<(OutputFrame, usize), parser::ScanError> {
+        parser::find_and_parse_frame(buffer)
+    }
+
+    /// Starts building a frame, e.g. for tests or emulators
+    pub fn builder() -> OutputFrameBuilder {
+        OutputFrameBuilder::new()
+    }
+
+    /// Serializes the frame as the sensor sends it, with the standard header and frame length and
+    /// a recomputed checksum, e.g. to relay readings or feed them to an emulated sensor
+    pub fn to_bytes(&self) -> [u8; OUTPUT_FRAME_SIZE] {
+        let mut buffer = OutputFrame {
+            start1: MN1,
+            start2: MN2,
+            frame_length: OUTPUT_LENGTH,
+            ..self.clone()
+        }
+        .encode();
+        let sum = checksum(&buffer[..OUTPUT_FRAME_SIZE - CHECKSUM_SIZE]);
+        buffer[OUTPUT_FRAME_SIZE - CHECKSUM_SIZE..].copy_from_slice(&sum.to_be_bytes());
+        buffer
+    }
+
+    /// Serializes all fields as-is, without recomputing the checksum
+    fn encode(&self) -> [u8; OUTPUT_FRAME_SIZE] {
+        let mut buffer = [0u8; OUTPUT_FRAME_SIZE];
+        buffer[0] = self.start1;
+        buffer[1] = self.start2;
+
+        let words = [
+            self.frame_length,
+            self.pm1_0,
+            self.pm2_5,
+            self.pm10,
+            self.pm1_0_atm,
+            self.pm2_5_atm,
+            self.pm10_atm,
+            self.beyond_0_3,
+            self.beyond_0_5,
+            self.beyond_1_0,
+            self.beyond_2_5,
+            self.beyond_5_0,
+            self.beyond_10_0,
+            self.reserved(),
+            self.check,
+        ];
+        for (chunk, word) in buffer[2..].chunks_exact_mut(2).zip(words) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+
+        buffer
+    }
+
+    /// Version and error code as one word, which older firmware leaves reserved
+    pub fn reserved(&self) -> u16 {
+        u16::from_be_bytes([self.version, self.error_code])
+    }
+
+    /// Concentrations under atmospheric environment, i.e. `pm*_atm`, what to log or compare
+    /// with air quality standards
+    pub fn atmospheric(&self) -> Concentrations {
+        Concentrations { pm1_0: self.pm1_0_atm, pm2_5: self.pm2_5_atm, pm10: self.pm10_atm }
+    }
+
+    /// Concentrations with CF=1, i.e. `pm*` without suffix, calibrated on standard particles
+    /// in the factory. They read higher than the atmospheric ones once pollution builds up.
+    pub fn standard(&self) -> Concentrations {
+        Concentrations { pm1_0: self.pm1_0, pm2_5: self.pm2_5, pm10: self.pm10 }
+    }
+
+    /// Whether the highest concentration, of either set, is within the sensor's linear range
+    pub fn range_flag(&self) -> RangeFlag {
+        let highest = [self.pm1_0, self.pm2_5, self.pm10, self.pm1_0_atm, self.pm2_5_atm, self.pm10_atm]
+            .into_iter()
+            .max()
+            .unwrap_or(0);
+        if highest <= EFFECTIVE_RANGE {
+            RangeFlag::Normal
+        } else if highest <= MAX_CONCENTRATION {
+            RangeFlag::Degraded
+        } else {
+            RangeFlag::Saturated
+        }
+    }
+
+    /// Failures the sensor reports in its error code, if any
+    pub fn fault(&self) -> Option<SensorFaults> {
+        SensorFaults::from_error_code(self.error_code)
+    }
+
+    /// Iterates over measurement fields as `(name, value)` pairs, in frame order
+    pub fn iter_fields(&self) -> impl Iterator<Item = (&'static str, u16)> + '_ {
+        Field::ALL.iter().map(move |field| (field.name(), self[*field]))
+    }
+}
+
+/// `{:?}` prints a single line grouped by section, `{:#?}` prints every field with its unit
+impl core::fmt::Debug for OutputFrame {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if !f.alternate() {
+            return write!(
+                f,
+                "OutputFrame {{ cf1: {}/{}/{} μg/m³, atm: {}/{}/{} μg/m³, counts: {}/{}/{}/{}/{}/{} per 0.1L }}",
+                self.pm1_0,
+                self.pm2_5,
+                self.pm10,
+                self.pm1_0_atm,
+                self.pm2_5_atm,
+                self.pm10_atm,
+                self.beyond_0_3,
+                self.beyond_0_5,
+                self.beyond_1_0,
+                self.beyond_2_5,
+                self.beyond_5_0,
+                self.beyond_10_0,
+            );
+        }
+
+        writeln!(f, "OutputFrame {{")?;
+        writeln!(f, "    // Standard particles (CF=1)")?;
+        writeln!(f, "    pm1_0: {} μg/m³,", self.pm1_0)?;
+        writeln!(f, "    pm2_5: {} μg/m³,", self.pm2_5)?;
+        writeln!(f, "    pm10: {} μg/m³,", self.pm10)?;
+        writeln!(f, "    // Atmospheric environment (ATM)")?;
+        writeln!(f, "    pm1_0_atm: {} μg/m³,", self.pm1_0_atm)?;
+        writeln!(f, "    pm2_5_atm: {} μg/m³,", self.pm2_5_atm)?;
+        writeln!(f, "    pm10_atm: {} μg/m³,", self.pm10_atm)?;
+        writeln!(f, "    // Particles beyond size, per 0.1L of air")?;
+        writeln!(f, "    beyond_0_3: {} (>0.3μm),", self.beyond_0_3)?;
+        writeln!(f, "    beyond_0_5: {} (>0.5μm),", self.beyond_0_5)?;
+        writeln!(f, "    beyond_1_0: {} (>1.0μm),", self.beyond_1_0)?;
+        writeln!(f, "    beyond_2_5: {} (>2.5μm),", self.beyond_2_5)?;
+        writeln!(f, "    beyond_5_0: {} (>5.0μm),", self.beyond_5_0)?;
+        writeln!(f, "    beyond_10_0: {} (>10μm),", self.beyond_10_0)?;
+        writeln!(f, "    // Frame")?;
+        writeln!(f, "    frame_length: {},", self.frame_length)?;
+        writeln!(f, "    version: {:#04x},", self.version)?;
+        writeln!(f, "    error_code: {:#04x},", self.error_code)?;
+        writeln!(f, "    check: {:#06x},", self.check)?;
+        write!(f, "}}")
+    }
+}
+
+/// Measurement fields of an [`OutputFrame`], usable as an index
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Field {
+    Pm1_0,
+    Pm2_5,
+    Pm10,
+    Pm1_0Atm,
+    Pm2_5Atm,
+    Pm10Atm,
+    Beyond0_3,
+    Beyond0_5,
+    Beyond1_0,
+    Beyond2_5,
+    Beyond5_0,
+    Beyond10_0,
+}
+
+impl Field {
+    /// All measurement fields, in frame order
+    pub const ALL: [Field; 12] = [
+        Field::Pm1_0,
+        Field::Pm2_5,
+        Field::Pm10,
+        Field::Pm1_0Atm,
+        Field::Pm2_5Atm,
+        Field::Pm10Atm,
+        Field::Beyond0_3,
+        Field::Beyond0_5,
+        Field::Beyond1_0,
+        Field::Beyond2_5,
+        Field::Beyond5_0,
+        Field::Beyond10_0,
+    ];
+
+    /// Name of the matching `OutputFrame` field
+    pub fn name(&self) -> &'static str {
+        match self {
+            Field::Pm1_0 => "pm1_0",
+            Field::Pm2_5 => "pm2_5",
+            Field::Pm10 => "pm10",
+            Field::Pm1_0Atm => "pm1_0_atm",
+            Field::Pm2_5Atm => "pm2_5_atm",
+            Field::Pm10Atm => "pm10_atm",
+            Field::Beyond0_3 => "beyond_0_3",
+            Field::Beyond0_5 => "beyond_0_5",
+            Field::Beyond1_0 => "beyond_1_0",
+            Field::Beyond2_5 => "beyond_2_5",
+            Field::Beyond5_0 => "beyond_5_0",
+            Field::Beyond10_0 => "beyond_10_0",
+        }
+    }
+
+    /// Looks up a field by its `OutputFrame` field name, e.g. from configuration
+    pub fn from_name(name: &str) -> Option<Field> {
+        Field::ALL.iter().copied().find(|field| field.name() == name)
+    }
+}
+
+impl core::ops::Index<Field> for OutputFrame {
+    type Output = u16;
+
+    fn index(&self, field: Field) -> &u16 {
+        match field {
+            Field::Pm1_0 => &self.pm1_0,
+            Field::Pm2_5 => &self.pm2_5,
+            Field::Pm10 => &self.pm10,
+            Field::Pm1_0Atm => &self.pm1_0_atm,
+            Field::Pm2_5Atm => &self.pm2_5_atm,
+            Field::Pm10Atm => &self.pm10_atm,
+            Field::Beyond0_3 => &self.beyond_0_3,
+            Field::Beyond0_5 => &self.beyond_0_5,
+            Field::Beyond1_0 => &self.beyond_1_0,
+            Field::Beyond2_5 => &self.beyond_2_5,
+            Field::Beyond5_0 => &self.beyond_5_0,
+            Field::Beyond10_0 => &self.beyond_10_0,
+        }
+    }
+}
+
+/// View of a received frame, decoding fields on access instead of copying them all into an
+/// [`OutputFrame`], e.g. on targets short of stack that only need PM2.5
+#[derive(Clone, Copy, Debug)]
+pub struct OutputFrameRef<'a> {
+    buffer: &'a [u8; OUTPUT_FRAME_SIZE],
+}
+
+impl<'a> OutputFrameRef<'a> {
+    /// Validates the header, frame length and checksum of a frame once
+    pub fn new<E>(buffer: &'a [u8; OUTPUT_FRAME_SIZE]) -> Result<Self, Error<E>> {
+        if buffer[0] != MN1 || buffer[1] != MN2 {
+            return Err(Error::IncorrectResponse(None));
+        }
+        let frame_length = u16::from_be_bytes([buffer[2], buffer[3]]);
+        if frame_length != OUTPUT_LENGTH {
+            return Err(Error::FrameLengthMismatch {
+                expected: OUTPUT_LENGTH,
+                got: frame_length,
+            });
+        }
+        if !verify_checksum(buffer) {
             return Err(Error::ChecksumError);
         }
+        Ok(Self { buffer })
+    }
 
-        Ok(frame)
+    /// Decodes a measurement field
+    pub fn get(&self, field: Field) -> u16 {
+        let offset = 4 + 2 * field as usize;
+        u16::from_be_bytes([self.buffer[offset], self.buffer[offset + 1]])
+    }
+
+    pub fn pm1_0(&self) -> u16 {
+        self.get(Field::Pm1_0)
+    }
+
+    pub fn pm2_5(&self) -> u16 {
+        self.get(Field::Pm2_5)
+    }
+
+    pub fn pm10(&self) -> u16 {
+        self.get(Field::Pm10)
+    }
+
+    pub fn pm1_0_atm(&self) -> u16 {
+        self.get(Field::Pm1_0Atm)
+    }
+
+    pub fn pm2_5_atm(&self) -> u16 {
+        self.get(Field::Pm2_5Atm)
+    }
+
+    pub fn pm10_atm(&self) -> u16 {
+        self.get(Field::Pm10Atm)
+    }
+
+    pub fn beyond_0_3(&self) -> u16 {
+        self.get(Field::Beyond0_3)
+    }
+
+    pub fn beyond_0_5(&self) -> u16 {
+        self.get(Field::Beyond0_5)
+    }
+
+    pub fn beyond_1_0(&self) -> u16 {
+        self.get(Field::Beyond1_0)
+    }
+
+    pub fn beyond_2_5(&self) -> u16 {
+        self.get(Field::Beyond2_5)
+    }
+
+    pub fn beyond_5_0(&self) -> u16 {
+        self.get(Field::Beyond5_0)
+    }
+
+    pub fn beyond_10_0(&self) -> u16 {
+        self.get(Field::Beyond10_0)
+    }
+
+    pub fn version(&self) -> u8 {
+        self.buffer[28]
+    }
+
+    pub fn error_code(&self) -> u8 {
+        self.buffer[29]
+    }
+
+    /// Decodes every field
+    pub fn to_frame(&self) -> OutputFrame {
+        OutputFrame::decode(self.buffer)
+    }
+}
+
+#[cfg(feature = "float")]
+impl OutputFrame {
+    /// PM1.0, PM2.5 and PM10 standard particle concentrations (CF=1) as floats
+    pub fn pm_f32(&self) -> (f32, f32, f32) {
+        (self.pm1_0 as f32, self.pm2_5 as f32, self.pm10 as f32)
+    }
+
+    /// PM1.0, PM2.5 and PM10 atmospheric environment concentrations as floats
+    pub fn pm_atm_f32(&self) -> (f32, f32, f32) {
+        (self.pm1_0_atm as f32, self.pm2_5_atm as f32, self.pm10_atm as f32)
+    }
+
+    /// Particle counts per 0.1L of air, from >0.3μm to >10μm, as floats
+    pub fn counts_f32(&self) -> [f32; 6] {
+        [
+            self.beyond_0_3 as f32,
+            self.beyond_0_5 as f32,
+            self.beyond_1_0 as f32,
+            self.beyond_2_5 as f32,
+            self.beyond_5_0 as f32,
+            self.beyond_10_0 as f32,
+        ]
+    }
+}
+
+/// Standard particle concentrations as `(pm1_0, pm2_5, pm10)`
+#[cfg(feature = "float")]
+impl From<&OutputFrame> for (f32, f32, f32) {
+    fn from(frame: &OutputFrame) -> Self {
+        frame.pm_f32()
+    }
+}
+
+/// Standard particle concentrations as `(pm1_0, pm2_5, pm10)`
+#[cfg(feature = "float")]
+impl From<OutputFrame> for (f32, f32, f32) {
+    fn from(frame: OutputFrame) -> Self {
+        frame.pm_f32()
     }
 }
 
+/// Builds internally consistent frames. Created by [`OutputFrame::builder`].
+#[derive(Debug)]
+pub struct OutputFrameBuilder {
+    frame: OutputFrame,
+}
+
+impl OutputFrameBuilder {
+    fn new() -> Self {
+        Self {
+            frame: OutputFrame {
+                start1: MN1,
+                start2: MN2,
+                frame_length: OUTPUT_LENGTH,
+                ..OutputFrame::default()
+            },
+        }
+    }
+
+    /// Sets any measurement field
+    pub fn field(mut self, field: Field, value: u16) -> Self {
+        let slot = match field {
+            Field::Pm1_0 => &mut self.frame.pm1_0,
+            Field::Pm2_5 => &mut self.frame.pm2_5,
+            Field::Pm10 => &mut self.frame.pm10,
+            Field::Pm1_0Atm => &mut self.frame.pm1_0_atm,
+            Field::Pm2_5Atm => &mut self.frame.pm2_5_atm,
+            Field::Pm10Atm => &mut self.frame.pm10_atm,
+            Field::Beyond0_3 => &mut self.frame.beyond_0_3,
+            Field::Beyond0_5 => &mut self.frame.beyond_0_5,
+            Field::Beyond1_0 => &mut self.frame.beyond_1_0,
+            Field::Beyond2_5 => &mut self.frame.beyond_2_5,
+            Field::Beyond5_0 => &mut self.frame.beyond_5_0,
+            Field::Beyond10_0 => &mut self.frame.beyond_10_0,
+        };
+        *slot = value;
+        self
+    }
+
+    pub fn pm1_0(self, value: u16) -> Self {
+        self.field(Field::Pm1_0, value)
+    }
+
+    pub fn pm2_5(self, value: u16) -> Self {
+        self.field(Field::Pm2_5, value)
+    }
+
+    pub fn pm10(self, value: u16) -> Self {
+        self.field(Field::Pm10, value)
+    }
+
+    pub fn pm1_0_atm(self, value: u16) -> Self {
+        self.field(Field::Pm1_0Atm, value)
+    }
+
+    pub fn pm2_5_atm(self, value: u16) -> Self {
+        self.field(Field::Pm2_5Atm, value)
+    }
+
+    pub fn pm10_atm(self, value: u16) -> Self {
+        self.field(Field::Pm10Atm, value)
+    }
 
+    pub fn beyond_0_3(self, value: u16) -> Self {
+        self.field(Field::Beyond0_3, value)
+    }
+
+    pub fn beyond_0_5(self, value: u16) -> Self {
+        self.field(Field::Beyond0_5, value)
+    }
+
+    pub fn beyond_1_0(self, value: u16) -> Self {
+        self.field(Field::Beyond1_0, value)
+    }
+
+    pub fn beyond_2_5(self, value: u16) -> Self {
+        self.field(Field::Beyond2_5, value)
+    }
+
+    pub fn beyond_5_0(self, value: u16) -> Self {
+        self.field(Field::Beyond5_0, value)
+    }
+
+    pub fn beyond_10_0(self, value: u16) -> Self {
+        self.field(Field::Beyond10_0, value)
+    }
+
+    /// Sets version and error code as one word
+    pub fn reserved(mut self, value: u16) -> Self {
+        [self.frame.version, self.frame.error_code] = value.to_be_bytes();
+        self
+    }
+
+    pub fn version(mut self, value: u8) -> Self {
+        self.frame.version = value;
+        self
+    }
+
+    pub fn error_code(mut self, value: u8) -> Self {
+        self.frame.error_code = value;
+        self
+    }
+
+    /// Returns the frame as-is, with a zero checksum
+    pub fn build(self) -> OutputFrame {
+        self.frame
+    }
+
+    /// Returns the frame with a checksum matching its contents
+    pub fn build_with_checksum(mut self) -> OutputFrame {
+        self.frame.check = checksum(&self.frame.encode()[..OUTPUT_FRAME_SIZE - CHECKSUM_SIZE]);
+        self.frame
+    }
+}
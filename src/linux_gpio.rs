@@ -0,0 +1,58 @@
+//! Output lines of Linux GPIO character devices, e.g. `/dev/gpiochip0` on a Raspberry Pi
+//!
+//! Built on gpio-cdev, which uses the v1 line handle ABI. Kernels built with
+//! `CONFIG_GPIO_CDEV_V1=n` don't provide it.
+
+use std::path::Path;
+
+use embedded_hal::digital::{ErrorKind, ErrorType, OutputPin};
+use gpio_cdev::{Chip, LineHandle, LineRequestFlags};
+
+/// Error of a [`GpioLine`]
+#[derive(Debug)]
+pub struct GpioError(pub gpio_cdev::Error);
+
+impl embedded_hal::digital::Error for GpioError {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+/// Single GPIO line requested as an output, released when dropped
+#[derive(Debug)]
+pub struct GpioLine {
+    handle: LineHandle,
+}
+
+impl GpioLine {
+    /// Requests a line as an output
+    /// * `chip` - GPIO character device, e.g. `/dev/gpiochip0`
+    /// * `line` - offset of the line on the chip, the BCM number on a Raspberry Pi
+    /// * `high` - initial level
+    pub fn open(chip: impl AsRef<Path>, line: u32, high: bool) -> Result<Self, GpioError> {
+        let handle = Chip::new(chip)
+            .and_then(|mut chip| chip.get_line(line))
+            .and_then(|line| line.request(LineRequestFlags::OUTPUT, high as u8, "pmsx003"))
+            .map_err(GpioError)?;
+        Ok(Self { handle })
+    }
+
+    /// Returns the wrapped line handle
+    pub fn release(self) -> LineHandle {
+        self.handle
+    }
+}
+
+impl ErrorType for GpioLine {
+    type Error = GpioError;
+}
+
+impl OutputPin for GpioLine {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.handle.set_value(0).map_err(GpioError)
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.handle.set_value(1).map_err(GpioError)
+    }
+}
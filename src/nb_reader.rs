@@ -0,0 +1,41 @@
+//! Non-blocking reader for HALs exposing `embedded_hal_nb::serial::Read` only
+
+use embedded_hal_nb::serial::Read;
+use embedded_io::ReadExactError;
+
+use crate::parser::FrameParser;
+use crate::{Error, OutputFrame};
+
+/// Reads frames without blocking, keeping a partially received frame between calls.
+/// Meant to be polled from a superloop or with `nb::block!`.
+pub struct NbReader<RX> {
+    rx: RX,
+    parser: FrameParser,
+}
+
+impl<RX: Read<u8>> NbReader<RX> {
+    /// Creates a new reader
+    /// * `rx` - UART receiver implementing embedded-hal-nb serial Read trait
+    pub fn new(rx: RX) -> Self {
+        Self { rx, parser: FrameParser::new() }
+    }
+
+    /// Returns the next frame, or `nb::Error::WouldBlock` once the receiver ran out of bytes
+    /// before a frame was complete
+    pub fn read(&mut self) -> nb::Result<OutputFrame, Error<RX::Error>> {
+        loop {
+            let byte = self
+                .rx
+                .read()
+                .map_err(|e| e.map(|e| Error::Read(ReadExactError::Other(e))))?;
+            if let Some(frame) = self.parser.push(byte) {
+                return frame.map_err(|e| nb::Error::Other(e.into()));
+            }
+        }
+    }
+
+    /// Returns the wrapped receiver
+    pub fn release(self) -> RX {
+        self.rx
+    }
+}
@@ -0,0 +1,212 @@
+//! Push parser for bytes received outside the driver, e.g. in interrupt handlers or DMA callbacks
+
+use core::convert::Infallible;
+
+use heapless::Deque;
+
+use crate::protocol::{Event, Protocol};
+use crate::{OutputFrame, ParseError, MN1, MN2, OUTPUT_FRAME_SIZE, OUTPUT_LENGTH, RESPONSE_FRAME_SIZE, RESPONSE_LENGTH};
+
+/// Reassembles frames from bytes pushed one at a time, a frames-only front-end of [`Protocol`].
+/// Resynchronizes on the magic numbers and skips command responses and unknown packets.
+#[derive(Clone, Debug)]
+pub struct FrameParser {
+    protocol: Protocol,
+}
+
+impl FrameParser {
+    pub const fn new() -> Self {
+        Self { protocol: Protocol::new() }
+    }
+
+    /// Feeds a byte, returning a frame once its last byte arrived
+    pub fn push(&mut self, byte: u8) -> Option<Result<OutputFrame, ParseError>> {
+        match self.protocol.handle_byte(byte)? {
+            Event::Frame(frame) => Some(Ok(frame)),
+            Event::Response(_) => None,
+            Event::Error(e) => Some(Err(e)),
+        }
+    }
+
+    /// Feeds several bytes, calling `on_frame` for every frame completed along the way
+    pub fn push_slice<F>(&mut self, bytes: &[u8], mut on_frame: F)
+    where
+        F: FnMut(Result<OutputFrame, ParseError>),
+    {
+        for byte in bytes {
+            if let Some(frame) = self.push(*byte) {
+                on_frame(frame);
+            }
+        }
+    }
+
+    /// Drops a partially received packet
+    pub fn reset(&mut self) {
+        self.protocol.reset();
+    }
+}
+
+impl Default for FrameParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parser for bytes received in chunks of any size, e.g. DMA half-buffers. Every chunk is parsed
+/// in full when fed, and the results are queued until read.
+/// * `N` - results queued at most. Beyond that, the oldest ones are dropped.
+#[derive(Clone, Debug)]
+pub struct FrameAccumulator<const N: usize> {
+    parser: FrameParser,
+    results: Deque<Result<OutputFrame, ParseError>, N>,
+    dropped: u32,
+}
+
+impl<const N: usize> FrameAccumulator<N> {
+    pub const fn new() -> Self {
+        Self {
+            parser: FrameParser::new(),
+            results: Deque::new(),
+            dropped: 0,
+        }
+    }
+
+    /// Feeds a chunk, yielding the frames completed so far. A frame split across chunks is kept
+    /// until the next call, and results left unread when the iterator is dropped are yielded
+    /// by the next call.
+    pub fn extend(&mut self, chunk: &[u8]) -> impl Iterator<Item = Result<OutputFrame, ParseError>> + '_ {
+        for byte in chunk {
+            if let Some(result) = self.parser.push(*byte) {
+                if self.results.is_full() {
+                    self.results.pop_front();
+                    self.dropped = self.dropped.saturating_add(1);
+                }
+                let _ = self.results.push_back(result);
+            }
+        }
+        core::iter::from_fn(move || self.results.pop_front())
+    }
+
+    /// Results dropped because the queue was full
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+
+    /// Drops a partially received packet and the queued results
+    pub fn reset(&mut self) {
+        self.parser.reset();
+        self.results.clear();
+    }
+}
+
+impl<const N: usize> Default for FrameAccumulator<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parser paired with a slot for the latest frame, to be shared between a UART RX interrupt
+/// feeding bytes and the task consuming frames, e.g. as an RTIC resource.
+/// It holds no references or I/O, so it's `Send` and cheap to access inside a lock.
+#[derive(Clone, Debug, Default)]
+pub struct FrameMailbox {
+    parser: FrameParser,
+    latest: Option<OutputFrame>,
+    errors: u32,
+}
+
+impl FrameMailbox {
+    pub const fn new() -> Self {
+        Self {
+            parser: FrameParser::new(),
+            latest: None,
+            errors: 0,
+        }
+    }
+
+    /// Feeds a byte from the interrupt handler, returning whether it completed a frame.
+    /// A frame not taken yet is replaced by the newer one.
+    pub fn push(&mut self, byte: u8) -> bool {
+        match self.parser.push(byte) {
+            Some(Ok(frame)) => {
+                self.latest = Some(frame);
+                true
+            }
+            Some(Err(_)) => {
+                self.errors = self.errors.wrapping_add(1);
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// Takes the latest complete frame, if one arrived since the last call
+    pub fn take(&mut self) -> Option<OutputFrame> {
+        self.latest.take()
+    }
+
+    /// Number of corrupted frames received so far
+    pub fn errors(&self) -> u32 {
+        self.errors
+    }
+}
+
+/// Error of [`find_and_parse_frame`]. Both variants tell how many bytes can be dropped
+/// from the start of the buffer before scanning it again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScanError {
+    /// No complete frame yet. Bytes from `consumed` on may start one and must be kept.
+    Incomplete { consumed: usize },
+    /// A frame ending at `consumed` had an invalid checksum
+    ChecksumError { consumed: usize },
+}
+
+impl ScanError {
+    /// Number of bytes which can be dropped
+    pub fn consumed(&self) -> usize {
+        match self {
+            ScanError::Incomplete { consumed } | ScanError::ChecksumError { consumed } => *consumed,
+        }
+    }
+}
+
+/// Finds and parses the first frame in a buffer, e.g. a DMA buffer, without copying it.
+/// Returns the frame and the number of bytes up to its end.
+/// Command responses and packets with an unknown length are skipped.
+pub fn find_and_parse_frame(buffer: &[u8]) -> Result<(OutputFrame, usize), ScanError> {
+    let mut start = 0;
+    while let Some(offset) = buffer[start..].windows(2).position(|w| w == [MN1, MN2]) {
+        let begin = start + offset;
+        let Some(length) = buffer.get(begin + 2..begin + 4) else {
+            return Err(ScanError::Incomplete { consumed: begin });
+        };
+        let size = match u16::from_be_bytes([length[0], length[1]]) {
+            OUTPUT_LENGTH => OUTPUT_FRAME_SIZE,
+            RESPONSE_LENGTH => RESPONSE_FRAME_SIZE,
+            // The length may hold the actual header when a byte went missing
+            _ => {
+                start = begin + 1;
+                continue;
+            }
+        };
+        let Some(packet) = buffer.get(begin..begin + size) else {
+            return Err(ScanError::Incomplete { consumed: begin });
+        };
+        let end = begin + size;
+        if let Ok(frame) = <&[u8; OUTPUT_FRAME_SIZE]>::try_from(packet) {
+            return parse_frame(frame)
+                .map(|frame| (frame, end))
+                .map_err(|_| ScanError::ChecksumError { consumed: end });
+        }
+        start = end;
+    }
+
+    // A trailing MN1 may be the start of the next frame
+    let consumed = if buffer[start..].last() == Some(&MN1) { buffer.len() - 1 } else { buffer.len() };
+    Err(ScanError::Incomplete { consumed })
+}
+
+/// Parses a frame whose header was already checked, so only the checksum can be wrong
+pub(crate) fn parse_frame(buffer: &[u8; OUTPUT_FRAME_SIZE]) -> Result<OutputFrame, ParseError> {
+    OutputFrame::from_buffer::<Infallible>(buffer).map_err(|_| ParseError::ChecksumError)
+}
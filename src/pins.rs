@@ -0,0 +1,49 @@
+//! Power management through the SET and RESET pins
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::OutputPin;
+
+// How long RESET is held low
+const RESET_PULSE_MS: u32 = 10;
+
+/// Drives the sensor's SET and RESET pins.
+/// Pulling SET low sleeps the sensor and stops the fan, which also works without a TX line.
+pub struct ControlPins<SET, RESET> {
+    set: SET,
+    reset: RESET,
+}
+
+impl<SET, RESET> ControlPins<SET, RESET>
+where
+    SET: OutputPin,
+    RESET: OutputPin<Error = SET::Error>,
+{
+    /// Creates a new instance, the pins are left as they are
+    /// * `set` - pin connected to SET, high for normal operation
+    /// * `reset` - pin connected to RESET, active low
+    pub fn new(set: SET, reset: RESET) -> Self {
+        Self { set, reset }
+    }
+
+    /// Puts the sensor to sleep
+    pub fn sleep(&mut self) -> Result<(), SET::Error> {
+        self.set.set_low()
+    }
+
+    /// Wakes the sensor up. The fan needs about 30 seconds before readings are stable.
+    pub fn wake(&mut self) -> Result<(), SET::Error> {
+        self.set.set_high()
+    }
+
+    /// Resets the sensor
+    pub fn reset<D: DelayNs>(&mut self, delay: &mut D) -> Result<(), SET::Error> {
+        self.reset.set_low()?;
+        delay.delay_ms(RESET_PULSE_MS);
+        self.reset.set_high()
+    }
+
+    /// Returns the wrapped pins
+    pub fn release(self) -> (SET, RESET) {
+        (self.set, self.reset)
+    }
+}
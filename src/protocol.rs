@@ -0,0 +1,252 @@
+//! Transport-independent protocol core.
+//!
+//! [`Protocol`] turns received bytes into frames and responses and encodes commands without
+//! doing any I/O, so the protocol can be embedded into unusual transports such as radio bridges
+//! or shared buses, and tested or fuzzed on its own. [`FrameParser`](crate::parser::FrameParser),
+//! and with it the non-blocking and DMA front-ends, is built on it.
+//!
+//! The blocking and async drivers share its resync and length logic, but read as many bytes at
+//! once as the packet being received still needs. Both expand the same macros, differing only in
+//! how bytes are read: a blocking driver passes a plain call, an async one the same call followed
+//! by `.await`.
+
+use core::convert::Infallible;
+
+use crate::codec::{FrameCodec, PlantowerCodec, MAX_FRAME_SIZE};
+use crate::{
+    Command, OutputFrame, Packet, ParseError, ResponseFrame, SyncStats, CMD_FRAME_SIZE, MN1, MN2, RESPONSE_FRAME_SIZE,
+    RESPONSE_LENGTH,
+};
+
+/// Packet completed by [`Protocol::handle_byte`]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    Frame(OutputFrame),
+    Response(ResponseFrame),
+    /// A frame or response was corrupted
+    Error(ParseError),
+}
+
+/// Reassembles packets from received bytes, resynchronizing on the magic numbers and skipping
+/// packets with an unknown length. The bytes it holds are always the last ones it was fed.
+#[derive(Clone, Debug)]
+pub(crate) struct Deframer {
+    buffer: [u8; MAX_FRAME_SIZE],
+    len: usize,
+    // Size of the packet being received, known once its length field arrived
+    size: usize,
+    response: bool,
+}
+
+impl Deframer {
+    pub(crate) const fn new() -> Self {
+        Self {
+            buffer: [0; MAX_FRAME_SIZE],
+            len: 0,
+            size: 0,
+            response: false,
+        }
+    }
+
+    /// Bytes of the packet being received so far
+    pub(crate) fn held(&self) -> usize {
+        self.len
+    }
+
+    /// How many bytes to feed next, never more than completes the header or the packet,
+    /// so a packet can only be completed by the last of them
+    pub(crate) fn wanted(&self) -> usize {
+        match self.len {
+            0 | 1 => 1,
+            2 | 3 => 4 - self.len,
+            len => self.size - len,
+        }
+    }
+
+    /// Feeds a received byte, returning the packet it completed
+    pub(crate) fn push<C: FrameCodec>(&mut self, byte: u8, codec: &C, sync: &mut SyncStats) -> Option<Result<Packet, ParseError>> {
+        match self.len {
+            0 if byte != MN1 => {
+                sync.discarded_bytes = sync.discarded_bytes.saturating_add(1);
+                return None;
+            }
+            // Another first magic number may start the actual header
+            1 if byte == MN1 => {
+                sync.discarded_bytes = sync.discarded_bytes.saturating_add(1);
+                return None;
+            }
+            1 if byte != MN2 => {
+                sync.discarded_bytes = sync.discarded_bytes.saturating_add(2);
+                sync.partial_header = true;
+                self.len = 0;
+                return None;
+            }
+            _ => {}
+        }
+
+        self.buffer[self.len] = byte;
+        self.len += 1;
+
+        if self.len == 4 {
+            (self.size, self.response) = match u16::from_be_bytes([self.buffer[2], self.buffer[3]]) {
+                length if length == codec.frame_length() && length as usize + 4 <= MAX_FRAME_SIZE => {
+                    (length as usize + 4, false)
+                }
+                RESPONSE_LENGTH => (RESPONSE_FRAME_SIZE, true),
+                _ => {
+                    // The length may hold the actual header when a byte went missing,
+                    // e.g. `42 4D 42 4D 00 1C`, so it's fed again
+                    sync.discarded_bytes = sync.discarded_bytes.saturating_add(2);
+                    sync.skipped_packets = sync.skipped_packets.saturating_add(1);
+                    self.len = 0;
+                    let length = [self.buffer[2], self.buffer[3]];
+                    for byte in length {
+                        // Two bytes can't complete a packet
+                        self.push(byte, codec, sync);
+                    }
+                    return None;
+                }
+            };
+        }
+
+        if self.len < 4 || self.len < self.size {
+            return None;
+        }
+
+        self.len = 0;
+        let packet = &self.buffer[..self.size];
+        if self.response {
+            let response = ResponseFrame::from_buffer::<Infallible>(packet.try_into().unwrap());
+            return Some(response.map(Packet::Response).map_err(|_| ParseError::ChecksumError));
+        }
+        if !codec.checksum_valid(packet) {
+            return Some(Err(ParseError::ChecksumError));
+        }
+        Some(Ok(Packet::Frame(codec.decode(packet))))
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.len = 0;
+    }
+}
+
+/// Protocol state machine fed one byte at a time.
+/// Resynchronizes on the magic numbers and skips packets with an unknown length.
+#[derive(Clone, Debug)]
+pub struct Protocol<C = PlantowerCodec> {
+    codec: C,
+    deframer: Deframer,
+    sync: SyncStats,
+}
+
+impl Protocol {
+    pub const fn new() -> Self {
+        Self::with_codec(PlantowerCodec)
+    }
+
+    /// Encodes a command to be sent to the sensor
+    pub fn encode_command(command: Command) -> [u8; CMD_FRAME_SIZE] {
+        command.encode()
+    }
+}
+
+impl Default for Protocol {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: FrameCodec> Protocol<C> {
+    /// Creates a new instance for a sensor sending frames in another format
+    pub const fn with_codec(codec: C) -> Self {
+        Self {
+            codec,
+            deframer: Deframer::new(),
+            sync: SyncStats {
+                discarded_bytes: 0,
+                skipped_packets: 0,
+                partial_header: false,
+                corrupted_frames: 0,
+                transport_errors: 0,
+            },
+        }
+    }
+
+    /// Feeds a received byte, returning an event once it completed a packet
+    pub fn handle_byte(&mut self, byte: u8) -> Option<Event> {
+        Some(match self.deframer.push(byte, &self.codec, &mut self.sync)? {
+            Ok(Packet::Frame(frame)) => Event::Frame(frame),
+            Ok(Packet::Response(response)) => Event::Response(response),
+            Err(e) => {
+                self.sync.corrupted_frames = self.sync.corrupted_frames.saturating_add(1);
+                Event::Error(e)
+            }
+        })
+    }
+
+    /// Bytes dropped and packets skipped so far
+    pub fn sync_stats(&self) -> SyncStats {
+        self.sync
+    }
+
+    /// Drops a partially received packet
+    pub fn reset(&mut self) {
+        self.deframer.reset();
+    }
+}
+
+/// Reads the next packet with a [`Deframer`], using the frame length field to tell data frames
+/// from responses. Packets with an unknown length are skipped.
+/// * `$codec` - [`FrameCodec`](crate::codec::FrameCodec) of the data frames
+/// * `$sync` - [`SyncStats`](crate::SyncStats) updated along the way
+/// * `$read` - fills `$buf` completely, evaluating to `Result<(), ReadExactError<E>>`
+/// * `$on_read` - evaluated before each read with `$held`, the number of bytes read so far that
+///   belong to the packet being received, any read before them having been dropped
+macro_rules! read_packet {
+    ($codec:expr, $sync:expr, |$buf:ident| $read:expr) => {
+        $crate::protocol::read_packet!($codec, $sync, |$buf| $read, |_held| {})
+    };
+    ($codec:expr, $sync:expr, |$buf:ident| $read:expr, |$held:ident| $on_read:expr) => {{
+        let mut deframer = $crate::protocol::Deframer::new();
+        loop {
+            let $held = deframer.held();
+            $on_read;
+            let mut bytes = [0u8; $crate::codec::MAX_FRAME_SIZE];
+            let bytes = &mut bytes[..deframer.wanted()];
+            {
+                let $buf = &mut *bytes;
+                $read.map_err($crate::Error::Read)?;
+            }
+            let mut completed = None;
+            for byte in bytes.iter() {
+                completed = deframer.push(*byte, &$codec, &mut $sync);
+            }
+            if let Some(packet) = completed {
+                break packet.map_err($crate::Error::from);
+            }
+        }
+    }};
+}
+
+/// Waits for the response echoing command byte `$cmd`, skipping up to `$max_frames` data frames.
+/// * `$next` - reads the next packet, evaluating to `Result<Packet, Error<E>>`
+macro_rules! wait_for_ack {
+    ($cmd:expr, $max_frames:expr, $next:expr) => {{
+        let mut frames = 0;
+        loop {
+            match $next? {
+                $crate::Packet::Response(response) if response.cmd == $cmd => break Ok(response),
+                $crate::Packet::Response(response) => break Err($crate::Error::IncorrectResponse(Some(response))),
+                $crate::Packet::Frame(_) => {
+                    frames += 1;
+                    if frames > $max_frames {
+                        break Err($crate::Error::NoResponse);
+                    }
+                }
+            }
+        }
+    }};
+}
+
+pub(crate) use read_packet;
+pub(crate) use wait_for_ack;
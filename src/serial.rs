@@ -0,0 +1,89 @@
+//! Desktop serial ports through serialport-rs, e.g. USB-UART dongles on Linux, macOS and Windows
+
+use std::io;
+use std::time::Duration;
+
+use embedded_io::{ErrorType, Read, Write};
+use serialport::SerialPort;
+
+use crate::PmsX003Sensor;
+
+/// Baud rate of the sensor
+pub const BAUD_RATE: u32 = 9600;
+
+/// Read timeout set by [`SerialPortUart::open`]. Active mode reports about every second,
+/// so a longer silence means the sensor is asleep, in passive mode or disconnected.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Serial port implementing embedded-io, reporting read timeouts as `ErrorKind::TimedOut`
+pub struct SerialPortUart {
+    port: Box<dyn SerialPort>,
+}
+
+impl SerialPortUart {
+    /// Opens a port at 9600 8N1
+    /// * `name` - port name, e.g. `/dev/ttyUSB0` or `COM3`. serialport adds the `\\.\` prefix
+    ///   Windows needs for `COM10` and above.
+    pub fn open(name: &str) -> serialport::Result<Self> {
+        let port = serialport::new(name, BAUD_RATE)
+            .data_bits(serialport::DataBits::Eight)
+            .parity(serialport::Parity::None)
+            .stop_bits(serialport::StopBits::One)
+            .flow_control(serialport::FlowControl::None)
+            .timeout(DEFAULT_TIMEOUT)
+            .open()?;
+        Ok(Self { port })
+    }
+
+    /// Wraps a port that was already opened and configured
+    pub fn from_port(port: Box<dyn SerialPort>) -> Self {
+        Self { port }
+    }
+
+    /// Returns the wrapped port
+    pub fn into_inner(self) -> Box<dyn SerialPort> {
+        self.port
+    }
+}
+
+impl ErrorType for SerialPortUart {
+    type Error = io::Error;
+}
+
+impl Read for SerialPortUart {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        match self.port.read(buf) {
+            // Some platforms report a timeout as an empty read, which embedded-io treats as end of file
+            Ok(0) if !buf.is_empty() => Err(io::ErrorKind::TimedOut.into()),
+            result => result,
+        }
+    }
+}
+
+impl Write for SerialPortUart {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.port.write(buf)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.port.flush()
+    }
+}
+
+/// Sensor on a desktop serial port, e.g. `StdPms::open("/dev/serial0")` on a Raspberry Pi
+pub type StdPms = PmsX003Sensor<SerialPortUart>;
+
+impl PmsX003Sensor<SerialPortUart> {
+    /// Opens a port at 9600 8N1 with a `DEFAULT_TIMEOUT` read timeout, so reads from a silent
+    /// sensor fail with `ErrorKind::TimedOut` instead of blocking forever
+    /// * `name` - port name, e.g. `/dev/ttyUSB0` or `COM3`
+    pub fn open(name: &str) -> serialport::Result<Self> {
+        SerialPortUart::open(name).map(Self::new)
+    }
+
+    /// Creates a new sensor instance on a port opened with serialport-rs, configured for 9600 8N1
+    /// and preferably a read timeout so a silent sensor doesn't block forever
+    pub fn from_serialport(port: Box<dyn SerialPort>) -> Self {
+        Self::new(SerialPortUart::from_port(port))
+    }
+}
@@ -0,0 +1,213 @@
+//! Simulated sensor for running application logic on a host or in wasm without hardware
+
+use embedded_hal::delay::DelayNs;
+use embedded_io::{ErrorKind, ErrorType, Read, Write};
+
+use crate::{checksum, OutputFrame, ProtocolProfile, ResponseFrame, CMD_FRAME_SIZE, MN1, MN2, RESPONSE_FRAME_SIZE};
+
+/// Default time between frames in active mode, like the real sensor's
+pub const DEFAULT_INTERVAL_MS: u32 = 1000;
+
+// Highest PM2.5 reached by the random walk, keeping PM10 in the sensor's range
+const MAX_PM2_5: u16 = 700;
+
+/// Serial port emulating a sensor, to be passed to [`PmsX003Sensor::new`](crate::PmsX003Sensor::new).
+///
+/// In active mode a frame is produced every interval, waited out with `delay`. Readings wander
+/// randomly around a baseline, with the other concentrations and the particle counts derived
+/// from PM2.5 in realistic proportions. Commands are acknowledged like a Plantower sensor does.
+/// Reads fail with `ErrorKind::TimedOut` while no frame is due, i.e. asleep or in passive mode
+/// without a pending request.
+pub struct SimTransport<D> {
+    delay: D,
+    interval_ms: u32,
+    active: bool,
+    asleep: bool,
+    baseline: u16,
+    pm2_5: u16,
+    rng: u32,
+    // Command bytes received so far
+    command: [u8; CMD_FRAME_SIZE],
+    command_len: usize,
+    // Bytes waiting to be read
+    output: [u8; 64],
+    output_pos: usize,
+    output_len: usize,
+}
+
+impl<D: DelayNs> SimTransport<D> {
+    /// Creates an awake sensor in active mode
+    /// * `delay` - waits out the interval between frames, e.g. a no-op to run as fast as possible
+    /// * `seed` - seed of the random readings, the same seed giving the same readings
+    pub fn new(delay: D, seed: u32) -> Self {
+        Self {
+            delay,
+            interval_ms: DEFAULT_INTERVAL_MS,
+            active: true,
+            asleep: false,
+            baseline: 12,
+            pm2_5: 12,
+            // xorshift gets stuck on zero
+            rng: seed | 1,
+            command: [0; CMD_FRAME_SIZE],
+            command_len: 0,
+            output: [0; 64],
+            output_pos: 0,
+            output_len: 0,
+        }
+    }
+
+    /// Sets the time between frames in active mode. Defaults to `DEFAULT_INTERVAL_MS`.
+    pub fn set_interval(&mut self, interval_ms: u32) {
+        self.interval_ms = interval_ms;
+    }
+
+    /// Sets the PM2.5 concentration readings wander around, in μg/m³. Defaults to 12.
+    pub fn set_baseline(&mut self, pm2_5: u16) {
+        self.baseline = pm2_5.min(MAX_PM2_5);
+        self.pm2_5 = self.baseline;
+    }
+
+    /// Whether the simulated sensor was put to sleep
+    pub fn is_asleep(&self) -> bool {
+        self.asleep
+    }
+
+    /// Produces the next reading, as the sensor would report it
+    pub fn next_frame(&mut self) -> OutputFrame {
+        // Random step, pulled back towards the baseline
+        let step = (self.random() % 5) as i32 - 2;
+        let pull = (self.baseline as i32 - self.pm2_5 as i32).signum();
+        self.pm2_5 = (self.pm2_5 as i32 + step + pull).clamp(0, MAX_PM2_5 as i32) as u16;
+
+        let pm2_5 = self.pm2_5;
+        let pm1_0 = pm2_5 * 7 / 10;
+        let pm10 = pm2_5 + pm2_5 * 3 / 10 + (self.random() % 3) as u16;
+        // Atmospheric values match the CF=1 ones at low concentrations, then fall behind
+        let atm = |value: u16| if value < 30 { value } else { 30 + (value - 30) * 2 / 3 };
+
+        let beyond_0_3 = pm2_5.saturating_mul(150).saturating_add((self.random() % 100) as u16);
+        let beyond_0_5 = beyond_0_3 / 3;
+        let beyond_1_0 = beyond_0_5 / 4;
+        let beyond_2_5 = beyond_1_0 / 6;
+        let beyond_5_0 = beyond_2_5 / 3;
+        let beyond_10_0 = beyond_5_0 / 3;
+
+        OutputFrame::builder()
+            .pm1_0(pm1_0)
+            .pm2_5(pm2_5)
+            .pm10(pm10)
+            .pm1_0_atm(atm(pm1_0))
+            .pm2_5_atm(atm(pm2_5))
+            .pm10_atm(atm(pm10))
+            .beyond_0_3(beyond_0_3)
+            .beyond_0_5(beyond_0_5)
+            .beyond_1_0(beyond_1_0)
+            .beyond_2_5(beyond_2_5)
+            .beyond_5_0(beyond_5_0)
+            .beyond_10_0(beyond_10_0)
+            .build_with_checksum()
+    }
+
+    fn random(&mut self) -> u32 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+        self.rng
+    }
+
+    fn queue(&mut self, bytes: &[u8]) {
+        // Bytes nobody read are overwritten, like a UART's overrun
+        if self.output_pos == self.output_len || self.output_len + bytes.len() > self.output.len() {
+            self.output_pos = 0;
+            self.output_len = 0;
+        }
+        self.output[self.output_len..self.output_len + bytes.len()].copy_from_slice(bytes);
+        self.output_len += bytes.len();
+    }
+
+    fn queue_frame(&mut self) {
+        let frame = self.next_frame().to_bytes();
+        self.queue(&frame);
+    }
+
+    fn respond(&mut self, response: ResponseFrame) {
+        let mut buffer = [MN1, MN2, 0x00, 0x04, response.cmd, response.data, 0, 0];
+        let sum = checksum(&buffer[..RESPONSE_FRAME_SIZE - 2]);
+        buffer[6..].copy_from_slice(&sum.to_be_bytes());
+        self.queue(&buffer);
+    }
+
+    fn handle_command(&mut self) {
+        let [_, _, cmd, _, data, ..] = self.command;
+        let profile = ProtocolProfile::PLANTOWER;
+        match (cmd, data) {
+            (0xe4, 0) => {
+                self.asleep = true;
+                self.respond(profile.sleep_ack);
+            }
+            (0xe4, _) => self.asleep = false,
+            (0xe1, 0) => {
+                self.active = false;
+                self.respond(profile.passive_ack);
+            }
+            (0xe1, _) => {
+                self.active = true;
+                self.respond(profile.active_ack);
+            }
+            (0xe2, _) if !self.asleep => self.queue_frame(),
+            _ => {}
+        }
+    }
+}
+
+impl<D> ErrorType for SimTransport<D> {
+    type Error = ErrorKind;
+}
+
+impl<D: DelayNs> Read for SimTransport<D> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        if self.output_pos == self.output_len {
+            if !self.active || self.asleep {
+                return Err(ErrorKind::TimedOut);
+            }
+            self.delay.delay_ms(self.interval_ms);
+            self.queue_frame();
+        }
+
+        let n = buf.len().min(self.output_len - self.output_pos);
+        buf[..n].copy_from_slice(&self.output[self.output_pos..self.output_pos + n]);
+        self.output_pos += n;
+        Ok(n)
+    }
+}
+
+impl<D: DelayNs> Write for SimTransport<D> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        for byte in buf {
+            // Resync on the magic numbers, like the sensor's own parser
+            match self.command_len {
+                0 if *byte != MN1 => continue,
+                1 if *byte != MN2 => {
+                    self.command_len = 0;
+                    continue;
+                }
+                _ => {}
+            }
+            self.command[self.command_len] = *byte;
+            self.command_len += 1;
+            if self.command_len == CMD_FRAME_SIZE {
+                self.command_len = 0;
+                let sum = checksum(&self.command[..CMD_FRAME_SIZE - 2]);
+                if sum.to_be_bytes() == self.command[CMD_FRAME_SIZE - 2..] {
+                    self.handle_command();
+                }
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
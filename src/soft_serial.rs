@@ -0,0 +1,123 @@
+//! Bit-banged serial receiver for boards whose hardware UARTs are all taken
+//!
+//! The sensor only needs its TX line listened to in active mode, so a single GPIO input is
+//! enough. Timing relies on `DelayNs` being reasonably accurate at 100μs scale, and on the
+//! receiver not being interrupted for long while a frame arrives.
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::InputPin;
+use embedded_io::{ErrorKind, ErrorType, Read, Write};
+
+/// Baud rate of the sensor
+pub const BAUD_RATE: u32 = 9600;
+
+// Start bit edges are looked for this many times per bit
+const EDGE_POLLS_PER_BIT: u32 = 8;
+
+/// Error of a [`SoftSerialRx`]
+#[derive(Debug)]
+pub enum SoftSerialError<E> {
+    Pin(E),
+    /// Stop bit wasn't high, usually due to a timing glitch
+    Framing,
+    /// The receiver can't transmit, so commands can't be sent
+    Unsupported,
+}
+
+impl<E: core::fmt::Debug> embedded_io::Error for SoftSerialError<E> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            SoftSerialError::Pin(_) => ErrorKind::Other,
+            SoftSerialError::Framing => ErrorKind::InvalidData,
+            SoftSerialError::Unsupported => ErrorKind::Unsupported,
+        }
+    }
+}
+
+/// RX-only software UART, 8N1.
+/// Writes fail with `SoftSerialError::Unsupported`, so the sensor must be left in active mode.
+pub struct SoftSerialRx<PIN, D> {
+    pin: PIN,
+    delay: D,
+    bit_ns: u32,
+}
+
+impl<PIN: InputPin, D: DelayNs> SoftSerialRx<PIN, D> {
+    /// Creates a new receiver at the sensor's 9600 baud
+    /// * `pin` - GPIO connected to the sensor's TX line
+    /// * `delay` - delay used to time bits, ideally backed by a hardware timer
+    pub fn new(pin: PIN, delay: D) -> Self {
+        Self::new_with_baud_rate(pin, delay, BAUD_RATE)
+    }
+
+    /// Creates a new receiver at a custom baud rate
+    pub fn new_with_baud_rate(pin: PIN, delay: D, baud_rate: u32) -> Self {
+        Self {
+            pin,
+            delay,
+            bit_ns: 1_000_000_000 / baud_rate,
+        }
+    }
+
+    /// Returns the wrapped pin and delay
+    pub fn release(self) -> (PIN, D) {
+        (self.pin, self.delay)
+    }
+
+    fn read_byte(&mut self) -> Result<u8, SoftSerialError<PIN::Error>> {
+        loop {
+            // Idle line is high, wait for the falling edge of the start bit
+            while self.pin.is_high().map_err(SoftSerialError::Pin)? {
+                self.delay.delay_ns(self.bit_ns / EDGE_POLLS_PER_BIT);
+            }
+
+            // Sample in the middle of each bit, starting with the start bit
+            self.delay.delay_ns(self.bit_ns / 2);
+            if self.pin.is_high().map_err(SoftSerialError::Pin)? {
+                // Glitch, not a start bit
+                continue;
+            }
+
+            let mut byte = 0u8;
+            for bit in 0..8 {
+                self.delay.delay_ns(self.bit_ns);
+                if self.pin.is_high().map_err(SoftSerialError::Pin)? {
+                    byte |= 1 << bit;
+                }
+            }
+
+            self.delay.delay_ns(self.bit_ns);
+            if !self.pin.is_high().map_err(SoftSerialError::Pin)? {
+                return Err(SoftSerialError::Framing);
+            }
+            return Ok(byte);
+        }
+    }
+}
+
+impl<PIN: InputPin, D> ErrorType for SoftSerialRx<PIN, D> {
+    type Error = SoftSerialError<PIN::Error>;
+}
+
+impl<PIN: InputPin, D: DelayNs> Read for SoftSerialRx<PIN, D> {
+    /// Receives a single byte per call, there's no buffering to catch bytes between calls
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        match buf.first_mut() {
+            Some(slot) => {
+                *slot = self.read_byte()?;
+                Ok(1)
+            }
+            None => Ok(0),
+        }
+    }
+}
+
+impl<PIN: InputPin, D> Write for SoftSerialRx<PIN, D> {
+    fn write(&mut self, _: &[u8]) -> Result<usize, Self::Error> {
+        Err(SoftSerialError::Unsupported)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
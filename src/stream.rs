@@ -0,0 +1,300 @@
+//! Combinators over async streams of frames
+
+use core::future::poll_fn;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use embassy_sync::blocking_mutex::raw::RawMutex;
+use embassy_sync::channel::Channel;
+use futures_core::Stream;
+use futures_sink::Sink;
+
+use crate::aqi::{self, Aqi, NowCast};
+use crate::dedup::Deduplicator;
+use crate::filter::{Ewma, Smoothing};
+use crate::OutputFrame;
+
+/// Configuration of [`AqiStream`]
+#[derive(Clone, Copy, Debug)]
+pub struct AqiConfig {
+    /// EWMA weight applied to each new concentration sample
+    pub alpha: f32,
+    /// Number of frames making up one hour, used to build NowCast hourly averages
+    pub samples_per_hour: u32,
+}
+
+impl AqiConfig {
+    /// Default configuration with the given smoothing preset
+    pub fn with_smoothing(preset: Smoothing) -> Self {
+        Self {
+            alpha: preset.alpha(),
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for AqiConfig {
+    fn default() -> Self {
+        // Active mode reports roughly every 800ms
+        Self {
+            alpha: Smoothing::default().alpha(),
+            samples_per_hour: 4500,
+        }
+    }
+}
+
+/// Index values derived from a single frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AqiReading {
+    pub pm2_5: Aqi,
+    pub pm10: Aqi,
+    /// Whether the values are based on NowCast rather than smoothed instantaneous concentrations
+    pub nowcast: bool,
+}
+
+impl AqiReading {
+    /// Index of the pollutant with the worse air quality
+    pub fn worst(&self) -> Aqi {
+        aqi::worst(self.pm2_5, self.pm10)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct Hourly {
+    hour_sum: f32,
+    hour_count: u32,
+    nowcast: NowCast,
+}
+
+impl Hourly {
+    fn push(&mut self, concentration: f32, samples_per_hour: u32) {
+        self.hour_sum += concentration;
+        self.hour_count += 1;
+        if self.hour_count >= samples_per_hour {
+            self.nowcast.push_hour(Some(self.hour_sum / self.hour_count as f32));
+            self.hour_sum = 0.0;
+            self.hour_count = 0;
+        }
+    }
+}
+
+/// Maps a stream of frames into smoothed AQI values. Created by [`FrameStreamExt::aqi`].
+pub struct AqiStream<S> {
+    inner: S,
+    config: AqiConfig,
+    pm2_5_filter: Ewma,
+    pm10_filter: Ewma,
+    pm2_5: Hourly,
+    pm10: Hourly,
+}
+
+impl<S> AqiStream<S> {
+    pub fn new(inner: S, config: AqiConfig) -> Self {
+        Self {
+            inner,
+            config,
+            pm2_5_filter: Ewma::new(config.alpha),
+            pm10_filter: Ewma::new(config.alpha),
+            pm2_5: Hourly::default(),
+            pm10: Hourly::default(),
+        }
+    }
+
+    /// Returns the wrapped stream
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn process(&mut self, frame: &OutputFrame) -> AqiReading {
+        let pm2_5 = frame.pm2_5_atm as f32;
+        let pm10 = frame.pm10_atm as f32;
+        self.pm2_5.push(pm2_5, self.config.samples_per_hour);
+        self.pm10.push(pm10, self.config.samples_per_hour);
+
+        let pm2_5_smoothed = self.pm2_5_filter.update(pm2_5);
+        let pm10_smoothed = self.pm10_filter.update(pm10);
+
+        match (self.pm2_5.nowcast.value(), self.pm10.nowcast.value()) {
+            (Some(pm2_5), Some(pm10)) => AqiReading {
+                pm2_5: aqi::pm2_5(pm2_5),
+                pm10: aqi::pm10(pm10),
+                nowcast: true,
+            },
+            _ => AqiReading {
+                pm2_5: aqi::pm2_5(pm2_5_smoothed),
+                pm10: aqi::pm10(pm10_smoothed),
+                nowcast: false,
+            },
+        }
+    }
+}
+
+impl<S, E> Stream for AqiStream<S>
+where
+    S: Stream<Item = Result<OutputFrame, E>> + Unpin,
+{
+    type Item = Result<AqiReading, E>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        match Pin::new(&mut this.inner).poll_next(cx) {
+            Poll::Ready(Some(Ok(frame))) => Poll::Ready(Some(Ok(this.process(&frame)))),
+            Poll::Ready(Some(Err(e))) => Poll::Ready(Some(Err(e))),
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// Drops consecutive identical frames. Created by [`FrameStreamExt::dedup`].
+pub struct DedupStream<S> {
+    inner: S,
+    dedup: Deduplicator,
+}
+
+impl<S, E> Stream for DedupStream<S>
+where
+    S: Stream<Item = Result<OutputFrame, E>> + Unpin,
+{
+    type Item = Result<OutputFrame, E>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            match Pin::new(&mut this.inner).poll_next(cx) {
+                Poll::Ready(Some(Ok(frame))) => {
+                    if let Some(frame) = this.dedup.filter(frame) {
+                        return Poll::Ready(Some(Ok(frame)));
+                    }
+                }
+                other => return other,
+            }
+        }
+    }
+}
+
+/// Extension methods for streams of frames
+pub trait FrameStreamExt<E>: Stream<Item = Result<OutputFrame, E>> + Sized {
+    /// Maps frames into smoothed AQI values ready for display
+    fn aqi(self, config: AqiConfig) -> AqiStream<Self> {
+        AqiStream::new(self, config)
+    }
+
+    /// Drops consecutive identical frames, see [`Deduplicator`]
+    fn dedup(self, keepalive: u16) -> DedupStream<Self> {
+        DedupStream {
+            inner: self,
+            dedup: Deduplicator::new(keepalive),
+        }
+    }
+}
+
+impl<S, E> FrameStreamExt<E> for S where S: Stream<Item = Result<OutputFrame, E>> {}
+
+/// What to do with an item when the consumer can't keep up
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Wait for the consumer, stalling the producer
+    Block,
+    /// Discard the oldest queued item to make room for the new one
+    DropOldest,
+    /// Discard the new item
+    DropNewest,
+}
+
+/// Counters reported once forwarding ends
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ForwardStats {
+    pub forwarded: u32,
+    pub dropped: u32,
+}
+
+async fn next<S: Stream + Unpin>(stream: &mut S) -> Option<S::Item> {
+    poll_fn(|cx| Pin::new(&mut *stream).poll_next(cx)).await
+}
+
+/// Forwards every item of `stream` into `channel` until the stream ends
+pub async fn forward_to_channel<S, M, const N: usize>(
+    mut stream: S,
+    channel: &Channel<M, S::Item, N>,
+    policy: OverflowPolicy,
+) -> ForwardStats
+where
+    S: Stream + Unpin,
+    M: RawMutex,
+{
+    let mut stats = ForwardStats::default();
+    while let Some(item) = next(&mut stream).await {
+        match policy {
+            OverflowPolicy::Block => channel.send(item).await,
+            OverflowPolicy::DropOldest => {
+                if channel.is_full() && channel.try_receive().is_ok() {
+                    stats.dropped += 1;
+                }
+                if channel.try_send(item).is_err() {
+                    stats.dropped += 1;
+                    continue;
+                }
+            }
+            OverflowPolicy::DropNewest => {
+                if channel.try_send(item).is_err() {
+                    stats.dropped += 1;
+                    continue;
+                }
+            }
+        }
+        stats.forwarded += 1;
+    }
+    stats
+}
+
+/// Forwards every item of `stream` into `sink` until the stream ends, flushing the sink after
+/// every item. Except with `OverflowPolicy::Block`, a stalled sink doesn't hold up the stream.
+/// A sink owns its buffer, so `OverflowPolicy::DropOldest` behaves like `OverflowPolicy::DropNewest`.
+pub async fn forward_to_sink<S, K>(
+    mut stream: S,
+    mut sink: K,
+    policy: OverflowPolicy,
+) -> Result<ForwardStats, K::Error>
+where
+    S: Stream + Unpin,
+    K: Sink<S::Item> + Unpin,
+{
+    let mut stats = ForwardStats::default();
+    while let Some(item) = next(&mut stream).await {
+        let ready = match policy {
+            OverflowPolicy::Block => poll_fn(|cx| Pin::new(&mut sink).poll_ready(cx)).await.map(|_| true),
+            _ => match poll_fn(|cx| Poll::Ready(Pin::new(&mut sink).poll_ready(cx))).await {
+                Poll::Ready(result) => result.map(|_| true),
+                Poll::Pending => Ok(false),
+            },
+        }?;
+
+        if !ready {
+            stats.dropped += 1;
+            // Keeps a sink that's full of unflushed items delivering them
+            flush_without_waiting::<S::Item, _>(&mut sink).await?;
+            continue;
+        }
+
+        Pin::new(&mut sink).start_send(item)?;
+        stats.forwarded += 1;
+
+        // The stream of a sensor never ends, so buffering sinks are flushed after every item
+        match policy {
+            OverflowPolicy::Block => poll_fn(|cx| Pin::new(&mut sink).poll_flush(cx)).await?,
+            _ => flush_without_waiting::<S::Item, _>(&mut sink).await?,
+        }
+    }
+
+    poll_fn(|cx| Pin::new(&mut sink).poll_flush(cx)).await?;
+    Ok(stats)
+}
+
+/// Starts flushing `sink` without waiting for it to complete
+async fn flush_without_waiting<T, K: Sink<T> + Unpin>(sink: &mut K) -> Result<(), K::Error> {
+    match poll_fn(|cx| Poll::Ready(Pin::new(&mut *sink).poll_flush(cx))).await {
+        Poll::Ready(result) => result,
+        Poll::Pending => Ok(()),
+    }
+}
@@ -0,0 +1,59 @@
+//! Host serial ports through Tokio, e.g. `tokio_serial::SerialStream` on a Raspberry Pi,
+//! so the async driver runs unchanged before flashing firmware
+
+use core::future::poll_fn;
+use core::pin::Pin;
+use std::io;
+
+use embedded_io_async::{ErrorType, Read, Write};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::asynch::PmsX003SensorAsync;
+
+/// Adapter implementing embedded-io-async for any Tokio `AsyncRead + AsyncWrite`.
+/// The port must already be configured for 9600 8N1.
+pub struct TokioUart<T> {
+    io: T,
+}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> TokioUart<T> {
+    pub fn new(io: T) -> Self {
+        Self { io }
+    }
+
+    /// Returns the wrapped port
+    pub fn into_inner(self) -> T {
+        self.io
+    }
+}
+
+impl<T> ErrorType for TokioUart<T> {
+    type Error = io::Error;
+}
+
+impl<T: AsyncRead + Unpin> Read for TokioUart<T> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let mut buf = ReadBuf::new(buf);
+        poll_fn(|cx| Pin::new(&mut self.io).poll_read(cx, &mut buf)).await?;
+        Ok(buf.filled().len())
+    }
+}
+
+impl<T: AsyncWrite + Unpin> Write for TokioUart<T> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        poll_fn(|cx| Pin::new(&mut self.io).poll_write(cx, buf)).await
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        poll_fn(|cx| Pin::new(&mut self.io).poll_flush(cx)).await
+    }
+}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> PmsX003SensorAsync<TokioUart<T>> {
+    /// Creates a new sensor instance on a Tokio port. Without the `embassy` feature reads
+    /// don't time out, wrap them in `tokio::time::timeout` instead.
+    /// * `io` - port implementing Tokio AsyncRead + AsyncWrite, e.g. `tokio_serial::SerialStream`
+    pub fn from_tokio(io: T) -> Self {
+        Self::new(TokioUart::new(io))
+    }
+}
@@ -0,0 +1,610 @@
+//! Adapters for serial drivers that don't fit the driver's blocking embedded-io model directly
+
+use core::cell::{Cell, RefCell};
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::OutputPin;
+use embedded_io::{ErrorKind, ErrorType, Read, ReadReady, Write, WriteReady};
+
+/// Error of a [`TimeoutUart`]
+#[derive(Debug)]
+pub enum TimeoutError<E> {
+    /// No data arrived within the timeout
+    TimedOut,
+    Uart(E),
+}
+
+impl<E: embedded_io::Error> embedded_io::Error for TimeoutError<E> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            TimeoutError::TimedOut => ErrorKind::TimedOut,
+            TimeoutError::Uart(e) => e.kind(),
+        }
+    }
+}
+
+/// Serial port whose reads give up after a timeout instead of blocking forever.
+///
+/// Drivers of RTOS-based HALs, such as esp-idf-hal's `UartDriver`, implement embedded-io reads
+/// by blocking indefinitely, so `read()` would hang on a sleeping or unplugged sensor.
+/// This adapter polls `ReadReady` instead and reports `TimeoutError::TimedOut`.
+pub struct TimeoutUart<UART, D> {
+    uart: UART,
+    delay: D,
+    timeout_ms: u32,
+}
+
+impl<UART, D> TimeoutUart<UART, D>
+where
+    UART: Read + ReadReady + Write,
+    D: DelayNs,
+{
+    /// Creates a new adapter
+    /// * `uart` - serial port implementing embedded-io traits
+    /// * `delay` - delay used between polls, e.g. `FreeRtos` on ESP-IDF
+    /// * `timeout_ms` - how long a read waits for the first byte
+    pub fn new(uart: UART, delay: D, timeout_ms: u32) -> Self {
+        Self { uart, delay, timeout_ms }
+    }
+
+    /// Returns the wrapped serial port and delay
+    pub fn release(self) -> (UART, D) {
+        (self.uart, self.delay)
+    }
+}
+
+impl<UART: ErrorType, D> ErrorType for TimeoutUart<UART, D> {
+    type Error = TimeoutError<UART::Error>;
+}
+
+impl<UART, D> Read for TimeoutUart<UART, D>
+where
+    UART: Read + ReadReady,
+    D: DelayNs,
+{
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let mut waited_ms = 0;
+        while !self.uart.read_ready().map_err(TimeoutError::Uart)? {
+            if waited_ms >= self.timeout_ms {
+                return Err(TimeoutError::TimedOut);
+            }
+            self.delay.delay_ms(1);
+            waited_ms += 1;
+        }
+        self.uart.read(buf).map_err(TimeoutError::Uart)
+    }
+}
+
+impl<UART: Write, D> Write for TimeoutUart<UART, D> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.uart.write(buf).map_err(TimeoutError::Uart)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.uart.flush().map_err(TimeoutError::Uart)
+    }
+}
+
+/// Error of a [`Rs485Uart`]
+#[derive(Debug)]
+pub enum Rs485Error<E, PE> {
+    Uart(E),
+    /// Driving the driver-enable pin failed
+    Pin(PE),
+}
+
+impl<E: embedded_io::Error, PE: core::fmt::Debug> embedded_io::Error for Rs485Error<E, PE> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Rs485Error::Uart(e) => e.kind(),
+            Rs485Error::Pin(_) => ErrorKind::Other,
+        }
+    }
+}
+
+/// Serial port behind a half-duplex RS-485 transceiver.
+///
+/// The driver-enable pin, usually tied to the inverted receiver-enable, is raised for the
+/// duration of each write and lowered once the bytes left the UART, so the sensor's responses
+/// aren't drowned out by our own transmitter.
+pub struct Rs485Uart<UART, DE, D> {
+    uart: UART,
+    de: DE,
+    delay: D,
+    turnaround_us: u32,
+}
+
+impl<UART, DE, D> Rs485Uart<UART, DE, D>
+where
+    UART: Read + Write,
+    DE: OutputPin,
+    D: DelayNs,
+{
+    /// Creates a new adapter, leaving the transceiver in receive mode
+    /// * `uart` - serial port implementing embedded-io traits
+    /// * `de` - driver-enable pin, active high
+    /// * `delay` - delay used for the turnaround time
+    /// * `turnaround_us` - time the transceiver needs to switch direction, waited after raising
+    ///   and before lowering the pin
+    pub fn new(uart: UART, mut de: DE, delay: D, turnaround_us: u32) -> Result<Self, DE::Error> {
+        de.set_low()?;
+        Ok(Self { uart, de, delay, turnaround_us })
+    }
+
+    /// Returns the wrapped serial port, pin and delay
+    pub fn release(self) -> (UART, DE, D) {
+        (self.uart, self.de, self.delay)
+    }
+}
+
+impl<UART: ErrorType, DE: OutputPin, D> ErrorType for Rs485Uart<UART, DE, D> {
+    type Error = Rs485Error<UART::Error, DE::Error>;
+}
+
+impl<UART: Read, DE: OutputPin, D> Read for Rs485Uart<UART, DE, D> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.uart.read(buf).map_err(Rs485Error::Uart)
+    }
+}
+
+impl<UART, DE, D> Write for Rs485Uart<UART, DE, D>
+where
+    UART: Write,
+    DE: OutputPin,
+    D: DelayNs,
+{
+    /// Transmits all of `buf`, waiting for the UART to drain before releasing the bus
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.de.set_high().map_err(Rs485Error::Pin)?;
+        self.delay.delay_us(self.turnaround_us);
+        let result = self.uart.write_all(buf).and_then(|()| self.uart.flush());
+        self.delay.delay_us(self.turnaround_us);
+        self.de.set_low().map_err(Rs485Error::Pin)?;
+        result.map_err(Rs485Error::Uart)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.uart.flush().map_err(Rs485Error::Uart)
+    }
+}
+
+/// Serial port which only moves data while being serviced, such as a usbd-serial CDC-ACM port
+/// needing `UsbDevice::poll()`.
+///
+/// CDC reads return whatever arrived in the last USB packets, or would block when nothing did.
+/// This adapter calls `poll` until the port is ready, so reads block like a UART's and the
+/// driver reassembles frames from the chunks.
+pub struct PolledUart<UART, F> {
+    uart: UART,
+    poll: F,
+}
+
+impl<UART, F> PolledUart<UART, F>
+where
+    UART: Read + ReadReady + Write + WriteReady,
+    F: FnMut(&mut UART),
+{
+    /// Creates a new adapter
+    /// * `uart` - port implementing embedded-io traits, including `ReadReady` and `WriteReady`
+    /// * `poll` - services the port, e.g. `|serial| { usb_dev.poll(&mut [serial]); }`
+    pub fn new(uart: UART, poll: F) -> Self {
+        Self { uart, poll }
+    }
+
+    /// Returns the wrapped port and poll function
+    pub fn release(self) -> (UART, F) {
+        (self.uart, self.poll)
+    }
+}
+
+impl<UART: ErrorType, F> ErrorType for PolledUart<UART, F> {
+    type Error = UART::Error;
+}
+
+impl<UART, F> Read for PolledUart<UART, F>
+where
+    UART: Read + ReadReady,
+    F: FnMut(&mut UART),
+{
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        loop {
+            (self.poll)(&mut self.uart);
+            if self.uart.read_ready()? {
+                return self.uart.read(buf);
+            }
+        }
+    }
+}
+
+impl<UART, F> Write for PolledUart<UART, F>
+where
+    UART: Write + WriteReady,
+    F: FnMut(&mut UART),
+{
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        loop {
+            (self.poll)(&mut self.uart);
+            if self.uart.write_ready()? {
+                return self.uart.write(buf);
+            }
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        (self.poll)(&mut self.uart);
+        self.uart.flush()
+    }
+}
+
+/// UART shared by several sensors behind a multiplexer, e.g. an analog switch.
+/// Each sensor gets its own [`MuxedUart`] handle from [`SharedUart::channel`].
+pub struct SharedUart<UART> {
+    uart: RefCell<UART>,
+    selected: Cell<Option<u8>>,
+}
+
+impl<UART: Read + Write> SharedUart<UART> {
+    pub const fn new(uart: UART) -> Self {
+        Self {
+            uart: RefCell::new(uart),
+            selected: Cell::new(None),
+        }
+    }
+
+    /// Handle for the sensor on a multiplexer channel
+    /// * `channel` - identifies the channel, so switching only happens when it changes
+    /// * `select` - switches the multiplexer to this channel, e.g. by setting its address pins
+    pub fn channel<S: FnMut()>(&self, channel: u8, select: S) -> MuxedUart<'_, UART, S> {
+        MuxedUart { bus: self, channel, select }
+    }
+
+    /// Returns the wrapped UART
+    pub fn into_inner(self) -> UART {
+        self.uart.into_inner()
+    }
+}
+
+/// Handle of one sensor on a [`SharedUart`], switching the multiplexer before using the UART.
+/// Bytes the previous sensor left in the UART's FIFO are dropped by the driver's resync.
+pub struct MuxedUart<'a, UART, S> {
+    bus: &'a SharedUart<UART>,
+    channel: u8,
+    select: S,
+}
+
+impl<UART, S: FnMut()> MuxedUart<'_, UART, S> {
+    fn select(&mut self) {
+        if self.bus.selected.get() != Some(self.channel) {
+            (self.select)();
+            self.bus.selected.set(Some(self.channel));
+        }
+    }
+}
+
+impl<UART: ErrorType, S> ErrorType for MuxedUart<'_, UART, S> {
+    type Error = UART::Error;
+}
+
+impl<UART: Read, S: FnMut()> Read for MuxedUart<'_, UART, S> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.select();
+        self.bus.uart.borrow_mut().read(buf)
+    }
+}
+
+impl<UART: Write, S: FnMut()> Write for MuxedUart<'_, UART, S> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.select();
+        self.bus.uart.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.bus.uart.borrow_mut().flush()
+    }
+}
+
+/// Adapter for any `std::io` stream, e.g. a `TcpStream` to a ser2net or ESPHome serial bridge.
+/// A closed connection is reported as `ReadExactError::UnexpectedEof`.
+///
+/// Reads are buffered, so the driver's small reads are served from whole network chunks
+/// instead of costing a system call each. Works with in-memory streams too, e.g. a `Cursor`
+/// over recorded bytes, see [`PmsX003Sensor::from_io`](crate::PmsX003Sensor::from_io).
+/// Ports wrapped in embedded-io-adapters' `FromStd` can be passed to `PmsX003Sensor::new` as is.
+#[cfg(feature = "std")]
+pub struct IoUart<T> {
+    inner: T,
+    buffer: [u8; IO_CHUNK_SIZE],
+    pos: usize,
+    len: usize,
+}
+
+// Larger than a TCP bridge's typical chunk of a few frames
+#[cfg(feature = "std")]
+const IO_CHUNK_SIZE: usize = 256;
+
+#[cfg(feature = "std")]
+impl<T: std::io::Read + std::io::Write> IoUart<T> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            buffer: [0; IO_CHUNK_SIZE],
+            pos: 0,
+            len: 0,
+        }
+    }
+
+    /// Returns the wrapped stream. Bytes received but not read yet are lost.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+#[cfg(feature = "std")]
+impl IoUart<std::net::TcpStream> {
+    /// Connects to a serial-to-TCP bridge, e.g. `IoUart::connect("192.168.1.20:23")`.
+    /// Nagle's algorithm is disabled so commands go out immediately.
+    pub fn connect(addr: impl std::net::ToSocketAddrs) -> std::io::Result<Self> {
+        let stream = std::net::TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        Ok(Self::new(stream))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: std::io::Read + std::io::Write> crate::PmsX003Sensor<IoUart<T>> {
+    /// Creates a new sensor instance on any `std::io` stream, e.g. a port from another serial crate
+    /// or a `Cursor` replaying a capture in tests
+    pub fn from_io(io: T) -> Self {
+        Self::new(IoUart::new(io))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> ErrorType for IoUart<T> {
+    type Error = std::io::Error;
+}
+
+#[cfg(feature = "std")]
+impl<T: std::io::Read> Read for IoUart<T> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.pos == self.len {
+            self.len = loop {
+                match self.inner.read(&mut self.buffer) {
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                    result => break result?,
+                }
+            };
+            self.pos = 0;
+        }
+
+        let n = buf.len().min(self.len - self.pos);
+        buf[..n].copy_from_slice(&self.buffer[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: std::io::Write> Write for IoUart<T> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.inner.flush()
+    }
+}
+
+/// Object-safe counterpart of embedded-io `Read + Write`, with errors reduced to their `ErrorKind`.
+/// Implemented by every embedded-io serial port.
+pub trait DynUart {
+    fn dyn_read(&mut self, buf: &mut [u8]) -> Result<usize, ErrorKind>;
+
+    fn dyn_write(&mut self, buf: &[u8]) -> Result<usize, ErrorKind>;
+
+    fn dyn_flush(&mut self) -> Result<(), ErrorKind>;
+}
+
+impl<T: Read + Write> DynUart for T {
+    fn dyn_read(&mut self, buf: &mut [u8]) -> Result<usize, ErrorKind> {
+        self.read(buf).map_err(|e| embedded_io::Error::kind(&e))
+    }
+
+    fn dyn_write(&mut self, buf: &[u8]) -> Result<usize, ErrorKind> {
+        self.write(buf).map_err(|e| embedded_io::Error::kind(&e))
+    }
+
+    fn dyn_flush(&mut self) -> Result<(), ErrorKind> {
+        self.flush().map_err(|e| embedded_io::Error::kind(&e))
+    }
+}
+
+/// Serial port borrowed as a trait object, so drivers over different UART types share one type.
+/// See [`ErasedPmsSensor`](crate::driver::ErasedPmsSensor).
+pub struct ErasedUart<'a> {
+    inner: &'a mut dyn DynUart,
+}
+
+impl<'a> ErasedUart<'a> {
+    pub fn new(inner: &'a mut dyn DynUart) -> Self {
+        Self { inner }
+    }
+}
+
+impl ErrorType for ErasedUart<'_> {
+    type Error = ErrorKind;
+}
+
+impl Read for ErasedUart<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.inner.dyn_read(buf)
+    }
+}
+
+impl Write for ErasedUart<'_> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.inner.dyn_write(buf)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.inner.dyn_flush()
+    }
+}
+
+/// Error of an [`NbUart`], telling which half failed
+#[cfg(feature = "nb")]
+#[derive(Debug)]
+pub enum NbUartError<TXE, RXE> {
+    Tx(TXE),
+    Rx(RXE),
+}
+
+#[cfg(feature = "nb")]
+impl<TXE: embedded_hal_nb::serial::Error, RXE: embedded_hal_nb::serial::Error> NbUartError<TXE, RXE> {
+    /// The serial error kind reported by the failing half
+    pub fn serial_kind(&self) -> embedded_hal_nb::serial::ErrorKind {
+        match self {
+            NbUartError::Tx(e) => e.kind(),
+            NbUartError::Rx(e) => e.kind(),
+        }
+    }
+
+    /// Whether the receiver dropped bytes because they weren't read in time.
+    /// embedded-io has no kind for this, so it maps to `ErrorKind::Other`.
+    pub fn is_overrun(&self) -> bool {
+        self.serial_kind() == embedded_hal_nb::serial::ErrorKind::Overrun
+    }
+}
+
+#[cfg(feature = "nb")]
+impl<TXE: embedded_hal_nb::serial::Error, RXE: embedded_hal_nb::serial::Error> embedded_io::Error
+    for NbUartError<TXE, RXE>
+{
+    fn kind(&self) -> ErrorKind {
+        use embedded_hal_nb::serial::ErrorKind as SerialKind;
+        match self.serial_kind() {
+            SerialKind::FrameFormat | SerialKind::Parity | SerialKind::Noise => ErrorKind::InvalidData,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+/// Placeholder for a serial half that isn't wired. Every operation on it fails.
+#[cfg(feature = "nb")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NotConnected;
+
+#[cfg(feature = "nb")]
+impl embedded_hal_nb::serial::ErrorType for NotConnected {
+    type Error = embedded_hal_nb::serial::ErrorKind;
+}
+
+#[cfg(feature = "nb")]
+impl embedded_hal_nb::serial::Read<u8> for NotConnected {
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        Err(nb::Error::Other(embedded_hal_nb::serial::ErrorKind::Other))
+    }
+}
+
+#[cfg(feature = "nb")]
+impl embedded_hal_nb::serial::Write<u8> for NotConnected {
+    fn write(&mut self, _word: u8) -> nb::Result<(), Self::Error> {
+        Err(nb::Error::Other(embedded_hal_nb::serial::ErrorKind::Other))
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        Err(nb::Error::Other(embedded_hal_nb::serial::ErrorKind::Other))
+    }
+}
+
+/// Separate transmitter and receiver implementing embedded-hal-nb, as many HALs hand out,
+/// combined into one embedded-io serial port. See
+/// [`PmsX003Sensor::new_tx_rx`](crate::PmsX003Sensor::new_tx_rx).
+#[cfg(feature = "nb")]
+pub struct NbUart<TX, RX: embedded_hal_nb::serial::ErrorType> {
+    tx: TX,
+    rx: RX,
+    /// Receive error hit after a partial read, reported by the next read
+    pending: Option<RX::Error>,
+}
+
+#[cfg(feature = "nb")]
+impl<TX, RX> NbUart<TX, RX>
+where
+    TX: embedded_hal_nb::serial::Write<u8>,
+    RX: embedded_hal_nb::serial::Read<u8>,
+{
+    pub fn new(tx: TX, rx: RX) -> Self {
+        Self { tx, rx, pending: None }
+    }
+
+    /// Returns the wrapped transmitter and receiver
+    pub fn release(self) -> (TX, RX) {
+        (self.tx, self.rx)
+    }
+}
+
+#[cfg(feature = "nb")]
+impl<TX, RX> ErrorType for NbUart<TX, RX>
+where
+    TX: embedded_hal_nb::serial::ErrorType,
+    RX: embedded_hal_nb::serial::ErrorType,
+{
+    type Error = NbUartError<TX::Error, RX::Error>;
+}
+
+#[cfg(feature = "nb")]
+impl<TX, RX> Read for NbUart<TX, RX>
+where
+    TX: embedded_hal_nb::serial::ErrorType,
+    RX: embedded_hal_nb::serial::Read<u8>,
+{
+    /// Blocks for the first byte, then returns what's received without blocking again.
+    /// A receive error after the first byte ends the read early, so the bytes already
+    /// taken from the receiver are returned first and the error by the next read.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let Some((first, rest)) = buf.split_first_mut() else {
+            return Ok(0);
+        };
+        if let Some(e) = self.pending.take() {
+            return Err(NbUartError::Rx(e));
+        }
+        *first = nb::block!(self.rx.read()).map_err(NbUartError::Rx)?;
+
+        let mut n = 1;
+        for byte in rest {
+            match self.rx.read() {
+                Ok(b) => *byte = b,
+                Err(nb::Error::WouldBlock) => break,
+                Err(nb::Error::Other(e)) => {
+                    self.pending = Some(e);
+                    break;
+                }
+            }
+            n += 1;
+        }
+        Ok(n)
+    }
+}
+
+#[cfg(feature = "nb")]
+impl<TX, RX> Write for NbUart<TX, RX>
+where
+    TX: embedded_hal_nb::serial::Write<u8>,
+    RX: embedded_hal_nb::serial::ErrorType,
+{
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        for byte in buf {
+            nb::block!(self.tx.write(*byte)).map_err(NbUartError::Tx)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        nb::block!(self.tx.flush()).map_err(NbUartError::Tx)
+    }
+}
@@ -0,0 +1,523 @@
+//! Sensor models, and frames of those reporting more than particles
+//!
+//! Each model other than the PMS5003, PMS7003 and PMSA003, whose 32-byte frames the driver itself
+//! decodes, is behind a cargo feature named after it: `pms1003`, `pms3003`, `pms5003` for the
+//! PMS5003S and PMS5003T, `pms5003st` and `pms9003m`. All are enabled by default.
+
+use embedded_io::{Read, Write};
+
+use crate::codec::{FrameCodec, PlantowerCodec};
+#[cfg(feature = "pms1003")]
+use crate::codec::Pms1003Codec;
+#[cfg(feature = "pms5003")]
+use crate::codec::{Pms5003sCodec, Pms5003tCodec};
+#[cfg(feature = "pms9003m")]
+use crate::codec::Pms9003mCodec;
+#[cfg(feature = "pms3003")]
+use crate::codec::Pms3003Codec;
+#[cfg(feature = "pms5003st")]
+use crate::codec::Pms5003stCodec;
+use crate::codec::MAX_FRAME_SIZE;
+use crate::{Command, Error, OutputFrame, PmsX003Sensor, SyncStats, MN1, MN2, OUTPUT_FRAME_SIZE};
+
+/// Frame type of a sensor model, completing the particle readings decoded by the model's codec
+/// with the fields only that model reports
+pub trait FromFrameBytes {
+    /// * `particles` - readings decoded by the codec
+    /// * `frame` - raw bytes of the frame with a valid checksum, header included
+    fn from_frame_bytes(particles: OutputFrame, frame: &[u8]) -> Self;
+}
+
+impl FromFrameBytes for OutputFrame {
+    fn from_frame_bytes(particles: OutputFrame, _frame: &[u8]) -> Self {
+        particles
+    }
+}
+
+/// Sensor model known at compile time. Supporting a new model takes a codec implementing this
+/// trait, and a frame type if the model reports more than particles; the driver then reads its
+/// frames with [`PmsX003Sensor::read_frame`]. See [`Variant`] to pick the model at runtime.
+pub trait PmsVariant: FrameCodec {
+    /// Size of a data frame, header and checksum included
+    const FRAME_SIZE: usize;
+
+    type Frame: FromFrameBytes;
+}
+
+impl PmsVariant for PlantowerCodec {
+    const FRAME_SIZE: usize = OUTPUT_FRAME_SIZE;
+    type Frame = OutputFrame;
+}
+
+#[cfg(feature = "pms1003")]
+impl PmsVariant for Pms1003Codec {
+    const FRAME_SIZE: usize = OUTPUT_FRAME_SIZE;
+    type Frame = OutputFrame;
+}
+
+#[cfg(feature = "pms3003")]
+impl PmsVariant for Pms3003Codec {
+    const FRAME_SIZE: usize = 24;
+    type Frame = OutputFrame;
+}
+
+#[cfg(feature = "pms5003")]
+impl PmsVariant for Pms5003sCodec {
+    const FRAME_SIZE: usize = OUTPUT_FRAME_SIZE;
+    type Frame = OutputFrameS;
+}
+
+#[cfg(feature = "pms5003")]
+impl PmsVariant for Pms5003tCodec {
+    const FRAME_SIZE: usize = OUTPUT_FRAME_SIZE;
+    type Frame = OutputFrameT;
+}
+
+#[cfg(feature = "pms5003st")]
+impl PmsVariant for Pms5003stCodec {
+    const FRAME_SIZE: usize = 40;
+    type Frame = OutputFrameSt;
+}
+
+#[cfg(feature = "pms9003m")]
+impl PmsVariant for Pms9003mCodec {
+    const FRAME_SIZE: usize = OUTPUT_FRAME_SIZE;
+    type Frame = OutputFrameM;
+}
+
+impl<UART: Read + Write, V: PmsVariant> PmsX003Sensor<UART, V> {
+    /// Reads sensor status in the frame type of the sensor's model, see `read()`
+    pub fn read_frame(&mut self) -> Result<V::Frame, Error<UART::Error>> {
+        let particles = self.read()?;
+        Ok(V::Frame::from_frame_bytes(particles, self.last_packet()))
+    }
+}
+
+#[cfg(feature = "pms3003")]
+impl<UART: Read + Write> PmsX003Sensor<UART, Pms3003Codec> {
+    /// Creates a new instance for a PMS3003, reporting concentrations only.
+    /// Passive mode isn't available and fails with `Error::UnsupportedByVariant`.
+    /// * `uart` - UART implementing embedded-io Read + Write traits
+    pub fn new_pms3003(uart: UART) -> Self {
+        Self::with_codec(uart, Pms3003Codec)
+    }
+}
+
+#[cfg(feature = "pms1003")]
+impl<UART: Read + Write> PmsX003Sensor<UART, Pms1003Codec> {
+    /// Creates a new instance for a PMS1003/G1, reporting concentrations only.
+    /// Passive mode isn't available and fails with `Error::UnsupportedByVariant`.
+    /// * `uart` - UART implementing embedded-io Read + Write traits
+    pub fn new_pms1003(uart: UART) -> Self {
+        Self::with_codec(uart, Pms1003Codec)
+    }
+}
+
+/// Data reported by a PMS5003T
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg(feature = "pms5003")]
+pub struct OutputFrameT {
+    /// Particle readings, without the >5.0μm and >10μm counts
+    pub particles: OutputFrame,
+    /// Temperature in tenths of °C
+    pub temperature: i16,
+    /// Relative humidity in tenths of %
+    pub humidity: u16,
+}
+
+#[cfg(feature = "pms5003")]
+impl OutputFrameT {
+    /// Parses a frame, validating its header, frame length and checksum
+    pub fn from_buffer<E>(buffer: &[u8; OUTPUT_FRAME_SIZE]) -> Result<Self, Error<E>> {
+        OutputFrame::from_buffer(buffer)?;
+        Ok(Self::from_frame_bytes(Pms5003tCodec.decode(buffer), buffer))
+    }
+}
+
+#[cfg(feature = "pms5003")]
+impl FromFrameBytes for OutputFrameT {
+    fn from_frame_bytes(particles: OutputFrame, buffer: &[u8]) -> Self {
+        Self {
+            particles,
+            temperature: i16::from_be_bytes([buffer[24], buffer[25]]),
+            humidity: u16::from_be_bytes([buffer[26], buffer[27]]),
+        }
+    }
+}
+
+#[cfg(all(feature = "float", feature = "pms5003"))]
+impl OutputFrameT {
+    /// Temperature in °C
+    pub fn temperature_c(&self) -> f32 {
+        self.temperature as f32 / 10.0
+    }
+
+    /// Relative humidity in %
+    pub fn humidity_percent(&self) -> f32 {
+        self.humidity as f32 / 10.0
+    }
+}
+
+#[cfg(feature = "pms5003")]
+impl<UART: Read + Write> PmsX003Sensor<UART, Pms5003tCodec> {
+    /// Creates a new instance for a PMS5003T
+    /// * `uart` - UART implementing embedded-io Read + Write traits
+    pub fn new_pms5003t(uart: UART) -> Self {
+        Self::with_codec(uart, Pms5003tCodec)
+    }
+
+    /// Reads sensor status along with temperature and humidity, see `read()`
+    pub fn read_t(&mut self) -> Result<OutputFrameT, Error<UART::Error>> {
+        self.read_frame()
+    }
+}
+
+/// Data reported by a PMS5003S
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg(feature = "pms5003")]
+pub struct OutputFrameS {
+    /// Particle readings
+    pub particles: OutputFrame,
+    /// Formaldehyde in μg/m³, i.e. thousandths of the mg/m³ the datasheet uses
+    pub hcho: u16,
+}
+
+#[cfg(feature = "pms5003")]
+impl OutputFrameS {
+    /// Parses a frame, validating its header, frame length and checksum
+    pub fn from_buffer<E>(buffer: &[u8; OUTPUT_FRAME_SIZE]) -> Result<Self, Error<E>> {
+        OutputFrame::from_buffer(buffer)?;
+        Ok(Self::from_frame_bytes(Pms5003sCodec.decode(buffer), buffer))
+    }
+
+    /// Formaldehyde in μg/m³
+    pub fn hcho_ug_m3(&self) -> u16 {
+        self.hcho
+    }
+}
+
+#[cfg(feature = "pms5003")]
+impl FromFrameBytes for OutputFrameS {
+    fn from_frame_bytes(particles: OutputFrame, buffer: &[u8]) -> Self {
+        Self {
+            particles,
+            hcho: u16::from_be_bytes([buffer[28], buffer[29]]),
+        }
+    }
+}
+
+#[cfg(all(feature = "float", feature = "pms5003"))]
+impl OutputFrameS {
+    /// Formaldehyde in mg/m³
+    pub fn hcho_mg_m3(&self) -> f32 {
+        self.hcho as f32 / 1000.0
+    }
+}
+
+#[cfg(feature = "pms5003")]
+impl<UART: Read + Write> PmsX003Sensor<UART, Pms5003sCodec> {
+    /// Creates a new instance for a PMS5003S
+    /// * `uart` - UART implementing embedded-io Read + Write traits
+    pub fn new_pms5003s(uart: UART) -> Self {
+        Self::with_codec(uart, Pms5003sCodec)
+    }
+
+    /// Reads sensor status along with formaldehyde, see `read()`
+    pub fn read_s(&mut self) -> Result<OutputFrameS, Error<UART::Error>> {
+        self.read_frame()
+    }
+}
+
+/// Data reported by a PMS5003ST
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg(feature = "pms5003st")]
+pub struct OutputFrameSt {
+    /// Particle readings
+    pub particles: OutputFrame,
+    /// Formaldehyde in μg/m³, i.e. thousandths of the mg/m³ the datasheet uses
+    pub hcho: u16,
+    /// Temperature in tenths of °C
+    pub temperature: i16,
+    /// Relative humidity in tenths of %
+    pub humidity: u16,
+}
+
+#[cfg(feature = "pms5003st")]
+impl OutputFrameSt {
+    /// Parses a frame, validating its header, frame length and checksum
+    pub fn from_buffer<E>(buffer: &[u8; <Pms5003stCodec as PmsVariant>::FRAME_SIZE]) -> Result<Self, Error<E>> {
+        if buffer[0] != MN1 || buffer[1] != MN2 {
+            return Err(Error::IncorrectResponse(None));
+        }
+        let frame_length = u16::from_be_bytes([buffer[2], buffer[3]]);
+        if frame_length != Pms5003stCodec.frame_length() {
+            return Err(Error::FrameLengthMismatch {
+                expected: Pms5003stCodec.frame_length(),
+                got: frame_length,
+            });
+        }
+        if !Pms5003stCodec.checksum_valid(buffer) {
+            return Err(Error::ChecksumError);
+        }
+        Ok(Self::from_frame_bytes(Pms5003stCodec.decode(buffer), buffer))
+    }
+
+    /// Formaldehyde in μg/m³
+    pub fn hcho_ug_m3(&self) -> u16 {
+        self.hcho
+    }
+}
+
+#[cfg(feature = "pms5003st")]
+impl FromFrameBytes for OutputFrameSt {
+    fn from_frame_bytes(particles: OutputFrame, buffer: &[u8]) -> Self {
+        Self {
+            particles,
+            hcho: u16::from_be_bytes([buffer[28], buffer[29]]),
+            temperature: i16::from_be_bytes([buffer[30], buffer[31]]),
+            humidity: u16::from_be_bytes([buffer[32], buffer[33]]),
+        }
+    }
+}
+
+#[cfg(all(feature = "float", feature = "pms5003st"))]
+impl OutputFrameSt {
+    /// Formaldehyde in mg/m³
+    pub fn hcho_mg_m3(&self) -> f32 {
+        self.hcho as f32 / 1000.0
+    }
+
+    /// Temperature in °C
+    pub fn temperature_c(&self) -> f32 {
+        self.temperature as f32 / 10.0
+    }
+
+    /// Relative humidity in %
+    pub fn humidity_percent(&self) -> f32 {
+        self.humidity as f32 / 10.0
+    }
+}
+
+#[cfg(feature = "pms5003st")]
+impl<UART: Read + Write> PmsX003Sensor<UART, Pms5003stCodec> {
+    /// Creates a new instance for a PMS5003ST
+    /// * `uart` - UART implementing embedded-io Read + Write traits
+    pub fn new_pms5003st(uart: UART) -> Self {
+        Self::with_codec(uart, Pms5003stCodec)
+    }
+
+    /// Reads sensor status along with formaldehyde, temperature and humidity, see `read()`
+    pub fn read_st(&mut self) -> Result<OutputFrameSt, Error<UART::Error>> {
+        self.read_frame()
+    }
+}
+
+/// Data reported by a PMS9003M or PMS9103M
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg(feature = "pms9003m")]
+pub struct OutputFrameM {
+    /// Particle readings
+    pub particles: OutputFrame,
+    /// Whether the sensor considers the readings valid, which it doesn't while the fan spins up
+    pub valid: bool,
+}
+
+#[cfg(feature = "pms9003m")]
+impl OutputFrameM {
+    /// Parses a frame, validating its header, frame length and checksum
+    pub fn from_buffer<E>(buffer: &[u8; OUTPUT_FRAME_SIZE]) -> Result<Self, Error<E>> {
+        OutputFrame::from_buffer(buffer)?;
+        Ok(Self::from_frame_bytes(Pms9003mCodec.decode(buffer), buffer))
+    }
+}
+
+#[cfg(feature = "pms9003m")]
+impl FromFrameBytes for OutputFrameM {
+    fn from_frame_bytes(particles: OutputFrame, buffer: &[u8]) -> Self {
+        Self { particles, valid: buffer[29] == 0 }
+    }
+}
+
+#[cfg(feature = "pms9003m")]
+impl<UART: Read + Write> PmsX003Sensor<UART, Pms9003mCodec> {
+    /// Creates a new instance for a PMS9003M or PMS9103M
+    /// * `uart` - UART implementing embedded-io Read + Write traits
+    pub fn new_pms9003m(uart: UART) -> Self {
+        Self::with_codec(uart, Pms9003mCodec)
+    }
+}
+
+/// Sensor model, chosen at runtime, e.g. from configuration. As the codec of a sensor it checks
+/// the frame length and rejects commands the model doesn't understand.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Variant {
+    #[cfg(feature = "pms1003")]
+    Pms1003,
+    #[cfg(feature = "pms3003")]
+    Pms3003,
+    #[default]
+    Pms5003,
+    #[cfg(feature = "pms5003")]
+    Pms5003S,
+    #[cfg(feature = "pms5003")]
+    Pms5003T,
+    #[cfg(feature = "pms5003st")]
+    Pms5003ST,
+    Pms7003,
+    PmsA003,
+    #[cfg(feature = "pms9003m")]
+    Pms9003M,
+    #[cfg(feature = "pms9003m")]
+    Pms9103M,
+}
+
+impl Variant {
+    /// Size of the model's data frames, header and checksum included
+    pub fn frame_size(&self) -> usize {
+        self.frame_length() as usize + 4
+    }
+
+    fn codec(&self) -> &dyn FrameCodec {
+        match self {
+            #[cfg(feature = "pms1003")]
+            Variant::Pms1003 => &Pms1003Codec,
+            #[cfg(feature = "pms3003")]
+            Variant::Pms3003 => &Pms3003Codec,
+            Variant::Pms5003 | Variant::Pms7003 | Variant::PmsA003 => &PlantowerCodec,
+            #[cfg(feature = "pms5003")]
+            Variant::Pms5003S => &Pms5003sCodec,
+            #[cfg(feature = "pms5003")]
+            Variant::Pms5003T => &Pms5003tCodec,
+            #[cfg(feature = "pms5003st")]
+            Variant::Pms5003ST => &Pms5003stCodec,
+            #[cfg(feature = "pms9003m")]
+            Variant::Pms9003M | Variant::Pms9103M => &Pms9003mCodec,
+        }
+    }
+}
+
+impl FrameCodec for Variant {
+    fn frame_length(&self) -> u16 {
+        self.codec().frame_length()
+    }
+
+    fn checksum_valid(&self, frame: &[u8]) -> bool {
+        self.codec().checksum_valid(frame)
+    }
+
+    fn decode(&self, frame: &[u8]) -> OutputFrame {
+        self.codec().decode(frame)
+    }
+
+    fn supports(&self, command: Command) -> bool {
+        self.codec().supports(command)
+    }
+}
+
+/// Data reported by any [`Variant`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VariantFrame {
+    /// Particle readings of models without other sensors
+    Particles(OutputFrame),
+    #[cfg(feature = "pms5003")]
+    S(OutputFrameS),
+    #[cfg(feature = "pms5003")]
+    T(OutputFrameT),
+    #[cfg(feature = "pms5003st")]
+    St(OutputFrameSt),
+    #[cfg(feature = "pms9003m")]
+    M(OutputFrameM),
+}
+
+impl VariantFrame {
+    /// Particle readings, reported by every model
+    pub fn particles(&self) -> &OutputFrame {
+        match self {
+            VariantFrame::Particles(frame) => frame,
+            #[cfg(feature = "pms5003")]
+            VariantFrame::S(frame) => &frame.particles,
+            #[cfg(feature = "pms5003")]
+            VariantFrame::T(frame) => &frame.particles,
+            #[cfg(feature = "pms5003st")]
+            VariantFrame::St(frame) => &frame.particles,
+            #[cfg(feature = "pms9003m")]
+            VariantFrame::M(frame) => &frame.particles,
+        }
+    }
+}
+
+impl<UART: Read + Write> PmsX003Sensor<UART, Variant> {
+    /// Creates a new instance for a sensor model chosen at runtime
+    /// * `uart` - UART implementing embedded-io Read + Write traits
+    /// * `variant` - model of the sensor
+    pub fn new_variant(uart: UART, variant: Variant) -> Self {
+        Self::with_codec(uart, variant)
+    }
+
+    /// Model the sensor was created for or detected as
+    pub fn variant(&self) -> Variant {
+        self.codec
+    }
+
+    /// Listens for a data frame and tells the model from its length, switching the sensor to it.
+    /// 32-byte models can't be told apart and are reported as `Variant::Pms5003`, so the
+    /// PMS5003S and PMS5003T still have to be configured explicitly.
+    pub fn detect(&mut self) -> Result<Variant, Error<UART::Error>> {
+        self.sync = SyncStats::default();
+        let mut buffer = [0u8; MAX_FRAME_SIZE];
+        loop {
+            // So `last_packet()` holds the probed frame rather than earlier traffic
+            self.packet_len = 0;
+            self.read_bytes(&mut buffer[..1]).map_err(Error::Read)?;
+            if buffer[0] != MN1 {
+                self.sync.discarded_bytes = self.sync.discarded_bytes.saturating_add(1);
+                continue;
+            }
+            self.read_bytes(&mut buffer[1..2]).map_err(Error::Read)?;
+            if buffer[1] != MN2 {
+                self.sync.discarded_bytes = self.sync.discarded_bytes.saturating_add(2);
+                self.sync.partial_header = true;
+                continue;
+            }
+            self.read_bytes(&mut buffer[2..4]).map_err(Error::Read)?;
+
+            let variant = match u16::from_be_bytes([buffer[2], buffer[3]]) {
+                #[cfg(feature = "pms3003")]
+                20 => Variant::Pms3003,
+                28 => Variant::Pms5003,
+                #[cfg(feature = "pms5003st")]
+                36 => Variant::Pms5003ST,
+                // Command responses and unknown frames
+                _ => {
+                    self.sync.skipped_packets = self.sync.skipped_packets.saturating_add(1);
+                    continue;
+                }
+            };
+            let frame = &mut buffer[..variant.frame_size()];
+            self.read_bytes(&mut frame[4..]).map_err(Error::Read)?;
+            if !variant.checksum_valid(frame) {
+                return Err(Error::ChecksumError);
+            }
+            self.codec = variant;
+            return Ok(variant);
+        }
+    }
+
+    /// Reads sensor status in the frame type of the sensor's model, see `read()`
+    pub fn read_variant(&mut self) -> Result<VariantFrame, Error<UART::Error>> {
+        let particles = self.read()?;
+        #[cfg(any(feature = "pms5003", feature = "pms5003st", feature = "pms9003m"))]
+        let packet = self.last_packet();
+        Ok(match self.codec {
+            #[cfg(feature = "pms5003")]
+            Variant::Pms5003S => VariantFrame::S(OutputFrameS::from_frame_bytes(particles, packet)),
+            #[cfg(feature = "pms5003")]
+            Variant::Pms5003T => VariantFrame::T(OutputFrameT::from_frame_bytes(particles, packet)),
+            #[cfg(feature = "pms5003st")]
+            Variant::Pms5003ST => VariantFrame::St(OutputFrameSt::from_frame_bytes(particles, packet)),
+            #[cfg(feature = "pms9003m")]
+            Variant::Pms9003M | Variant::Pms9103M => VariantFrame::M(OutputFrameM::from_frame_bytes(particles, packet)),
+            _ => VariantFrame::Particles(particles),
+        })
+    }
+}
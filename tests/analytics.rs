@@ -0,0 +1,243 @@
+use pmsx003::exposure::ExposureDose;
+
+const HOUR_MS: u64 = 3_600_000;
+
+#[test]
+fn exposure_dose_integrates_and_resets_daily() {
+    let mut dose = ExposureDose::new();
+    // 22:00 UTC on some day
+    let start = 22 * HOUR_MS;
+
+    dose.update(start, 10.0);
+    assert_eq!(dose.update(start + 60_000, 10.0), 10.0 / 60.0);
+
+    // A long gap isn't counted
+    assert_eq!(dose.update(start + HOUR_MS, 50.0), 10.0 / 60.0);
+
+    // Crossing midnight starts a new day
+    dose.update(start + 2 * HOUR_MS + 60_000, 20.0);
+    assert_eq!(dose.previous_day(), Some(10.0 / 60.0));
+    assert_eq!(dose.dose(), 0.0);
+}
+
+#[test]
+fn exposure_dose_honors_utc_offset() {
+    let mut dose = ExposureDose::new();
+    dose.set_utc_offset(120);
+
+    // 21:59 and 22:01 UTC straddle local midnight
+    dose.update(22 * HOUR_MS - 60_000, 30.0);
+    dose.update(22 * HOUR_MS + 60_000, 30.0);
+    assert!(dose.previous_day().is_some());
+}
+
+#[test]
+fn exceedance_of_who_guideline() {
+    use pmsx003::exposure::{ExceedanceTracker, Guideline};
+
+    let mut tracker = ExceedanceTracker::new(Guideline::WHO_2021);
+    let mut state = Default::default();
+
+    // A clean day, then a polluted one
+    for hour in 0..48 {
+        let pm2_5 = if hour < 24 { 4.0 } else { 40.0 };
+        for minute in 0..4 {
+            state = tracker.update(hour * HOUR_MS + minute * 15 * 60_000, pm2_5, 10.0);
+        }
+        if hour == 23 {
+            assert!(!tracker.state().any());
+        }
+    }
+    // Closes the second day
+    tracker.update(48 * HOUR_MS, 40.0, 10.0);
+
+    assert!(state.pm2_5_24h && state.pm2_5_annual);
+    assert!(!state.pm10_24h && !state.pm10_annual);
+    assert_eq!(tracker.exceedance_days().pm2_5, 1);
+    assert_eq!(tracker.exceedance_days().pm10, 0);
+}
+
+#[test]
+fn cooking_event_is_detected() {
+    use pmsx003::events::{Detection, EventDetector};
+
+    let mut detector = EventDetector::default();
+    let profile = [5.0, 5.0, 6.0, 40.0, 80.0, 60.0, 30.0, 15.0, 10.0, 6.0];
+    let detections: Vec<_> = profile
+        .iter()
+        .enumerate()
+        .filter_map(|(minute, pm2_5)| detector.update(minute as u64 * 60_000, *pm2_5))
+        .collect();
+
+    assert_eq!(detections.len(), 2);
+    assert!(matches!(detections[0], Detection::Started { start_ms: 180_000, .. }));
+    let Detection::Ended(event) = detections[1] else { panic!("event didn't end") };
+    assert_eq!((event.peak, event.peak_ms), (80.0, 240_000));
+    assert_eq!(event.end_ms, 420_000);
+}
+
+#[test]
+fn history_percentiles() {
+    use pmsx003::history::History;
+    use pmsx003::{Field, OutputFrame};
+
+    let mut history = History::<100>::new();
+    assert_eq!(history.p50(Field::Pm2_5), None);
+
+    // 0..=149, only the last 100 are kept
+    for pm2_5 in (0..150).rev() {
+        history.push(OutputFrame::builder().pm2_5(pm2_5).build_with_checksum());
+    }
+
+    assert_eq!(history.len(), 100);
+    assert_eq!(history.latest().unwrap().pm2_5, 0);
+    assert_eq!(history.p50(Field::Pm2_5), Some(49));
+    assert_eq!(history.p95(Field::Pm2_5), Some(94));
+    assert_eq!(history.p99(Field::Pm2_5), Some(98));
+    assert_eq!(history.percentile(Field::Pm2_5, 100), Some(99));
+    assert_eq!(history.percentile(Field::Pm2_5, 0), Some(0));
+}
+
+#[test]
+fn downsampler_aggregates_minutes() {
+    use pmsx003::downsample::{Downsampler, FieldStats, ONE_MINUTE_MS};
+    use pmsx003::{Field, OutputFrame};
+
+    let mut downsampler = Downsampler::new(ONE_MINUTE_MS);
+    let mut aggregates = Vec::new();
+
+    for second in 30..150 {
+        let frame = OutputFrame::builder().pm2_5(second as u16 % 60).build_with_checksum();
+        aggregates.extend(downsampler.push(second * 1000, &frame));
+    }
+    aggregates.extend(downsampler.flush());
+
+    assert_eq!(aggregates.len(), 3);
+    assert_eq!((aggregates[0].start_ms, aggregates[0].count), (0, 30));
+    assert_eq!(aggregates[0].get(Field::Pm2_5), FieldStats { mean: 44.5, min: 30, max: 59 });
+    assert_eq!(aggregates[1].get(Field::Pm2_5), FieldStats { mean: 29.5, min: 0, max: 59 });
+    assert_eq!((aggregates[2].start_ms, aggregates[2].count), (120_000, 30));
+}
+
+#[test]
+fn divergence_monitor_flags_sustained_anomalies() {
+    use pmsx003::health::DivergenceMonitor;
+    use pmsx003::OutputFrame;
+
+    let healthy = OutputFrame::builder().pm2_5(90).pm2_5_atm(60).pm10(20).pm10_atm(20).build_with_checksum();
+    let diverged = OutputFrame::builder().pm2_5(12).pm2_5_atm(40).build_with_checksum();
+    assert!(!DivergenceMonitor::is_anomalous(&healthy));
+    assert!(DivergenceMonitor::is_anomalous(&diverged));
+
+    let mut monitor = DivergenceMonitor::new(3);
+    assert!(!monitor.update(&diverged));
+    assert!(!monitor.update(&healthy));
+    assert!(!monitor.update(&diverged));
+    assert!(!monitor.update(&diverged));
+    assert!(monitor.update(&diverged));
+
+    // Stays flagged until healthy frames make up for the anomalies
+    assert!(monitor.update(&healthy));
+    assert!(monitor.update(&healthy));
+    assert!(!monitor.update(&healthy));
+}
+
+#[test]
+fn drift_detector_flags_rising_floor() {
+    use pmsx003::health::DriftDetector;
+
+    let mut detector = DriftDetector::default();
+    for day in 0..22u64 {
+        // Clean nights, with a floor rising by half a μg/m³ a day after the first week
+        let floor = 2.0 + day.saturating_sub(7) as f32 * 0.5;
+        for hour in 0..24 {
+            let pm2_5 = if hour < 6 { floor } else { floor + 20.0 };
+            detector.update((day * 24 + hour) * HOUR_MS, pm2_5);
+        }
+        if day == 14 {
+            assert_eq!(detector.drift(), Some(0.0));
+            assert!(!detector.is_drifting());
+        }
+    }
+
+    assert!(detector.is_drifting());
+    // Lowest floor of days 14 to 20 against days 0 to 6
+    assert_eq!(detector.drift(), Some(3.5));
+}
+
+#[test]
+fn smoothing_presets() {
+    use pmsx003::filter::{Ewma, Smoothing};
+
+    assert_eq!(Smoothing::from_name("smooth"), Some(Smoothing::Smooth));
+    assert_eq!(Smoothing::from_name("silky"), None);
+
+    let settle = |preset: Smoothing| {
+        let mut filter = Ewma::from(preset);
+        filter.update(0.0);
+        (1..).find(|_| filter.update(100.0) > 90.0).unwrap()
+    };
+    assert!(settle(Smoothing::Fast) < settle(Smoothing::Balanced));
+    assert!(settle(Smoothing::Balanced) < settle(Smoothing::Smooth));
+}
+
+#[test]
+fn runtime_tracker_persists_and_hints() {
+    use pmsx003::health::{MaintenanceHint, RuntimeTracker};
+
+    let mut tracker = RuntimeTracker::new();
+    tracker.start(0);
+    tracker.tick(90 * 60_000 + 500);
+    tracker.stop(2 * HOUR_MS);
+    // Asleep time isn't counted
+    tracker.start(10 * HOUR_MS);
+    tracker.stop(11 * HOUR_MS);
+    assert_eq!(tracker.hours(), 3.0);
+
+    let mut blob = tracker.to_bytes();
+    let restored = RuntimeTracker::from_bytes(&blob).unwrap();
+    assert_eq!(restored.hours(), 3.0);
+    blob[3] ^= 1;
+    assert!(RuntimeTracker::from_bytes(&blob).is_none());
+
+    let mut tracker = restored;
+    tracker.start(20 * HOUR_MS);
+    tracker.stop(25 * HOUR_MS);
+    assert_eq!(tracker.hint(), MaintenanceHint::Ok);
+    assert_eq!(tracker.hint_for(8), MaintenanceHint::Replace);
+    assert_eq!(tracker.hint_for(10), MaintenanceHint::PlanReplacement);
+    assert_eq!(tracker.hint_for(11), MaintenanceHint::Ok);
+}
+
+#[test]
+fn runtime_tracker_tolerates_clock_stepping_back() {
+    use pmsx003::health::RuntimeTracker;
+
+    let mut tracker = RuntimeTracker::new();
+    tracker.start(HOUR_MS);
+    tracker.tick(2 * HOUR_MS);
+    tracker.tick(HOUR_MS + 60_000);
+    tracker.stop(3 * HOUR_MS);
+    assert_eq!(tracker.hours(), 2.0);
+}
+
+#[test]
+fn dual_sensor_fusion() {
+    use pmsx003::fusion::{DualSensorFusion, Source, Unit};
+    use pmsx003::OutputFrame;
+
+    let pm = |pm2_5: u16| OutputFrame::builder().pm2_5(pm2_5).pm2_5_atm(pm2_5).build_with_checksum();
+    let mut fusion = DualSensorFusion::new();
+
+    let fused = fusion.update(Some(&pm(10)), Some(&pm(14))).unwrap();
+    assert_eq!((fused.pm2_5, fused.source), (12.0, Source::Both));
+
+    // B jumps away, A continues the previous readings
+    let fused = fusion.update(Some(&pm(11)), Some(&pm(60))).unwrap();
+    assert!(fusion.is_diverged());
+    assert_eq!((fused.pm2_5, fused.source), (11.0, Source::Single(Unit::A)));
+
+    let fused = fusion.update(None, Some(&pm(13))).unwrap();
+    assert_eq!(fused.source, Source::Single(Unit::B));
+    assert!(fusion.update(None, None).is_none());
+}
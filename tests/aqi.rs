@@ -0,0 +1,90 @@
+use pmsx003::aqi::{self, AqiCategory, NowCast};
+
+#[test]
+fn pm2_5_breakpoints() {
+    assert_eq!(aqi::pm2_5(0.0).value, 0);
+    assert_eq!(aqi::pm2_5(9.0).value, 50);
+    assert_eq!(aqi::pm2_5(9.09).category, AqiCategory::Good);
+    assert_eq!(aqi::pm2_5(35.4).value, 100);
+    assert_eq!(aqi::pm2_5(35.5).category, AqiCategory::UnhealthyForSensitiveGroups);
+    assert_eq!(aqi::pm2_5(1000.0).value, 500);
+}
+
+#[test]
+fn pm10_breakpoints() {
+    assert_eq!(aqi::pm10(54.0).value, 50);
+    assert_eq!(aqi::pm10(155.0).value, 101);
+    assert_eq!(aqi::pm10(425.0).category, AqiCategory::Hazardous);
+}
+
+#[test]
+fn nowcast_requires_two_recent_hours() {
+    let mut nowcast = NowCast::new();
+    nowcast.push_hour(Some(10.0));
+    assert_eq!(nowcast.value(), None);
+    nowcast.push_hour(Some(10.0));
+    assert_eq!(nowcast.value(), Some(10.0));
+}
+
+#[test]
+fn nowcast_weights_recent_hours() {
+    let mut nowcast = NowCast::new();
+    nowcast.push_hour(Some(10.0));
+    nowcast.push_hour(Some(40.0));
+    // Weight factor is clamped to 0.5: (40 + 0.5 * 10) / 1.5
+    assert_eq!(nowcast.value(), Some(30.0));
+}
+
+#[cfg(feature = "async")]
+mod stream {
+    use core::pin::Pin;
+    use core::task::{Context, Poll, Waker};
+
+    use futures_core::Stream;
+    use pmsx003::OutputFrame;
+    use pmsx003::stream::{AqiConfig, FrameStreamExt};
+
+    struct Frames(core::ops::Range<u16>);
+
+    impl Stream for Frames {
+        type Item = Result<OutputFrame, ()>;
+
+        fn poll_next(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            Poll::Ready(self.0.next().map(|_| {
+                Ok(OutputFrame { pm2_5_atm: 40, pm10_atm: 60, ..Default::default() })
+            }))
+        }
+    }
+
+    #[test]
+    fn yields_index_per_frame() {
+        let mut stream = Frames(0..3).aqi(AqiConfig { alpha: 0.5, samples_per_hour: 1 });
+        let mut cx = Context::from_waker(Waker::noop());
+
+        let Poll::Ready(Some(Ok(first))) = Pin::new(&mut stream).poll_next(&mut cx) else { panic!() };
+        assert!(!first.nowcast);
+        assert_eq!(first.pm2_5.value, aqi_of(40.0));
+
+        let Poll::Ready(Some(Ok(second))) = Pin::new(&mut stream).poll_next(&mut cx) else { panic!() };
+        assert!(second.nowcast);
+        assert_eq!(second.worst(), second.pm2_5);
+    }
+
+    fn aqi_of(c: f32) -> u16 {
+        pmsx003::aqi::pm2_5(c).value
+    }
+}
+
+#[test]
+fn max_severity_picks_worst_pollutant() {
+    use pmsx003::OutputFrame;
+
+    let dusty = OutputFrame::builder().pm2_5_atm(5).pm10_atm(200).build();
+    let smoky = OutputFrame::builder().pm2_5_atm(40).pm10_atm(45).build();
+    let clean = OutputFrame::builder().pm2_5_atm(2).pm10_atm(4).build();
+
+    assert_eq!(aqi::frame(&dusty).pollutant, aqi::Pollutant::Pm10);
+    assert!(aqi::frame(&dusty) > aqi::frame(&smoky));
+    assert_eq!(aqi::max_severity(&[smoky.clone(), dusty.clone(), clean]), Some(&dusty));
+    assert_eq!(aqi::max_severity(&[]), None);
+}
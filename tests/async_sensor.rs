@@ -0,0 +1,227 @@
+#![cfg(feature = "async")]
+
+mod common;
+
+use core::cell::RefCell;
+use core::future::{poll_fn, Future};
+use core::pin::pin;
+use core::task::{Context, Poll, Waker};
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use embedded_io_async::{ErrorType, Read, Write};
+use futures_core::Stream;
+use pmsx003::asynch::PmsX003SensorAsync;
+use pmsx003::{Command, Error, ResponseFrame};
+use common::frame;
+
+/// Serial port replaying canned bytes and recording writes
+struct UartMock {
+    rx: Vec<u8>,
+    tx: Vec<u8>,
+}
+
+impl ErrorType for UartMock {
+    type Error = embedded_io::ErrorKind;
+}
+
+impl Read for UartMock {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let n = buf.len().min(self.rx.len());
+        buf[..n].copy_from_slice(&self.rx[..n]);
+        self.rx.drain(..n);
+        Ok(n)
+    }
+}
+
+impl Write for UartMock {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.tx.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+#[test]
+fn read_skips_responses() {
+    let mut rx = vec![0x42, 0x4D, 0x00, 0x04, 0xE1, 0x00, 0x01, 0x74];
+    rx.extend_from_slice(&frame(21));
+    let mut sensor = PmsX003SensorAsync::new(UartMock { rx, tx: Vec::new() });
+
+    assert_eq!(block_on(sensor.read()).unwrap().pm2_5, 21);
+}
+
+#[test]
+fn read_shares_resync_logic_with_blocking_driver() {
+    let mut rx = vec![0x00, 0x42, 0x00, 0x42, 0x4D, 0x00, 0x10];
+    rx.extend_from_slice(&frame(22));
+    let mut sensor = PmsX003SensorAsync::new(UartMock { rx, tx: Vec::new() });
+
+    assert_eq!(block_on(sensor.read()).unwrap().pm2_5, 22);
+    let stats = sensor.sync_stats();
+    assert_eq!((stats.discarded_bytes, stats.skipped_packets, stats.partial_header), (7, 1, true));
+}
+
+#[test]
+fn faults_become_errors_on_request() {
+    let mut faulty = frame(25);
+    faulty[29] = 0x01;
+    faulty[31] += 0x01;
+    let mut rx = faulty.to_vec();
+    rx.extend_from_slice(&faulty);
+    let mut sensor = PmsX003SensorAsync::new(UartMock { rx, tx: Vec::new() });
+
+    assert_eq!(block_on(sensor.read()).unwrap().fault().unwrap().error_code(), 0x01);
+    sensor.set_faults_as_errors(true);
+    assert!(matches!(block_on(sensor.read()), Err(Error::SensorFault(_))));
+}
+
+#[test]
+fn commands_wait_for_response() {
+    let mut rx = frame(1).to_vec();
+    rx.extend_from_slice(&[0x42, 0x4D, 0x00, 0x04, 0xE1, 0x00, 0x01, 0x74]);
+    rx.extend_from_slice(&[0x42, 0x4D, 0x00, 0x04, 0xE1, 0x00, 0x01, 0x74]);
+    let mut sensor = PmsX003SensorAsync::new(UartMock { rx, tx: Vec::new() });
+
+    assert!(block_on(sensor.passive()).is_ok());
+    assert!(matches!(
+        block_on(sensor.sleep()),
+        Err(Error::IncorrectResponse(Some(ResponseFrame { cmd: 0xE1, data: 0x00 })))
+    ));
+    block_on(sensor.request()).unwrap();
+
+    let mut sent = Command::SetPassive.encode().to_vec();
+    sent.extend_from_slice(&Command::Sleep.encode());
+    sent.extend_from_slice(&Command::Request.encode());
+    assert_eq!(sensor.release().tx, sent);
+}
+
+/// Serial port whose bytes arrive over time, pending while none are available
+struct TrickleUart {
+    rx: Rc<RefCell<VecDeque<u8>>>,
+}
+
+impl ErrorType for TrickleUart {
+    type Error = embedded_io::ErrorKind;
+}
+
+impl Read for TrickleUart {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        poll_fn(|_| match self.rx.borrow_mut().pop_front() {
+            Some(byte) => {
+                buf[0] = byte;
+                Poll::Ready(Ok(1))
+            }
+            None => Poll::Pending,
+        })
+        .await
+    }
+}
+
+impl Write for TrickleUart {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        Ok(buf.len())
+    }
+}
+
+#[test]
+fn cancelled_read_keeps_partial_frame() {
+    let rx = Rc::new(RefCell::new(VecDeque::from_iter(frame(24)[..20].iter().copied())));
+    let mut sensor = PmsX003SensorAsync::new(TrickleUart { rx: rx.clone() });
+
+    {
+        let mut read = pin!(sensor.read());
+        let mut cx = Context::from_waker(Waker::noop());
+        assert!(read.as_mut().poll(&mut cx).is_pending());
+    }
+    assert!(rx.borrow().is_empty());
+
+    rx.borrow_mut().extend(&frame(24)[20..]);
+    assert_eq!(block_on(sensor.read()).unwrap().pm2_5, 24);
+}
+
+#[cfg(not(feature = "active-only"))]
+#[test]
+fn request_and_read_skips_late_responses() {
+    let mut rx = vec![0x42, 0x4D, 0x00, 0x04, 0xE1, 0x00, 0x01, 0x74];
+    rx.extend_from_slice(&frame(23));
+    let mut sensor = PmsX003SensorAsync::new(UartMock { rx, tx: Vec::new() });
+
+    assert_eq!(block_on(sensor.request_and_read()).unwrap().pm2_5, 23);
+    assert_eq!(sensor.release().tx, Command::Request.encode());
+}
+
+#[test]
+fn frames_stream_yields_frames() {
+    let mut rx = frame(1).to_vec();
+    rx.extend_from_slice(&frame(2));
+    let mut sensor = PmsX003SensorAsync::new(UartMock { rx, tx: Vec::new() });
+    let mut frames = pin!(sensor.frames());
+
+    let mut next = || block_on(poll_fn(|cx| frames.as_mut().poll_next(cx)));
+    assert_eq!(next().unwrap().unwrap().pm2_5, 1);
+    assert_eq!(next().unwrap().unwrap().pm2_5, 2);
+    assert!(next().unwrap().is_err());
+}
+
+#[cfg(feature = "embassy")]
+mod timeout {
+    use core::sync::atomic::{AtomicU64, Ordering};
+    use core::task::Waker;
+
+    use embassy_time::Duration;
+
+    use super::*;
+
+    /// Clock advancing by a millisecond every time it's read
+    struct MockDriver(AtomicU64);
+
+    impl embassy_time_driver::Driver for MockDriver {
+        fn now(&self) -> u64 {
+            self.0.fetch_add(1000, Ordering::Relaxed)
+        }
+
+        fn schedule_wake(&self, _at: u64, waker: &Waker) {
+            waker.wake_by_ref();
+        }
+    }
+
+    embassy_time_driver::time_driver_impl!(static DRIVER: MockDriver = MockDriver(AtomicU64::new(0)));
+
+    /// Sensor which never answers
+    struct SilentUart;
+
+    impl ErrorType for SilentUart {
+        type Error = embedded_io::ErrorKind;
+    }
+
+    impl Read for SilentUart {
+        async fn read(&mut self, _buf: &mut [u8]) -> Result<usize, Self::Error> {
+            core::future::pending().await
+        }
+    }
+
+    impl Write for SilentUart {
+        async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            Ok(buf.len())
+        }
+    }
+
+    #[test]
+    fn silent_sensor_times_out() {
+        let mut sensor = PmsX003SensorAsync::new(SilentUart);
+        sensor.set_timeout(Duration::from_millis(50));
+
+        assert!(matches!(block_on(sensor.read()), Err(Error::Timeout)));
+        assert!(matches!(block_on(sensor.sleep()), Err(Error::Timeout)));
+    }
+}
@@ -0,0 +1,269 @@
+#![cfg(feature = "async")]
+
+mod common;
+
+use core::cell::Cell;
+use core::future::Future;
+use core::pin::pin;
+use core::task::{Context, Poll, Waker};
+
+use embassy_futures::select::{select, Either};
+use embassy_futures::yield_now;
+use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+use embedded_hal_async::delay::DelayNs;
+use embedded_io_async::{ErrorType, Read, Write};
+use pmsx003::asynch::{split, SensorArray, SplitState};
+use pmsx003::Error;
+use common::frame;
+
+/// Replays `data` once the sensor has been sent something, emulating a sensor answering
+struct RxMock<'a> {
+    data: &'a [u8],
+    sent: &'a Cell<bool>,
+}
+
+struct TxMock<'a> {
+    written: [u8; 64],
+    len: usize,
+    sent: &'a Cell<bool>,
+}
+
+impl ErrorType for RxMock<'_> {
+    type Error = embedded_io::ErrorKind;
+}
+
+impl Read for RxMock<'_> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        while self.data.is_empty() || !self.sent.get() {
+            // Idle line
+            yield_now().await;
+        }
+        let n = buf.len().min(self.data.len());
+        buf[..n].copy_from_slice(&self.data[..n]);
+        self.data = &self.data[n..];
+        Ok(n)
+    }
+}
+
+impl ErrorType for TxMock<'_> {
+    type Error = embedded_io::ErrorKind;
+}
+
+impl Write for TxMock<'_> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.written[self.len..self.len + buf.len()].copy_from_slice(buf);
+        self.len += buf.len();
+        self.sent.set(true);
+        Ok(buf.len())
+    }
+}
+
+/// Delay which expires once polled a second time
+struct DelayMock;
+
+impl DelayNs for DelayMock {
+    async fn delay_ns(&mut self, _: u32) {
+        yield_now().await
+    }
+}
+
+/// Delay which never expires
+struct NoTimeout;
+
+impl DelayNs for NoTimeout {
+    async fn delay_ns(&mut self, _: u32) {
+        core::future::pending().await
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+#[test]
+fn handle_receives_frames_from_runner() {
+    let mut data = [0u8; 40];
+    data[..8].copy_from_slice(&[0x42, 0x4D, 0x00, 0x04, 0xE1, 0x00, 0x01, 0x74]);
+    data[8..].copy_from_slice(&frame(12));
+
+    let sent = Cell::new(true);
+    let rx = RxMock { data: &data, sent: &sent };
+    let tx = TxMock { written: [0; 64], len: 0, sent: &sent };
+    let state = SplitState::<NoopRawMutex, _>::new();
+    let (handle, mut runner) = split(rx, tx, NoTimeout, &state);
+
+    match block_on(select(runner.run(), handle.read())) {
+        Either::Second(frame) => assert_eq!(frame.pm2_5, 12),
+        Either::First(e) => panic!("runner stopped: {:?}", e),
+    }
+}
+
+#[test]
+fn handle_commands_are_transmitted() {
+    let sent = Cell::new(false);
+    let rx = RxMock { data: &[], sent: &sent };
+    let mut tx = TxMock { written: [0; 64], len: 0, sent: &sent };
+    let state = SplitState::<NoopRawMutex, _>::new();
+    let (handle, mut runner) = split(rx, &mut tx, NoTimeout, &state);
+
+    block_on(select(runner.run(), async {
+        handle.wake().await.unwrap();
+    }));
+
+    assert_eq!(&tx.written[..tx.len], &[0x42, 0x4D, 0xE4, 0x00, 0x01, 0x01, 0x74]);
+}
+
+#[test]
+fn sleep_tolerates_interleaved_frames() {
+    let mut data = [0u8; 40];
+    data[..32].copy_from_slice(&frame(7));
+    data[32..].copy_from_slice(&[0x42, 0x4D, 0x00, 0x04, 0xE4, 0x00, 0x01, 0x77]);
+
+    let sent = Cell::new(false);
+    let rx = RxMock { data: &data, sent: &sent };
+    let tx = TxMock { written: [0; 64], len: 0, sent: &sent };
+    let state = SplitState::<NoopRawMutex, _>::new();
+    let (handle, mut runner) = split(rx, tx, NoTimeout, &state);
+
+    match block_on(select(runner.run(), handle.sleep())) {
+        Either::Second(result) => assert!(result.is_ok()),
+        Either::First(e) => panic!("runner stopped: {:?}", e),
+    }
+}
+
+#[test]
+fn sleep_times_out_without_response() {
+    let sent = Cell::new(false);
+    let rx = RxMock { data: &[], sent: &sent };
+    let tx = TxMock { written: [0; 64], len: 0, sent: &sent };
+    let state = SplitState::<NoopRawMutex, _>::new();
+    let (handle, mut runner) = split(rx, tx, DelayMock, &state);
+
+    match block_on(select(runner.run(), handle.sleep())) {
+        Either::Second(result) => assert!(matches!(result, Err(Error::NoResponse))),
+        Either::First(e) => panic!("runner stopped: {:?}", e),
+    }
+}
+
+/// Sensor acknowledging passive mode commands only
+#[derive(Default)]
+struct PassiveAcker {
+    replies: core::cell::RefCell<std::collections::VecDeque<u8>>,
+}
+
+impl ErrorType for &PassiveAcker {
+    type Error = embedded_io::ErrorKind;
+}
+
+impl Read for &PassiveAcker {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        loop {
+            if let Some(byte) = self.replies.borrow_mut().pop_front() {
+                buf[0] = byte;
+                return Ok(1);
+            }
+            yield_now().await;
+        }
+    }
+}
+
+impl Write for &PassiveAcker {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        if buf[2] == 0xE1 {
+            self.replies.borrow_mut().extend([0x42, 0x4D, 0x00, 0x04, 0xE1, 0x00, 0x01, 0x74]);
+        }
+        Ok(buf.len())
+    }
+}
+
+#[cfg(not(feature = "active-only"))]
+#[test]
+fn cancelled_command_result_goes_to_nobody() {
+    let sensor = PassiveAcker::default();
+    let state = SplitState::<NoopRawMutex, _>::new();
+    let (handle, mut runner) = split(&sensor, &sensor, DelayMock, &state);
+
+    let result = block_on(select(runner.run(), async {
+        // Given up on once queued, so the runner times it out while the next one waits
+        select(handle.sleep(), yield_now()).await;
+        handle.passive().await
+    }));
+    match result {
+        Either::Second(result) => assert!(result.is_ok(), "got {:?}", result),
+        Either::First(e) => panic!("runner stopped: {:?}", e),
+    }
+}
+
+#[test]
+fn array_reads_all_sensors() {
+    let (first, second) = (frame(5), frame(9));
+
+    let sent = Cell::new(true);
+    let state = [SplitState::<NoopRawMutex, _>::new(), SplitState::new()];
+    let (a, mut runner_a) = split(
+        RxMock { data: &first, sent: &sent },
+        TxMock { written: [0; 64], len: 0, sent: &sent },
+        NoTimeout,
+        &state[0],
+    );
+    let (b, mut runner_b) = split(
+        RxMock { data: &second, sent: &sent },
+        TxMock { written: [0; 64], len: 0, sent: &sent },
+        NoTimeout,
+        &state[1],
+    );
+    let mut array = SensorArray::new([a, b], NoTimeout);
+
+    let runners = select(runner_a.run(), runner_b.run());
+    match block_on(select(runners, array.read_all())) {
+        Either::Second([a, b]) => assert_eq!((a.unwrap().pm2_5, b.unwrap().pm2_5), (5, 9)),
+        Either::First(_) => panic!("runner stopped"),
+    }
+}
+
+/// Delay which expires after being polled a number of times
+struct DelayPolls(u32);
+
+impl DelayNs for DelayPolls {
+    async fn delay_ns(&mut self, _: u32) {
+        for _ in 0..self.0 {
+            yield_now().await;
+        }
+    }
+}
+
+#[test]
+fn array_times_out_silent_sensors() {
+    let data = frame(5);
+
+    let sent = Cell::new(true);
+    let state = [SplitState::<NoopRawMutex, _>::new(), SplitState::new()];
+    let (a, mut runner_a) = split(
+        RxMock { data: &data, sent: &sent },
+        TxMock { written: [0; 64], len: 0, sent: &sent },
+        NoTimeout,
+        &state[0],
+    );
+    let (b, mut runner_b) = split(
+        RxMock { data: &[], sent: &sent },
+        TxMock { written: [0; 64], len: 0, sent: &sent },
+        NoTimeout,
+        &state[1],
+    );
+    let mut array = SensorArray::new([a, b], DelayPolls(10));
+
+    let runners = select(runner_a.run(), runner_b.run());
+    match block_on(select(runners, array.read_all())) {
+        Either::Second([a, b]) => {
+            assert_eq!(a.unwrap().pm2_5, 5);
+            assert!(matches!(b, Err(Error::Timeout)));
+        }
+        Either::First(_) => panic!("runner stopped"),
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::VecDeque;
+
+use embedded_hal::i2c::{ErrorKind, ErrorType, I2c, Operation};
+use pmsx003::bridge::Sc16is75x;
+use pmsx003::PmsX003Sensor;
+
+/// SC16IS752 on an I2C bus, with the sensor attached to channel B
+struct BridgeMock {
+    registers: Vec<(u8, u8)>,
+    rx_fifo: VecDeque<u8>,
+    tx_fifo: Vec<u8>,
+    // Bytes in the transmit FIFO yet to be shifted out
+    tx_pending: usize,
+}
+
+impl ErrorType for BridgeMock {
+    type Error = ErrorKind;
+}
+
+impl I2c for BridgeMock {
+    fn transaction(&mut self, address: u8, operations: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+        assert_eq!(address, 0x48);
+        let mut subaddress = None;
+        for operation in operations {
+            match operation {
+                Operation::Write(data) => {
+                    let (first, rest) = data.split_first().unwrap();
+                    assert_eq!(first & 0x06, 0x02, "channel B");
+                    let reg = first >> 3;
+                    subaddress = Some(reg);
+                    match (reg, rest) {
+                        (_, []) => {}
+                        (0x00, bytes) if self.registers.contains(&(0x03, 0x03)) => {
+                            assert!(bytes.len() <= 16, "burst longer than the bridge's buffer");
+                            self.tx_fifo.extend_from_slice(bytes);
+                            self.tx_pending += bytes.len();
+                        }
+                        (reg, [value]) => self.registers.push((reg, *value)),
+                        _ => panic!("unexpected burst"),
+                    }
+                }
+                Operation::Read(buf) => match subaddress.unwrap() {
+                    0x00 => buf.iter_mut().for_each(|b| *b = self.rx_fifo.pop_front().unwrap()),
+                    0x08 => {
+                        buf[0] = (64 - self.tx_pending) as u8;
+                        // Shifts out a few bytes between polls
+                        self.tx_pending = self.tx_pending.saturating_sub(8);
+                    }
+                    0x09 => buf[0] = self.rx_fifo.len().min(64) as u8,
+                    reg => panic!("unexpected read of register {reg}"),
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn sensor_over_i2c_bridge() {
+    let mut frame = [0u8; 32];
+    frame[..4].copy_from_slice(&[0x42, 0x4D, 0x00, 0x1C]);
+    frame[7] = 25;
+    frame[30..].copy_from_slice(&(0x42u16 + 0x4D + 0x1C + 25).to_be_bytes());
+    let mut rx_fifo = VecDeque::from(frame.to_vec());
+    rx_fifo.extend([0x42, 0x4D, 0x00, 0x04, 0xE4, 0x00, 0x01, 0x77]);
+
+    let mock = BridgeMock {
+        registers: Vec::new(),
+        rx_fifo,
+        tx_fifo: Vec::new(),
+        tx_pending: 0,
+    };
+    let bridge = Sc16is75x::new_i2c(mock, 0x48, 1, 14_745_600).unwrap();
+    let mut sensor = PmsX003Sensor::new(bridge);
+
+    assert_eq!(sensor.read().unwrap().pm2_5, 25);
+    sensor.sleep().unwrap();
+}
+
+#[test]
+fn bridge_configures_9600_8n1() {
+    let mock = BridgeMock {
+        registers: Vec::new(),
+        rx_fifo: VecDeque::new(),
+        tx_fifo: Vec::new(),
+        tx_pending: 0,
+    };
+    let mut bridge = Sc16is75x::new_i2c(mock, 0x48, 1, 14_745_600).unwrap();
+    embedded_io::Write::write_all(&mut bridge, &[1, 2, 3]).unwrap();
+
+    let mock = bridge.release().release();
+    assert_eq!(mock.registers, [(0x03, 0x80), (0x00, 96), (0x01, 0), (0x03, 0x03), (0x02, 0x07)]);
+    assert_eq!(mock.tx_fifo, [1, 2, 3]);
+}
+
+#[test]
+fn long_writes_are_split_and_flush_waits_for_empty_fifo() {
+    use pmsx003::bridge::BridgeBus;
+
+    let mock = BridgeMock {
+        registers: Vec::new(),
+        rx_fifo: VecDeque::new(),
+        tx_fifo: Vec::new(),
+        tx_pending: 0,
+    };
+    let bridge = Sc16is75x::new_i2c(mock, 0x48, 1, 14_745_600).unwrap();
+    let mut bus = bridge.release();
+    let data: Vec<u8> = (0..40).collect();
+    bus.write_register(1, 0x00, &data).unwrap();
+    assert_eq!(bus.release().tx_fifo, data);
+
+    let mock = BridgeMock {
+        registers: Vec::new(),
+        rx_fifo: VecDeque::new(),
+        tx_fifo: Vec::new(),
+        tx_pending: 0,
+    };
+    let mut bridge = Sc16is75x::new_i2c(mock, 0x48, 1, 14_745_600).unwrap();
+    embedded_io::Write::write_all(&mut bridge, &[0x55; 20]).unwrap();
+    embedded_io::Write::flush(&mut bridge).unwrap();
+    assert_eq!(bridge.release().release().tx_pending, 0);
+}
@@ -0,0 +1,62 @@
+#![cfg(feature = "capi")]
+
+mod common;
+
+use core::ffi::c_void;
+
+use pmsx003::capi::{pms_init, pms_parse_buffer, pms_read, PmsFrame, PmsSensor, PmsStatus};
+use common::frame;
+
+struct Port {
+    rx: Vec<u8>,
+    tx: Vec<u8>,
+}
+
+unsafe extern "C" fn read(user_data: *mut c_void, buf: *mut u8, len: usize) -> i32 {
+    let port = unsafe { &mut *user_data.cast::<Port>() };
+    let n = len.min(port.rx.len());
+    if n == 0 {
+        return -1;
+    }
+    unsafe { core::ptr::copy_nonoverlapping(port.rx.as_ptr(), buf, n) };
+    port.rx.drain(..n);
+    n as i32
+}
+
+unsafe extern "C" fn write(user_data: *mut c_void, buf: *const u8, len: usize) -> i32 {
+    let port = unsafe { &mut *user_data.cast::<Port>() };
+    port.tx.extend_from_slice(unsafe { core::slice::from_raw_parts(buf, len) });
+    len as i32
+}
+
+#[test]
+fn parse_buffer() {
+    let buffer = frame(17);
+    let mut out = PmsFrame::default();
+    let status = unsafe { pms_parse_buffer(buffer.as_ptr(), buffer.len(), &mut out) };
+    assert_eq!(status, PmsStatus::Ok);
+    assert_eq!(out.pm2_5, 17);
+
+    let mut corrupted = buffer;
+    corrupted[31] ^= 1;
+    let status = unsafe { pms_parse_buffer(corrupted.as_ptr(), corrupted.len(), &mut out) };
+    assert_eq!(status, PmsStatus::ChecksumError);
+
+    let status = unsafe { pms_parse_buffer(buffer.as_ptr(), 8, &mut out) };
+    assert_eq!(status, PmsStatus::InvalidArgument);
+}
+
+#[test]
+fn read_through_callbacks() {
+    let mut port = Port { rx: frame(23).to_vec(), tx: Vec::new() };
+    let mut sensor = core::mem::MaybeUninit::<PmsSensor>::uninit();
+    let mut out = PmsFrame::default();
+
+    unsafe {
+        let user_data = (&mut port as *mut Port).cast();
+        assert_eq!(pms_init(sensor.as_mut_ptr(), read, write, user_data), PmsStatus::Ok);
+        assert_eq!(pms_read(sensor.as_mut_ptr(), &mut out), PmsStatus::Ok);
+        assert_eq!(pms_read(sensor.as_mut_ptr(), &mut out), PmsStatus::ReadFailed);
+    }
+    assert_eq!(out.pm2_5, 23);
+}
@@ -0,0 +1,25 @@
+mod common;
+
+use pmsx003::capture::Replay;
+use pmsx003::ParseError;
+use common::frame;
+
+#[test]
+fn replay_decodes_capture() {
+    let mut corrupted = frame(2);
+    corrupted[31] ^= 1;
+
+    let mut data = vec![0x00, 0x42];
+    data.extend_from_slice(&frame(1));
+    data.extend_from_slice(&[0x42, 0x4D, 0x00, 0x04, 0xE1, 0x00, 0x01, 0x74]);
+    data.extend_from_slice(&corrupted);
+    data.extend_from_slice(&frame(3));
+    data.extend_from_slice(&frame(4)[..10]);
+
+    let mut replay = Replay::new(&data);
+    assert_eq!(replay.next().unwrap().unwrap().pm2_5, 1);
+    assert!(matches!(replay.next(), Some(Err(ParseError::ChecksumError))));
+    assert_eq!(replay.next().unwrap().unwrap().pm2_5, 3);
+    assert!(replay.next().is_none());
+    assert_eq!(replay.remaining(), &frame(4)[..10]);
+}
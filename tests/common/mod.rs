@@ -0,0 +1,9 @@
+//! Fixtures shared by the integration tests
+#![allow(dead_code)]
+
+use pmsx003::OutputFrame;
+
+/// Bytes of a valid frame reporting `pm2_5`
+pub fn frame(pm2_5: u16) -> [u8; 32] {
+    OutputFrame::builder().pm2_5(pm2_5).build().to_bytes()
+}
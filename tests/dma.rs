@@ -0,0 +1,137 @@
+mod common;
+
+use pmsx003::dma::CircularDma;
+use pmsx003::parser::FrameParser;
+use pmsx003::ParseError;
+use common::frame;
+
+#[test]
+fn parser_resyncs_and_skips_responses() {
+    let mut corrupted = frame(2);
+    corrupted[31] ^= 1;
+
+    let mut data = vec![0x42, 0x42, 0x00, 0x42, 0x4D, 0x00, 0x07];
+    data.extend_from_slice(&[0x42, 0x4D, 0x00, 0x04, 0xE1, 0x00, 0x01, 0x74]);
+    data.extend_from_slice(&frame(1));
+    data.extend_from_slice(&corrupted);
+
+    let mut parser = FrameParser::new();
+    let mut frames = Vec::new();
+    parser.push_slice(&data, |frame| frames.push(frame));
+
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0].as_ref().unwrap().pm2_5, 1);
+    assert!(matches!(frames[1], Err(ParseError::ChecksumError)));
+}
+
+#[test]
+fn circular_dma_handles_wraparound() {
+    // Three frames written through a 48-byte ring, interrupts firing as the DMA would
+    let stream: Vec<u8> = [frame(1), frame(2), frame(3)].concat();
+    let mut ring = [0u8; 48];
+    let mut dma = CircularDma::new();
+    let mut received = Vec::new();
+
+    for (i, byte) in stream.iter().enumerate() {
+        ring[i % 48] = *byte;
+        match (i + 1) % 48 {
+            24 => dma.on_half_transfer(&ring, |f| received.push(f.unwrap().pm2_5)),
+            0 => dma.on_transfer_complete(&ring, |f| received.push(f.unwrap().pm2_5)),
+            _ => {}
+        }
+    }
+    // Idle line after the last byte
+    dma.on_idle(&ring, 48 - stream.len() % 48, |f| received.push(f.unwrap().pm2_5));
+
+    assert_eq!(received, [1, 2, 3]);
+}
+
+#[test]
+fn mailbox_keeps_latest_frame() {
+    use pmsx003::parser::FrameMailbox;
+
+    fn assert_send<T: Send>() {}
+    assert_send::<FrameMailbox>();
+
+    let mut corrupted = frame(3);
+    corrupted[31] ^= 1;
+    let mut mailbox = FrameMailbox::new();
+    for byte in [frame(1), frame(2), corrupted].concat() {
+        mailbox.push(byte);
+    }
+
+    assert_eq!(mailbox.take().unwrap().pm2_5, 2);
+    assert!(mailbox.take().is_none());
+    assert_eq!(mailbox.errors(), 1);
+}
+
+#[test]
+fn finds_frames_in_place() {
+    use pmsx003::parser::{find_and_parse_frame, ScanError};
+
+    let mut corrupted = frame(2);
+    corrupted[31] ^= 1;
+    let mut data = vec![0x00, 0x42, 0x4D, 0x00, 0x04, 0xE1, 0x00, 0x01, 0x74];
+    data.extend_from_slice(&frame(1));
+    data.extend_from_slice(&corrupted);
+    data.extend_from_slice(&frame(3)[..10]);
+
+    let (first, consumed) = find_and_parse_frame(&data).unwrap();
+    assert_eq!((first.pm2_5, consumed), (1, 41));
+    let rest = &data[consumed..];
+    assert_eq!(find_and_parse_frame(rest), Err(ScanError::ChecksumError { consumed: 32 }));
+    assert_eq!(find_and_parse_frame(&rest[32..]), Err(ScanError::Incomplete { consumed: 0 }));
+    assert_eq!(find_and_parse_frame(&[0x01, 0x02, 0x42]).unwrap_err().consumed(), 2);
+}
+
+#[test]
+fn finds_frame_after_truncated_header() {
+    use pmsx003::parser::find_and_parse_frame;
+
+    let mut data = vec![0x42, 0x4D];
+    data.extend_from_slice(&frame(5));
+
+    let (found, consumed) = find_and_parse_frame(&data).unwrap();
+    assert_eq!((found.pm2_5, consumed), (5, 34));
+}
+
+#[test]
+fn output_frame_parses_from_slice() {
+    use pmsx003::OutputFrame;
+
+    let mut data = vec![0x13, 0x37];
+    data.extend_from_slice(&frame(4));
+    data.push(0x42);
+
+    let (parsed, consumed) = OutputFrame::parse_from_slice(&data).unwrap();
+    assert_eq!((parsed.pm2_5, consumed), (4, 34));
+    assert_eq!(OutputFrame::parse_from_slice(&data[consumed..]).unwrap_err().consumed(), 0);
+}
+
+#[test]
+fn accumulator_reassembles_chunks() {
+    use pmsx003::parser::FrameAccumulator;
+
+    let data = [frame(1), frame(2), frame(3)].concat();
+    let mut accumulator = FrameAccumulator::<4>::new();
+    let mut frames = Vec::new();
+    for chunk in data.chunks(13) {
+        frames.extend(accumulator.extend(chunk).map(|frame| frame.unwrap().pm2_5));
+    }
+
+    assert_eq!(frames, [1, 2, 3]);
+}
+
+#[test]
+fn accumulator_keeps_results_left_unread() {
+    use pmsx003::parser::FrameAccumulator;
+
+    let data = [frame(1), frame(2), frame(3)].concat();
+    let mut accumulator = FrameAccumulator::<2>::new();
+    let _ = accumulator.extend(&data[..64]);
+    assert_eq!(accumulator.extend(&[]).next().unwrap().unwrap().pm2_5, 1);
+
+    let frames: Vec<u16> = accumulator.extend(&data[64..]).map(|frame| frame.unwrap().pm2_5).collect();
+    assert_eq!(frames, [2, 3]);
+    assert_eq!(accumulator.dropped(), 0);
+}
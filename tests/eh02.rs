@@ -0,0 +1,53 @@
+#![cfg(feature = "eh02")]
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use embedded_hal_02::serial::{Read, Write};
+use pmsx003::PmsX003Sensor;
+
+/// embedded-hal 0.2 receiver replaying canned bytes
+struct RxMock {
+    bytes: Vec<u8>,
+}
+
+impl Read<u8> for RxMock {
+    type Error = ();
+
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        match self.bytes.is_empty() {
+            true => Err(nb::Error::Other(())),
+            false => Ok(self.bytes.remove(0)),
+        }
+    }
+}
+
+/// embedded-hal 0.2 transmitter recording writes
+struct TxMock {
+    bytes: Rc<RefCell<Vec<u8>>>,
+}
+
+impl Write<u8> for TxMock {
+    type Error = ();
+
+    fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
+        self.bytes.borrow_mut().push(word);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[test]
+fn sensor_from_eh02_halves() {
+    let rx = RxMock {
+        bytes: vec![0x42, 0x4D, 0x00, 0x04, 0xE4, 0x00, 0x01, 0x77],
+    };
+    let sent = Rc::new(RefCell::new(Vec::new()));
+    let mut pms = PmsX003Sensor::new_eh02(TxMock { bytes: sent.clone() }, rx);
+
+    pms.sleep().unwrap();
+    assert_eq!(*sent.borrow(), [0x42, 0x4D, 0xE4, 0x00, 0x00, 0x01, 0x73]);
+}
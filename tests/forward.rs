@@ -0,0 +1,103 @@
+#![cfg(feature = "async")]
+
+use core::future::Future;
+use core::pin::{pin, Pin};
+use core::task::{Context, Poll, Waker};
+
+use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+use embassy_sync::channel::Channel;
+use futures_core::Stream;
+use futures_sink::Sink;
+use pmsx003::stream::{forward_to_channel, forward_to_sink, ForwardStats, OverflowPolicy};
+
+struct Counter(core::ops::Range<u16>);
+
+impl Stream for Counter {
+    type Item = u16;
+
+    fn poll_next(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<u16>> {
+        Poll::Ready(self.0.next())
+    }
+}
+
+fn poll_once<F: Future>(future: F) -> Poll<F::Output> {
+    pin!(future).poll(&mut Context::from_waker(Waker::noop()))
+}
+
+#[test]
+fn drop_oldest_keeps_latest_items() {
+    let channel = Channel::<NoopRawMutex, u16, 2>::new();
+    let stats = poll_once(forward_to_channel(Counter(0..5), &channel, OverflowPolicy::DropOldest));
+
+    assert_eq!(stats, Poll::Ready(ForwardStats { forwarded: 5, dropped: 3 }));
+    assert_eq!(channel.try_receive(), Ok(3));
+    assert_eq!(channel.try_receive(), Ok(4));
+}
+
+#[test]
+fn drop_newest_keeps_earliest_items() {
+    let channel = Channel::<NoopRawMutex, u16, 2>::new();
+    let stats = poll_once(forward_to_channel(Counter(0..5), &channel, OverflowPolicy::DropNewest));
+
+    assert_eq!(stats, Poll::Ready(ForwardStats { forwarded: 2, dropped: 3 }));
+    assert_eq!(channel.try_receive(), Ok(0));
+}
+
+#[test]
+fn block_waits_for_consumer() {
+    let channel = Channel::<NoopRawMutex, u16, 2>::new();
+    assert!(poll_once(forward_to_channel(Counter(0..5), &channel, OverflowPolicy::Block)).is_pending());
+}
+
+/// Stream of a few items that then stays pending, like a sensor between frames
+struct Endless(core::ops::Range<u16>);
+
+impl Stream for Endless {
+    type Item = u16;
+
+    fn poll_next(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<u16>> {
+        match self.0.next() {
+            Some(item) => Poll::Ready(Some(item)),
+            None => Poll::Pending,
+        }
+    }
+}
+
+/// Sink delivering items only when flushed, like a framed writer
+#[derive(Default)]
+struct BufferingSink {
+    buffered: Vec<u16>,
+    delivered: Vec<u16>,
+}
+
+impl Sink<u16> for BufferingSink {
+    type Error = ();
+
+    fn poll_ready(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), ()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: u16) -> Result<(), ()> {
+        self.buffered.push(item);
+        Ok(())
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), ()>> {
+        let this = &mut *self;
+        this.delivered.append(&mut this.buffered);
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), ()>> {
+        self.poll_flush(cx)
+    }
+}
+
+#[test]
+fn sink_is_flushed_while_stream_runs() {
+    for policy in [OverflowPolicy::Block, OverflowPolicy::DropNewest] {
+        let mut sink = BufferingSink::default();
+        assert!(poll_once(forward_to_sink(Endless(0..3), &mut sink, policy)).is_pending());
+        assert_eq!(sink.delivered, [0, 1, 2]);
+    }
+}
@@ -0,0 +1,76 @@
+#![cfg(feature = "hpma")]
+
+use embedded_io::{ErrorKind, ErrorType, Read, Write};
+use pmsx003::hpma::{Hpma115s0, HpmaCommand};
+use pmsx003::Error;
+
+/// Serial port replaying canned bytes and recording writes
+struct UartMock {
+    rx: Vec<u8>,
+    tx: Vec<u8>,
+}
+
+impl ErrorType for UartMock {
+    type Error = ErrorKind;
+}
+
+impl Read for UartMock {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let n = buf.len().min(self.rx.len());
+        buf[..n].copy_from_slice(&self.rx[..n]);
+        self.rx.drain(..n);
+        Ok(n)
+    }
+}
+
+impl Write for UartMock {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.tx.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+fn auto_send_frame(pm2_5: u16, pm10: u16) -> Vec<u8> {
+    let mut bytes = vec![0x42, 0x4D, 0x00, 0x1C, 0x00, 0x00];
+    bytes.extend_from_slice(&pm2_5.to_be_bytes());
+    bytes.extend_from_slice(&pm10.to_be_bytes());
+    bytes.resize(30, 0);
+    let checksum: u16 = bytes.iter().map(|b| *b as u16).sum();
+    bytes.extend_from_slice(&checksum.to_be_bytes());
+    bytes
+}
+
+#[test]
+fn commands_are_acknowledged() {
+    let mut sensor = Hpma115s0::new(UartMock { rx: vec![0xA5, 0xA5, 0x96, 0x96], tx: Vec::new() });
+
+    sensor.send(HpmaCommand::StopAutoSend).unwrap();
+    assert!(matches!(sensor.send(HpmaCommand::StartMeasurement), Err(Error::IncorrectResponse(_))));
+    assert_eq!(sensor.release().tx, [0x68, 0x01, 0x20, 0x77, 0x68, 0x01, 0x01, 0x96]);
+}
+
+#[test]
+fn read_measurement_skips_auto_send_frame() {
+    let mut rx = auto_send_frame(1, 2);
+    rx.extend_from_slice(&[0x40, 0x05, 0x04, 0x00, 0x23, 0x00, 0x30, 0x64]);
+    let mut sensor = Hpma115s0::new(UartMock { rx, tx: Vec::new() });
+
+    let frame = sensor.read_measurement().unwrap();
+    assert_eq!((frame.pm2_5, frame.pm10, frame.pm2_5_atm), (35, 48, 35));
+    assert_eq!(sensor.release().tx, [0x68, 0x01, 0x04, 0x93]);
+}
+
+#[test]
+fn read_returns_auto_send_frames() {
+    let mut rx = vec![0xA5, 0xA5];
+    rx.extend_from_slice(&auto_send_frame(12, 20));
+    let mut sensor = Hpma115s0::new(UartMock { rx, tx: Vec::new() });
+
+    let frame = sensor.read().unwrap();
+    assert_eq!((frame.pm2_5, frame.pm10), (12, 20));
+    assert_eq!(sensor.sync_stats().discarded_bytes, 2);
+}
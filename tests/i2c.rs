@@ -0,0 +1,96 @@
+#![cfg(feature = "pmsa003i")]
+
+use embedded_hal::i2c::{ErrorKind, ErrorType, I2c, Operation};
+use pmsx003::i2c::I2cPmsA003i;
+
+/// Bus returning a canned frame to reads from the PMSA003I
+struct BlockingI2cMock {
+    frame: [u8; 32],
+}
+
+impl ErrorType for BlockingI2cMock {
+    type Error = ErrorKind;
+}
+
+impl I2c for BlockingI2cMock {
+    fn transaction(&mut self, address: u8, operations: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+        if address != 0x12 {
+            return Err(ErrorKind::NoAcknowledge(embedded_hal::i2c::NoAcknowledgeSource::Address));
+        }
+        for operation in operations {
+            if let Operation::Read(buf) = operation {
+                buf.copy_from_slice(&self.frame[..buf.len()]);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn reads_frame_over_blocking_i2c() {
+    let mut data = [0u8; 32];
+    data[..4].copy_from_slice(&[0x42, 0x4D, 0x00, 0x1C]);
+    data[9] = 23;
+    data[30..].copy_from_slice(&(0x42u16 + 0x4D + 0x1C + 23).to_be_bytes());
+
+    let mut sensor = I2cPmsA003i::new(BlockingI2cMock { frame: data });
+    assert_eq!(sensor.read().unwrap().pm10, 23);
+
+    let mut sensor = I2cPmsA003i::new_with_address(sensor.release(), 0x13);
+    assert!(matches!(sensor.read(), Err(pmsx003::Error::Read(_))));
+}
+
+#[cfg(feature = "async")]
+mod asynch {
+    use core::future::Future;
+    use core::pin::pin;
+    use core::task::{Context, Poll, Waker};
+
+    use embedded_hal_async::i2c::{ErrorKind, ErrorType, I2c, Operation};
+    use pmsx003::asynch::I2cPmsA003iAsync;
+
+    struct I2cMock {
+        frame: [u8; 32],
+    }
+
+    impl ErrorType for I2cMock {
+        type Error = ErrorKind;
+    }
+
+    impl I2c for I2cMock {
+        async fn transaction(&mut self, address: u8, operations: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+            assert_eq!(address, 0x12);
+            for operation in operations {
+                if let Operation::Read(buf) = operation {
+                    buf.copy_from_slice(&self.frame[..buf.len()]);
+                }
+            }
+            Ok(())
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("mock bus never blocks"),
+        }
+    }
+
+    fn frame(pm10: u16) -> [u8; 32] {
+        pmsx003::OutputFrame::builder().pm10(pm10).build().to_bytes()
+    }
+
+    #[test]
+    fn reads_frame_over_i2c() {
+        let mut sensor = I2cPmsA003iAsync::new(I2cMock { frame: frame(21) });
+        assert_eq!(block_on(sensor.read()).unwrap().pm10, 21);
+    }
+
+    #[test]
+    fn rejects_corrupted_frame() {
+        let mut data = frame(21);
+        data[9] ^= 0xFF;
+        let mut sensor = I2cPmsA003iAsync::new(I2cMock { frame: data });
+        assert!(matches!(block_on(sensor.read()), Err(pmsx003::Error::ChecksumError)));
+    }
+}
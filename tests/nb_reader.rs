@@ -0,0 +1,61 @@
+#![cfg(feature = "nb")]
+
+use std::collections::VecDeque;
+
+use embedded_hal_nb::serial::{ErrorKind, ErrorType, Read};
+use pmsx003::nb_reader::NbReader;
+use pmsx003::{Error, OutputFrame};
+
+/// Receiver handing out bytes, with `None` standing for an empty receive buffer
+struct RxMock {
+    bytes: VecDeque<Option<u8>>,
+}
+
+impl ErrorType for RxMock {
+    type Error = ErrorKind;
+}
+
+impl Read for RxMock {
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        match self.bytes.pop_front() {
+            Some(Some(byte)) => Ok(byte),
+            Some(None) | None => Err(nb::Error::WouldBlock),
+        }
+    }
+}
+
+fn bytes(frame: &OutputFrame) -> Vec<u8> {
+    let mut bytes = vec![frame.start1, frame.start2];
+    bytes.extend_from_slice(&frame.frame_length.to_be_bytes());
+    for (_, value) in frame.iter_fields() {
+        bytes.extend_from_slice(&value.to_be_bytes());
+    }
+    bytes.extend_from_slice(&frame.reserved().to_be_bytes());
+    bytes.extend_from_slice(&frame.check.to_be_bytes());
+    bytes
+}
+
+#[test]
+fn partial_frames_are_kept_between_calls() {
+    let frame = OutputFrame::builder().pm2_5(13).build_with_checksum();
+    let data = bytes(&frame);
+    let mut rx = RxMock { bytes: VecDeque::new() };
+    rx.bytes.extend(data[..10].iter().map(|b| Some(*b)));
+    rx.bytes.push_back(None);
+    rx.bytes.extend(data[10..].iter().map(|b| Some(*b)));
+
+    let mut reader = NbReader::new(rx);
+    assert!(matches!(reader.read(), Err(nb::Error::WouldBlock)));
+    assert_eq!(reader.read().unwrap().pm2_5, 13);
+    assert!(matches!(reader.read(), Err(nb::Error::WouldBlock)));
+}
+
+#[test]
+fn corrupted_frames_are_reported() {
+    let mut frame = OutputFrame::builder().pm2_5(13).build_with_checksum();
+    frame.check ^= 1;
+    let rx = RxMock { bytes: bytes(&frame).into_iter().map(Some).collect() };
+
+    let mut reader = NbReader::new(rx);
+    assert!(matches!(reader.read(), Err(nb::Error::Other(Error::ChecksumError))));
+}
@@ -0,0 +1,175 @@
+#[cfg(feature = "float")]
+#[test]
+fn float_views_match_fields() {
+    let frame = pmsx003::OutputFrame { pm1_0: 1, pm2_5: 2, pm10: 3, pm2_5_atm: 4, ..Default::default() };
+    let (pm1_0, pm2_5, pm10): (f32, f32, f32) = (&frame).into();
+    assert_eq!((pm1_0, pm2_5, pm10), (1.0, 2.0, 3.0));
+    assert_eq!(frame.pm_atm_f32().1, 4.0);
+}
+
+#[test]
+fn iter_fields_yields_measurements_in_order() {
+    let frame = pmsx003::OutputFrame { pm2_5: 35, beyond_10_0: 2, ..Default::default() };
+    let mut fields = frame.iter_fields();
+
+    assert_eq!(fields.next(), Some(("pm1_0", 0)));
+    assert_eq!(fields.next(), Some(("pm2_5", 35)));
+    assert_eq!(fields.last(), Some(("beyond_10_0", 2)));
+}
+
+#[test]
+fn index_by_field() {
+    use pmsx003::Field;
+
+    let frame = pmsx003::OutputFrame { pm10_atm: 48, ..Default::default() };
+    assert_eq!(frame[Field::Pm10Atm], 48);
+    assert_eq!(Field::from_name("pm10_atm"), Some(Field::Pm10Atm));
+    assert_eq!(Field::from_name("check"), None);
+}
+
+#[test]
+fn builder_produces_consistent_frame() {
+    let frame = pmsx003::OutputFrame::builder().pm2_5(35).pm10_atm(60).build_with_checksum();
+
+    assert_eq!((frame.start1, frame.start2, frame.frame_length), (0x42, 0x4D, 28));
+    assert_eq!(frame.check, 0x42 + 0x4D + 28 + 35 + 60);
+}
+
+#[test]
+fn standard_and_atmospheric_sets() {
+    use pmsx003::Concentrations;
+
+    let frame = pmsx003::OutputFrame::builder().pm1_0(40).pm2_5(62).pm10(80).pm1_0_atm(33).pm2_5_atm(48).pm10_atm(60).build();
+
+    assert_eq!(frame.standard(), Concentrations { pm1_0: 40, pm2_5: 62, pm10: 80 });
+    assert_eq!(frame.atmospheric(), Concentrations { pm1_0: 33, pm2_5: 48, pm10: 60 });
+}
+
+#[test]
+fn range_flag_follows_highest_concentration() {
+    use pmsx003::{OutputFrame, RangeFlag};
+
+    assert_eq!(OutputFrame::builder().pm10(500).build().range_flag(), RangeFlag::Normal);
+    assert_eq!(OutputFrame::builder().pm10(501).pm10_atm(340).build().range_flag(), RangeFlag::Degraded);
+    assert_eq!(OutputFrame::builder().pm2_5_atm(1000).build().range_flag(), RangeFlag::Degraded);
+    assert_eq!(OutputFrame::builder().pm10(1200).build().range_flag(), RangeFlag::Saturated);
+}
+
+#[test]
+fn version_and_error_code_split_reserved_word() {
+    use pmsx003::{SensorFault, SensorFaults};
+
+    let mut buffer = [0u8; 32];
+    buffer[..4].copy_from_slice(&[0x42, 0x4D, 0x00, 0x1C]);
+    buffer[28..30].copy_from_slice(&[0x97, 0x02]);
+    let checksum: u16 = buffer[..30].iter().map(|b| *b as u16).sum();
+    buffer[30..].copy_from_slice(&checksum.to_be_bytes());
+
+    let frame = pmsx003::OutputFrame::from_buffer::<()>(&buffer).unwrap();
+    assert_eq!((frame.version, frame.error_code, frame.reserved()), (0x97, 0x02, 0x9702));
+    assert_eq!(frame.fault().unwrap().error_code(), 0x02);
+
+    let frame = pmsx003::OutputFrame::builder().version(0x91).build();
+    assert_eq!(frame.fault(), None);
+
+    // Every set bit is reported
+    let faults = SensorFaults::from_error_code(0x83).unwrap();
+    let reported: Vec<_> = faults.iter().collect();
+    assert_eq!(reported, [SensorFault::Unknown(0x01), SensorFault::Unknown(0x02), SensorFault::Unknown(0x80)]);
+    assert!(faults.contains(SensorFault::Unknown(0x02)));
+    assert!(!faults.contains(SensorFault::Unknown(0x04)));
+}
+
+#[test]
+fn from_buffer_checks_frame_length() {
+    let mut buffer = [0u8; 32];
+    buffer[..4].copy_from_slice(&[0x42, 0x4D, 0x00, 0x14]);
+    let checksum = pmsx003::checksum(&buffer[..30]);
+    buffer[30..].copy_from_slice(&checksum.to_be_bytes());
+
+    assert!(matches!(
+        pmsx003::OutputFrame::from_buffer::<()>(&buffer),
+        Err(pmsx003::Error::FrameLengthMismatch { expected: 28, got: 20 })
+    ));
+}
+
+#[test]
+fn to_bytes_round_trips() {
+    let frame = pmsx003::OutputFrame { pm2_5: 35, beyond_0_3: 900, version: 0x91, ..Default::default() };
+    let bytes = frame.to_bytes();
+
+    assert!(pmsx003::verify_checksum(&bytes));
+    let parsed = pmsx003::OutputFrame::from_buffer::<()>(&bytes).unwrap();
+    assert_eq!((parsed.pm2_5, parsed.beyond_0_3, parsed.version, parsed.frame_length), (35, 900, 0x91, 28));
+    assert_eq!(parsed.to_bytes(), bytes);
+}
+
+#[test]
+fn frame_ref_decodes_on_demand() {
+    use pmsx003::{Field, OutputFrameRef};
+
+    let frame = pmsx003::OutputFrame { pm2_5: 35, beyond_10_0: 2, error_code: 1, ..Default::default() };
+    let mut bytes = frame.to_bytes();
+    let view = OutputFrameRef::new::<()>(&bytes).unwrap();
+
+    assert_eq!((view.pm2_5(), view.get(Field::Beyond10_0), view.error_code()), (35, 2, 1));
+    assert_eq!(view.to_frame(), pmsx003::OutputFrame::from_buffer::<()>(&bytes).unwrap());
+
+    bytes[7] ^= 1;
+    assert!(matches!(OutputFrameRef::new::<()>(&bytes), Err(pmsx003::Error::ChecksumError)));
+}
+
+#[test]
+fn checksum_utilities() {
+    use pmsx003::{checksum, verify_checksum, Command};
+
+    assert_eq!(checksum(&[0x42, 0x4D, 0xE4, 0x00, 0x00]), 0x0173);
+    assert!(verify_checksum(&Command::Sleep.encode()));
+    assert!(!verify_checksum(&[0x42, 0x4D, 0xE4, 0x00, 0x00, 0x01, 0x74]));
+    assert!(!verify_checksum(&[0x00]));
+}
+
+#[test]
+fn debug_output_is_compact_or_annotated() {
+    let frame = pmsx003::OutputFrame::builder().pm1_0(1).pm2_5(2).pm10(3).build();
+
+    let compact = format!("{:?}", frame);
+    assert!(compact.starts_with("OutputFrame { cf1: 1/2/3 μg/m³, atm: 0/0/0 μg/m³"));
+    assert!(!compact.contains('\n'));
+
+    let annotated = format!("{:#?}", frame);
+    assert!(annotated.contains("// Standard particles (CF=1)\n    pm1_0: 1 μg/m³,"));
+    assert!(annotated.contains("// Atmospheric environment (ATM)"));
+}
+
+#[test]
+fn deduplicator_emits_changes_and_keepalives() {
+    use pmsx003::dedup::Deduplicator;
+
+    let a = pmsx003::OutputFrame::builder().pm2_5(5).build_with_checksum();
+    let b = pmsx003::OutputFrame::builder().pm2_5(6).build_with_checksum();
+    let mut dedup = Deduplicator::new(2);
+
+    let emitted: Vec<bool> = [&a, &a, &a, &a, &b, &b]
+        .iter()
+        .map(|frame| dedup.filter((*frame).clone()).is_some())
+        .collect();
+    assert_eq!(emitted, [true, false, false, true, true, false]);
+}
+
+#[test]
+fn deduplicator_without_keepalive_survives_long_stable_periods() {
+    use pmsx003::dedup::Deduplicator;
+
+    let a = pmsx003::OutputFrame::builder().pm2_5(5).build_with_checksum();
+    let b = pmsx003::OutputFrame::builder().pm2_5(6).build_with_checksum();
+    let mut dedup = Deduplicator::new(0);
+
+    assert!(dedup.filter(a.clone()).is_some());
+    for _ in 0..70_000 {
+        assert!(dedup.filter(a.clone()).is_none());
+    }
+    assert_eq!(dedup.suppressed(), u16::MAX);
+    assert!(dedup.filter(b).is_some());
+    assert_eq!(dedup.suppressed(), 0);
+}
@@ -0,0 +1,56 @@
+use std::cell::RefCell;
+use std::convert::Infallible;
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{ErrorType, OutputPin};
+use pmsx003::pins::ControlPins;
+
+/// Pin logging its level changes
+struct PinMock<'a> {
+    name: &'static str,
+    log: &'a RefCell<Vec<(&'static str, bool)>>,
+}
+
+impl ErrorType for PinMock<'_> {
+    type Error = Infallible;
+}
+
+impl OutputPin for PinMock<'_> {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.log.borrow_mut().push((self.name, false));
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.log.borrow_mut().push((self.name, true));
+        Ok(())
+    }
+}
+
+struct NoDelay;
+
+impl DelayNs for NoDelay {
+    fn delay_ns(&mut self, _: u32) {}
+}
+
+#[test]
+fn control_pins_drive_set_and_reset() {
+    let log = RefCell::new(Vec::new());
+    let mut pins = ControlPins::new(PinMock { name: "set", log: &log }, PinMock { name: "reset", log: &log });
+
+    pins.sleep().unwrap();
+    pins.wake().unwrap();
+    pins.reset(&mut NoDelay).unwrap();
+
+    assert_eq!(*log.borrow(), [("set", false), ("set", true), ("reset", false), ("reset", true)]);
+}
+
+#[cfg(all(feature = "std", target_os = "linux"))]
+#[test]
+fn gpio_line_reports_missing_chips() {
+    use pmsx003::linux_gpio::GpioLine;
+
+    assert!(GpioLine::open("/dev/gpiochip-missing", 4, false).is_err());
+    // Not a GPIO chip, so the line request is refused
+    assert!(GpioLine::open("/dev/null", 4, false).is_err());
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use pmsx003::protocol::{Event, Protocol};
+use pmsx003::{Command, ParseError, ResponseFrame};
+use common::frame;
+
+#[test]
+fn handles_bytes_without_io() {
+    let mut corrupted = frame(2);
+    corrupted[31] ^= 1;
+    let mut data = vec![0x00, 0x42, 0x00, 0x42, 0x4D, 0x00, 0x10];
+    data.extend_from_slice(&[0x42, 0x4D, 0x00, 0x04, 0xE1, 0x00, 0x01, 0x74]);
+    data.extend_from_slice(&frame(1));
+    data.extend_from_slice(&corrupted);
+
+    let mut protocol = Protocol::new();
+    let events: Vec<Event> = data.iter().filter_map(|byte| protocol.handle_byte(*byte)).collect();
+
+    assert_eq!(events.len(), 3);
+    assert_eq!(events[0], Event::Response(ResponseFrame { cmd: 0xE1, data: 0x00 }));
+    assert!(matches!(&events[1], Event::Frame(frame) if frame.pm2_5 == 1));
+    assert_eq!(events[2], Event::Error(ParseError::ChecksumError));
+
+    let stats = protocol.sync_stats();
+    assert_eq!((stats.discarded_bytes, stats.skipped_packets, stats.partial_header), (7, 1, true));
+}
+
+#[test]
+fn resyncs_on_header_inside_truncated_one() {
+    // The length of the first header went missing
+    let mut data = vec![0x42, 0x4D];
+    data.extend_from_slice(&frame(6));
+
+    let mut protocol = Protocol::new();
+    let events: Vec<Event> = data.iter().filter_map(|byte| protocol.handle_byte(*byte)).collect();
+
+    assert!(matches!(&events[..], [Event::Frame(frame)] if frame.pm2_5 == 6));
+    let stats = protocol.sync_stats();
+    assert_eq!((stats.discarded_bytes, stats.skipped_packets), (2, 1));
+}
+
+#[test]
+fn encodes_commands() {
+    assert_eq!(Protocol::encode_command(Command::Sleep), [0x42, 0x4D, 0xE4, 0x00, 0x00, 0x01, 0x73]);
+}
+
+#[test]
+fn counters_saturate_on_long_streams() {
+    let mut protocol = Protocol::new();
+    let mut corrupted = frame(1);
+    corrupted[31] ^= 1;
+    for _ in 0..70_000 {
+        // Packet of unknown length
+        for byte in [0x42, 0x4D, 0x00, 0x10] {
+            protocol.handle_byte(byte);
+        }
+        for byte in corrupted {
+            protocol.handle_byte(byte);
+        }
+    }
+
+    let stats = protocol.sync_stats();
+    assert_eq!((stats.skipped_packets, stats.corrupted_frames), (u16::MAX, u16::MAX));
+    assert_eq!(stats.discarded_bytes, 4 * 70_000);
+}
@@ -0,0 +1,629 @@
+use embedded_io::{ErrorKind, ErrorType, Read, ReadReady, Write};
+use pmsx003::{Command, Error, OutputFrame, ParseMode, PmsX003Sensor, ReadStrategy};
+
+/// Serial port replaying canned bytes and recording writes
+struct UartMock {
+    rx: Vec<u8>,
+    tx: Vec<u8>,
+    // Read size every read must use, like a DMA-based UART
+    read_size: Option<usize>,
+}
+
+impl UartMock {
+    fn new(frames: &[OutputFrame]) -> Self {
+        let mut rx = Vec::new();
+        for frame in frames {
+            rx.extend_from_slice(&bytes(frame));
+        }
+        Self { rx, tx: Vec::new(), read_size: None }
+    }
+}
+
+impl ErrorType for UartMock {
+    type Error = ErrorKind;
+}
+
+impl Read for UartMock {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        if let Some(size) = self.read_size {
+            assert_eq!(buf.len(), size);
+        }
+        let n = buf.len().min(self.rx.len());
+        buf[..n].copy_from_slice(&self.rx[..n]);
+        self.rx.drain(..n);
+        Ok(n)
+    }
+}
+
+impl Write for UartMock {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.tx.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+fn bytes(frame: &OutputFrame) -> Vec<u8> {
+    let mut bytes = vec![frame.start1, frame.start2];
+    bytes.extend_from_slice(&frame.frame_length.to_be_bytes());
+    for (_, value) in frame.iter_fields() {
+        bytes.extend_from_slice(&value.to_be_bytes());
+    }
+    bytes.extend_from_slice(&frame.reserved().to_be_bytes());
+    bytes.extend_from_slice(&frame.check.to_be_bytes());
+    bytes
+}
+
+fn frame(pm2_5: u16) -> OutputFrame {
+    OutputFrame::builder().pm2_5(pm2_5).build_with_checksum()
+}
+
+#[test]
+fn read_returns_next_frame() {
+    let mut sensor = PmsX003Sensor::new(UartMock::new(&[frame(10), frame(11)]));
+    assert_eq!(sensor.read().unwrap().pm2_5, 10);
+    assert_eq!(sensor.read().unwrap().pm2_5, 11);
+}
+
+#[test]
+fn sensor_borrows_uart_between_reads() {
+    let mut uart = UartMock::new(&[frame(12), frame(13)]);
+
+    {
+        let mut sensor = PmsX003Sensor::new(&mut uart);
+        assert_eq!(sensor.read().unwrap().pm2_5, 12);
+    }
+
+    // The UART is free for other peripherals until the sensor is rebuilt
+    uart.write_all(b"other").unwrap();
+    assert_eq!(PmsX003Sensor::new(&mut uart).read().unwrap().pm2_5, 13);
+    assert_eq!(uart.tx, b"other");
+}
+
+#[test]
+fn release_returns_uart() {
+    let mut sensor = PmsX003Sensor::new(UartMock::new(&[frame(14)]));
+    sensor.wake().unwrap();
+
+    let uart = sensor.release();
+    assert_eq!(uart.tx, Command::Wake.encode());
+    assert_eq!(PmsX003Sensor::new(uart).read().unwrap().pm2_5, 14);
+}
+
+#[test]
+fn inner_uart_accessible_without_release() {
+    let mut sensor = PmsX003Sensor::new(UartMock::new(&[frame(15), frame(16)]));
+
+    // Flush the receive FIFO, dropping the stale frame
+    sensor.inner_mut().rx.drain(..32);
+    assert_eq!(sensor.inner().rx.len(), 32);
+    assert_eq!(sensor.read().unwrap().pm2_5, 16);
+}
+
+#[test]
+fn checksum_retries_resync_after_corrupted_frames() {
+    // A software UART duplicated a byte of the first frame and dropped one of the second,
+    // losing the header of the third
+    let mut rx = bytes(&frame(17));
+    rx.insert(20, rx[20]);
+    let mut dropped = bytes(&frame(18));
+    dropped.remove(10);
+    rx.extend(dropped);
+    rx.extend(bytes(&frame(19)));
+    rx.extend(bytes(&frame(20)));
+
+    let uart = UartMock { rx, tx: Vec::new(), read_size: None };
+    let mut sensor = PmsX003Sensor::new(uart);
+    sensor.set_checksum_retries(2);
+
+    assert_eq!(sensor.read().unwrap().pm2_5, 20);
+    let stats = sensor.sync_stats();
+    assert_eq!(stats.corrupted_frames, 2);
+    assert!(stats.discarded_bytes > 0);
+}
+
+#[test]
+fn read_discards_warmup_frames_after_wake() {
+    let mut sensor = PmsX003Sensor::new(UartMock::new(&[frame(1), frame(2), frame(3)]));
+    sensor.set_warmup_discard(2);
+    sensor.wake().unwrap();
+
+    assert_eq!(sensor.read().unwrap().pm2_5, 3);
+}
+
+#[test]
+fn queued_command_is_sent_and_acknowledged_during_reads() {
+    let mut uart = UartMock::new(&[frame(1)]);
+    uart.rx.extend_from_slice(&[0x42, 0x4D, 0x00, 0x04, 0xE1, 0x00, 0x01, 0x74]);
+    uart.rx.extend_from_slice(&bytes(&frame(2)));
+
+    let mut sensor = PmsX003Sensor::new(uart);
+    sensor.queue_command(Command::SetPassive).unwrap();
+
+    assert_eq!(sensor.read().unwrap().pm2_5, 1);
+    assert_eq!(sensor.pending_commands(), 1);
+    assert_eq!(sensor.read().unwrap().pm2_5, 2);
+    assert_eq!(sensor.pending_commands(), 0);
+    assert_eq!(sensor.take_failed_command(), None);
+}
+
+#[test]
+fn queued_command_fails_without_response() {
+    let mut sensor = PmsX003Sensor::new(UartMock::new(&[frame(1), frame(2), frame(3)]));
+    sensor.queue_command(Command::Sleep).unwrap();
+
+    for _ in 0..3 {
+        sensor.read().unwrap();
+    }
+    assert_eq!(sensor.take_failed_command(), Some(Command::Sleep));
+}
+
+#[test]
+fn strict_mode_rejects_implausible_frames() {
+    let implausible = OutputFrame::builder().pm2_5(40).pm10(20).build_with_checksum();
+    let plausible = OutputFrame::builder().pm2_5(20).pm10(40).build_with_checksum();
+
+    let mut sensor = PmsX003Sensor::new(UartMock::new(&[implausible, plausible]));
+    sensor.set_parse_mode(ParseMode::Strict);
+
+    assert!(matches!(sensor.read(), Err(Error::OutOfRange)));
+    assert_eq!(sensor.read().unwrap().pm10, 40);
+}
+
+#[test]
+fn faults_become_errors_on_request() {
+    use pmsx003::SensorFaults;
+
+    let faulty = OutputFrame::builder().pm2_5(12).error_code(0x01).build_with_checksum();
+    let mut sensor = PmsX003Sensor::new(UartMock::new(&[faulty.clone(), faulty, frame(13)]));
+
+    assert_eq!(sensor.read().unwrap().fault(), SensorFaults::from_error_code(0x01));
+    sensor.set_faults_as_errors(true);
+    assert!(matches!(sensor.read(), Err(Error::SensorFault(faults)) if faults.error_code() == 0x01));
+    assert_eq!(OutputFrame::from_buffer::<()>(sensor.last_packet().try_into().unwrap()).unwrap().pm2_5, 12);
+    assert_eq!(sensor.read().unwrap().pm2_5, 13);
+}
+
+#[test]
+fn lenient_mode_accepts_implausible_frames() {
+    let implausible = OutputFrame::builder().pm2_5(40).pm10(20).build_with_checksum();
+    let mut sensor = PmsX003Sensor::new(UartMock::new(&[implausible]));
+
+    assert_eq!(sensor.read().unwrap().pm2_5, 40);
+}
+
+#[test]
+fn chunked_reads_use_fixed_size() {
+    let mut uart = UartMock::new(&[frame(3), frame(4)]);
+    // Sensor answering a command before the frames
+    uart.rx.splice(0..0, [0x42, 0x4D, 0x00, 0x04, 0xE1, 0x00, 0x01, 0x74]);
+    uart.read_size = Some(8);
+
+    let mut sensor = PmsX003Sensor::new(uart);
+    sensor.set_read_strategy(ReadStrategy::Chunked);
+
+    assert_eq!(sensor.read().unwrap().pm2_5, 3);
+    assert_eq!(sensor.read().unwrap().pm2_5, 4);
+}
+
+#[test]
+fn mode_commands_parse_responses() {
+    let mut uart = UartMock::new(&[]);
+    uart.rx.extend_from_slice(&[0x42, 0x4D, 0x00, 0x04, 0xE1, 0x00, 0x01, 0x74]);
+    uart.rx.extend_from_slice(&[0x42, 0x4D, 0x00, 0x04, 0xE1, 0x00, 0x01, 0x74]);
+    uart.rx.extend_from_slice(&[0x42, 0x4D, 0x00, 0x04, 0xE4, 0x00, 0x01, 0x70]);
+    let mut sensor = PmsX003Sensor::new(uart);
+
+    assert!(sensor.passive().is_ok());
+    // Response to a different mode
+    assert!(matches!(sensor.active(), Err(Error::IncorrectResponse(_))));
+    assert!(matches!(sensor.sleep(), Err(Error::ChecksumError)));
+}
+
+#[test]
+fn response_frame_parsing() {
+    use pmsx003::ResponseFrame;
+
+    let response = ResponseFrame::from_buffer::<()>(&[0x42, 0x4D, 0x00, 0x04, 0xE4, 0x00, 0x01, 0x77]).unwrap();
+    assert_eq!(response, ResponseFrame { cmd: 0xE4, data: 0x00 });
+    assert!(response.acknowledges(Command::Sleep));
+    assert!(!response.acknowledges(Command::SetPassive));
+}
+
+#[test]
+fn wait_for_ack_skips_data_frames() {
+    let mut uart = UartMock::new(&[frame(1), frame(2)]);
+    uart.rx.extend_from_slice(&[0x42, 0x4D, 0x00, 0x04, 0xE4, 0x00, 0x01, 0x77]);
+    let mut sensor = PmsX003Sensor::new(uart);
+
+    let response = sensor.wait_for_ack(0xE4, 2).unwrap();
+    assert_eq!(response.data, 0x00);
+}
+
+#[test]
+fn wait_for_ack_gives_up_after_frame_budget() {
+    let mut sensor = PmsX003Sensor::new(UartMock::new(&[frame(1), frame(2), frame(3)]));
+    assert!(matches!(sensor.wait_for_ack(0xE4, 2), Err(Error::NoResponse)));
+}
+
+#[test]
+fn read_valid_skips_rejected_frames() {
+    let implausible = OutputFrame::builder().pm2_5(40).pm10(20).build_with_checksum();
+    let plausible = OutputFrame::builder().pm2_5(6).pm10(6).build_with_checksum();
+    let mut corrupted = plausible.clone();
+    corrupted.check ^= 1;
+    let mut sensor = PmsX003Sensor::new(UartMock::new(&[implausible, corrupted, plausible]));
+
+    assert_eq!(sensor.read_valid(2).unwrap().pm2_5, 6);
+}
+
+#[test]
+fn read_valid_returns_last_rejection_beyond_budget() {
+    let implausible = OutputFrame::builder().pm2_5(40).pm10(20).build_with_checksum();
+    let mut sensor = PmsX003Sensor::new(UartMock::new(&[implausible.clone(), implausible]));
+
+    assert!(matches!(sensor.read_valid(1), Err(Error::OutOfRange)));
+}
+
+#[test]
+fn blocking_driver_and_protocol_agree() {
+    use pmsx003::protocol::{Event, Protocol};
+
+    // A doubled first header byte, a response, a packet of unknown length and a header missing
+    // its length around two frames
+    let mut stream = vec![0x00, 0x42];
+    stream.extend_from_slice(&bytes(&frame(1)));
+    stream.extend_from_slice(&[0x42, 0x4D, 0x00, 0x04, 0xE1, 0x00, 0x01, 0x74, 0x42, 0x00, 0x42, 0x4D, 0x00, 0x10]);
+    stream.extend_from_slice(&[0x42, 0x4D]);
+    stream.extend_from_slice(&bytes(&frame(2)));
+
+    let mut protocol = Protocol::new();
+    let events: Vec<_> = stream.iter().filter_map(|byte| protocol.handle_byte(*byte)).collect();
+    assert!(matches!(&events[..], [Event::Frame(a), Event::Response(_), Event::Frame(b)] if a.pm2_5 == 1 && b.pm2_5 == 2));
+
+    let mut uart = UartMock::new(&[]);
+    uart.rx = stream;
+    let mut sensor = PmsX003Sensor::new(uart);
+    assert_eq!(sensor.read().unwrap().pm2_5, 1);
+    assert_eq!(sensor.last_packet(), bytes(&frame(1)));
+    let first = sensor.sync_stats();
+    assert_eq!(sensor.read().unwrap().pm2_5, 2);
+    assert_eq!(sensor.last_packet(), bytes(&frame(2)));
+    let second = sensor.sync_stats();
+
+    let expected = protocol.sync_stats();
+    assert_eq!(first.discarded_bytes + second.discarded_bytes, expected.discarded_bytes);
+    assert_eq!(first.skipped_packets + second.skipped_packets, expected.skipped_packets);
+    assert_eq!(second.partial_header, expected.partial_header);
+}
+
+#[test]
+fn sync_stats_describe_resync() {
+    let mut uart = UartMock::new(&[frame(7)]);
+    // Noise, a lone first header byte and a packet of unknown length
+    uart.rx.splice(0..0, [0x00, 0xFF, 0x42, 0x00, 0x42, 0x4D, 0x00, 0x10]);
+
+    let mut sensor = PmsX003Sensor::new(uart);
+    assert_eq!(sensor.read().unwrap().pm2_5, 7);
+
+    let stats = sensor.sync_stats();
+    assert_eq!(stats.discarded_bytes, 8);
+    assert_eq!(stats.skipped_packets, 1);
+    assert!(stats.partial_header);
+
+    // Stats only cover the last read
+    assert!(sensor.read().is_err());
+    assert_eq!(sensor.sync_stats(), pmsx003::SyncStats::default());
+}
+
+#[test]
+fn sensor_usable_as_trait_object() {
+    use pmsx003::driver::PmSensor;
+
+    let mut uart = UartMock::new(&[frame(8)]);
+    uart.rx.splice(0..0, [0x00]);
+    let mut sensor = PmsX003Sensor::new(uart);
+    let sensor: &mut dyn PmSensor<Error = Error<ErrorKind>> = &mut sensor;
+
+    assert_eq!(sensor.read_latest().unwrap().pm2_5, 8);
+    assert_eq!(sensor.diagnostics().sync.discarded_bytes, 1);
+    assert!(sensor.wake().is_ok());
+}
+
+/// Serial port of a second type, failing every read
+struct BrokenUart;
+
+impl ErrorType for BrokenUart {
+    type Error = ErrorKind;
+}
+
+impl Read for BrokenUart {
+    fn read(&mut self, _buf: &mut [u8]) -> Result<usize, Self::Error> {
+        Err(ErrorKind::BrokenPipe)
+    }
+}
+
+impl Write for BrokenUart {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[test]
+fn erased_sensors_share_one_type() {
+    use pmsx003::driver::ErasedPmsSensor;
+
+    let mut mock = UartMock::new(&[frame(9)]);
+    let mut broken = BrokenUart;
+    let mut sensors = [ErasedPmsSensor::new_erased(&mut mock), ErasedPmsSensor::new_erased(&mut broken)];
+
+    assert_eq!(sensors[0].read().unwrap().pm2_5, 9);
+    assert!(matches!(
+        sensors[1].read(),
+        Err(Error::Read(embedded_io::ReadExactError::Other(ErrorKind::BrokenPipe)))
+    ));
+}
+
+#[test]
+fn protocol_profile_sets_expected_acks() {
+    use pmsx003::{ProtocolProfile, ResponseFrame};
+
+    // Clone acknowledging passive mode with a data byte of 0x01
+    let mut uart = UartMock::new(&[]);
+    uart.rx.extend_from_slice(&[0x42, 0x4D, 0x00, 0x04, 0xE1, 0x01, 0x01, 0x75]);
+    uart.rx.extend_from_slice(&[0x42, 0x4D, 0x00, 0x04, 0xE1, 0x01, 0x01, 0x75]);
+    let mut sensor = PmsX003Sensor::new(uart);
+
+    assert!(matches!(sensor.passive(), Err(Error::IncorrectResponse(_))));
+
+    sensor.set_protocol_profile(ProtocolProfile {
+        passive_ack: ResponseFrame { cmd: 0xE1, data: 0x01 },
+        ..ProtocolProfile::PLANTOWER
+    });
+    assert!(sensor.passive().is_ok());
+}
+
+#[test]
+fn transaction_skips_interleaved_frames() {
+    let mut uart = UartMock::new(&[frame(1)]);
+    uart.rx.extend_from_slice(&[0x42, 0x4D, 0x00, 0x04, 0xE1, 0x00, 0x01, 0x74]);
+    uart.rx.extend_from_slice(&bytes(&frame(2)));
+    let mut sensor = PmsX003Sensor::new(uart);
+
+    sensor.transaction(&[Command::Wake, Command::SetPassive, Command::Request]).unwrap();
+    assert_eq!(sensor.read().unwrap().pm2_5, 2);
+}
+
+#[test]
+fn transaction_reports_failed_command() {
+    let mut uart = UartMock::new(&[]);
+    uart.rx.extend_from_slice(&[0x42, 0x4D, 0x00, 0x04, 0xE1, 0x00, 0x01, 0x74]);
+    uart.rx.extend_from_slice(&[0x42, 0x4D, 0x00, 0x04, 0xE1, 0x00, 0x01, 0x74]);
+    let mut sensor = PmsX003Sensor::new(uart);
+
+    let err = sensor.transaction(&[Command::SetPassive, Command::Sleep]).unwrap_err();
+    assert_eq!(err.completed, 1);
+    assert_eq!(err.command, Command::Sleep);
+    assert!(matches!(err.error, Error::IncorrectResponse(_)));
+}
+
+/// Shorter frame carrying standard and atmospheric concentrations only
+struct ShortCodec;
+
+impl pmsx003::codec::FrameCodec for ShortCodec {
+    fn frame_length(&self) -> u16 {
+        16
+    }
+
+    fn decode(&self, frame: &[u8]) -> OutputFrame {
+        let word = |i: usize| u16::from_be_bytes([frame[4 + 2 * i], frame[5 + 2 * i]]);
+        OutputFrame::builder()
+            .pm1_0(word(0))
+            .pm2_5(word(1))
+            .pm10(word(2))
+            .pm1_0_atm(word(3))
+            .pm2_5_atm(word(4))
+            .pm10_atm(word(5))
+            .build()
+    }
+}
+
+#[test]
+fn custom_codec_decodes_frames() {
+    let mut packet = vec![0x42, 0x4D, 0x00, 0x10, 0, 1, 0, 2, 0, 3, 0, 4, 0, 5, 0, 6, 0, 0];
+    let sum = packet.iter().map(|b| *b as u16).sum::<u16>();
+    packet.extend_from_slice(&sum.to_be_bytes());
+
+    let mut uart = UartMock::new(&[frame(1)]);
+    uart.rx.extend_from_slice(&packet);
+    let mut sensor = PmsX003Sensor::with_codec(uart, ShortCodec);
+
+    // The standard frame is skipped as an unknown packet
+    let frame = sensor.read().unwrap();
+    assert_eq!((frame.pm2_5, frame.pm10_atm), (2, 6));
+    assert_eq!(sensor.sync_stats().skipped_packets, 1);
+}
+
+/// Serial port receiving bytes from a shared queue, as if from an interrupt handler
+struct SharedUart(std::rc::Rc<std::cell::RefCell<Vec<u8>>>);
+
+impl ErrorType for SharedUart {
+    type Error = ErrorKind;
+}
+
+impl Read for SharedUart {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let mut rx = self.0.borrow_mut();
+        let n = buf.len().min(rx.len());
+        buf[..n].copy_from_slice(&rx[..n]);
+        rx.drain(..n);
+        Ok(n)
+    }
+}
+
+impl ReadReady for SharedUart {
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(!self.0.borrow().is_empty())
+    }
+}
+
+impl Write for SharedUart {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[test]
+fn poll_read_returns_none_until_frame_is_complete() {
+    let data = bytes(&frame(14));
+    let rx = std::rc::Rc::new(std::cell::RefCell::new(data[..20].to_vec()));
+    let mut sensor = PmsX003Sensor::new(SharedUart(rx.clone()));
+
+    assert_eq!(sensor.poll_read().unwrap(), None);
+    assert_eq!(sensor.poll_read().unwrap(), None);
+
+    rx.borrow_mut().extend_from_slice(&data[20..]);
+    assert_eq!(sensor.poll_read().unwrap().unwrap().pm2_5, 14);
+    assert_eq!(sensor.poll_read().unwrap(), None);
+}
+
+/// Serial port whose FIFO overruns partway through, until the flag is cleared
+struct OverrunUart {
+    rx: Vec<u8>,
+    until_overrun: Option<usize>,
+    overrun: bool,
+}
+
+impl ErrorType for OverrunUart {
+    type Error = ErrorKind;
+}
+
+impl Read for OverrunUart {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        if self.until_overrun == Some(0) {
+            self.overrun = true;
+            self.until_overrun = None;
+        }
+        if self.overrun {
+            return Err(ErrorKind::Other);
+        }
+        let n = buf.len().min(self.rx.len()).min(self.until_overrun.unwrap_or(usize::MAX));
+        buf[..n].copy_from_slice(&self.rx[..n]);
+        self.rx.drain(..n);
+        self.until_overrun = self.until_overrun.map(|left| left - n);
+        Ok(n)
+    }
+}
+
+impl Write for OverrunUart {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[test]
+fn transport_errors_are_classified() {
+    let rx = bytes(&frame(15));
+    let mut sensor = PmsX003Sensor::new(OverrunUart { rx, until_overrun: Some(20), overrun: false });
+
+    let error = sensor.read().unwrap_err();
+    assert_eq!(error.transport_kind(), Some(ErrorKind::Other));
+    assert_eq!(Error::<ErrorKind>::ChecksumError.transport_kind(), None);
+}
+
+#[test]
+fn transport_error_hook_recovers_read() {
+    let rx = [bytes(&frame(15)), bytes(&frame(16)), bytes(&frame(17))].concat();
+    let mut sensor = PmsX003Sensor::new(OverrunUart { rx, until_overrun: Some(40), overrun: false });
+    sensor.set_on_transport_error(|uart, kind| {
+        assert_eq!(kind, ErrorKind::Other);
+        uart.overrun = false;
+        true
+    });
+
+    // The overrun cut the second frame short
+    assert_eq!(sensor.read().unwrap().pm2_5, 15);
+    assert_eq!(sensor.read().unwrap().pm2_5, 17);
+    assert_eq!(sensor.sync_stats().transport_errors, 1);
+}
+
+#[test]
+fn raw_commands_and_responses() {
+    let mut uart = UartMock::new(&[frame(1)]);
+    uart.rx.extend_from_slice(&[0x42, 0x4D, 0x00, 0x04, 0xE7, 0x05, 0x01, 0x7F]);
+    let mut sensor = PmsX003Sensor::new(uart);
+
+    sensor.send_raw_command(0xE7, 0x0005).unwrap();
+    let response = sensor.read_raw_response(1).unwrap();
+    assert_eq!((response.cmd, response.data), (0xE7, 0x05));
+    assert_eq!(sensor.release().tx, [0x42, 0x4D, 0xE7, 0x00, 0x05, 0x01, 0x7B]);
+}
+
+#[test]
+fn command_frames_round_trip() {
+    for command in [Command::Sleep, Command::Wake] {
+        assert_eq!(Command::decode(&command.encode()), Some(command));
+    }
+
+    let mut frame = Command::Sleep.encode();
+    frame[6] ^= 1;
+    assert_eq!(Command::decode(&frame), None);
+    assert_eq!(Command::decode(&[0x42, 0x4D, 0xE7, 0x00, 0x05, 0x01, 0x7B]), None);
+}
+
+#[test]
+fn incorrect_response_can_be_inspected() {
+    let mut uart = UartMock::new(&[]);
+    uart.rx.extend_from_slice(&[0x42, 0x4D, 0x00, 0x04, 0xE4, 0x01, 0x01, 0x78]);
+    let mut sensor = PmsX003Sensor::new(uart);
+
+    let Err(Error::IncorrectResponse(Some(carried))) = sensor.sleep() else {
+        panic!("expected the received response");
+    };
+    assert_eq!((carried.cmd, carried.data), (0xE4, 1));
+    let response = pmsx003::ResponseFrame::from_buffer::<()>(sensor.last_packet().try_into().unwrap()).unwrap();
+    assert_eq!(response, carried);
+}
+
+#[cfg(all(feature = "std", unix))]
+#[test]
+fn std_pms_opens_port_by_name() {
+    use std::io::{Read as _, Write as _};
+    use std::time::Duration;
+
+    use pmsx003::serial::StdPms;
+    use serialport::{SerialPort, TTYPort};
+
+    let (mut master, slave) = TTYPort::pair().unwrap();
+    let name = slave.name().unwrap();
+    drop(slave);
+    master.set_timeout(Duration::from_secs(1)).unwrap();
+    let mut sensor = StdPms::open(&name).unwrap();
+    let mut sent = [0; 7];
+
+    master.write_all(&[0x42, 0x4D, 0x00, 0x04, 0xE1, 0x00, 0x01, 0x74]).unwrap();
+    sensor.passive().unwrap();
+    master.read_exact(&mut sent).unwrap();
+    assert_eq!(sent, Command::SetPassive.encode());
+
+    sensor.request().unwrap();
+    master.read_exact(&mut sent).unwrap();
+    assert_eq!(sent, Command::Request.encode());
+    master.write_all(&bytes(&frame(12))).unwrap();
+    assert_eq!(sensor.read().unwrap().pm2_5, 12);
+}
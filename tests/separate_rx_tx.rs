@@ -1,4 +1,5 @@
-use pmsx003::PmsX003Sensor;
+use pmsx003::{Error, PmsX003Sensor};
+use pmsx003::transport::{NbUart, NotConnected};
 use embedded_hal_nb::serial::{ErrorType, Read, Write, Error as SerialError, ErrorKind};
 
 struct RxMock {}
@@ -45,3 +46,96 @@ fn crate_instance_using_separate_rx_tx() {
     let mut pms = PmsX003Sensor::new_tx_rx(tx, rx);
     let _ = pms.sleep();
 }
+
+#[test]
+fn release_tx_rx_returns_both_halves() {
+    let pms = PmsX003Sensor::new_tx_rx(TxMock {}, RxMock {});
+    let (_tx, _rx): (TxMock, RxMock) = pms.release_tx_rx();
+}
+
+/// Receiver handing out canned bytes, with a gap before every byte
+struct BurstyRx {
+    bytes: Vec<u8>,
+    gap: bool,
+}
+
+impl ErrorType for BurstyRx {
+    type Error = MockError;
+}
+
+impl Read for BurstyRx {
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        self.gap = !self.gap;
+        if self.gap {
+            return Err(nb::Error::WouldBlock);
+        }
+        match self.bytes.is_empty() {
+            true => Err(nb::Error::Other(MockError)),
+            false => Ok(self.bytes.remove(0)),
+        }
+    }
+}
+
+#[test]
+fn rx_only_sensor_reads_frames() {
+    let mut bytes = vec![0x42, 0x4D, 0x00, 0x1C];
+    bytes.extend_from_slice(&[0; 26]);
+    bytes.extend_from_slice(&0x00ABu16.to_be_bytes());
+    let mut pms = PmsX003Sensor::new_rx_only(BurstyRx { bytes, gap: false });
+
+    pms.read().unwrap();
+    assert!(matches!(pms.sleep(), Err(Error::Write(_))));
+}
+
+#[derive(Debug)]
+struct KindError(ErrorKind);
+
+impl SerialError for KindError {
+    fn kind(&self) -> ErrorKind {
+        self.0
+    }
+}
+
+/// Receiver yielding its canned results in order, then blocking
+struct ScriptedRx(Vec<nb::Result<u8, KindError>>);
+
+impl ErrorType for ScriptedRx {
+    type Error = KindError;
+}
+
+impl Read for ScriptedRx {
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        match self.0.is_empty() {
+            true => Err(nb::Error::WouldBlock),
+            false => self.0.remove(0),
+        }
+    }
+}
+
+#[test]
+fn receive_error_keeps_bytes_already_read() {
+    use embedded_io::{Error as _, ErrorKind as IoKind, Read as _};
+
+    let rx = ScriptedRx(vec![Ok(0x42), Ok(0x4D), Err(nb::Error::Other(KindError(ErrorKind::Overrun))), Ok(0x00)]);
+    let mut uart = NbUart::new(NotConnected, rx);
+    let mut buf = [0; 8];
+
+    assert_eq!(uart.read(&mut buf).unwrap(), 2);
+    assert_eq!(&buf[..2], &[0x42, 0x4D]);
+    let error = uart.read(&mut buf).unwrap_err();
+    assert!(error.is_overrun());
+    assert_eq!(error.kind(), IoKind::Other);
+    assert_eq!(uart.read(&mut buf).unwrap(), 1);
+}
+
+#[test]
+fn line_errors_map_to_invalid_data() {
+    use embedded_io::{Error as _, ErrorKind as IoKind, Read as _};
+
+    for kind in [ErrorKind::FrameFormat, ErrorKind::Parity, ErrorKind::Noise] {
+        let mut uart = NbUart::new(NotConnected, ScriptedRx(vec![Err(nb::Error::Other(KindError(kind)))]));
+        let error = uart.read(&mut [0; 4]).unwrap_err();
+        assert_eq!(error.kind(), IoKind::InvalidData);
+        assert!(!error.is_overrun());
+    }
+}
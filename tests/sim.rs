@@ -0,0 +1,45 @@
+#![cfg(feature = "sim")]
+
+use embedded_hal::delay::DelayNs;
+use pmsx003::sim::SimTransport;
+use pmsx003::{Error, ParseMode, PmsX003Sensor};
+
+/// Runs the simulation as fast as possible, keeping track of simulated time
+struct SimClock<'a>(&'a mut u64);
+
+impl DelayNs for SimClock<'_> {
+    fn delay_ns(&mut self, ns: u32) {
+        *self.0 += ns as u64;
+    }
+}
+
+#[test]
+fn simulated_sensor_reports_plausible_frames() {
+    let mut elapsed_ns = 0;
+    {
+        let mut sim = SimTransport::new(SimClock(&mut elapsed_ns), 42);
+        sim.set_baseline(80);
+        let mut sensor = PmsX003Sensor::new(sim);
+        sensor.set_parse_mode(ParseMode::Strict);
+
+        for _ in 0..100 {
+            let frame = sensor.read().unwrap();
+            assert!((40..=120).contains(&frame.pm2_5), "{}", frame.pm2_5);
+        }
+    }
+    assert_eq!(elapsed_ns, 100_000_000_000);
+}
+
+#[test]
+fn simulated_sensor_follows_commands() {
+    let mut elapsed_ns = 0;
+    let mut sensor = PmsX003Sensor::new(SimTransport::new(SimClock(&mut elapsed_ns), 7));
+
+    sensor.passive().unwrap();
+    assert!(matches!(sensor.read(), Err(Error::Read(_))));
+    sensor.request().unwrap();
+    assert!(sensor.read().is_ok());
+
+    sensor.sleep().unwrap();
+    assert!(sensor.release().is_asleep());
+}
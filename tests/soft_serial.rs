@@ -0,0 +1,75 @@
+#![cfg(feature = "soft-serial")]
+
+mod common;
+
+use std::cell::Cell;
+use std::convert::Infallible;
+use std::rc::Rc;
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{ErrorType, InputPin};
+use pmsx003::soft_serial::SoftSerialRx;
+use pmsx003::PmsX003Sensor;
+use common::frame;
+
+const BIT_NS: u64 = 1_000_000_000 / 9600;
+
+/// Line driven by a simulated transmitter, clocked by the delay
+struct Line {
+    levels: Vec<bool>,
+    now_ns: Rc<Cell<u64>>,
+}
+
+impl Line {
+    fn new(bytes: &[u8], now_ns: Rc<Cell<u64>>) -> Self {
+        // Some idle time, then 8N1 bytes back to back
+        let mut levels = vec![true; 5];
+        for byte in bytes {
+            levels.push(false);
+            levels.extend((0..8).map(|bit| byte & (1 << bit) != 0));
+            levels.push(true);
+        }
+        Self { levels, now_ns }
+    }
+}
+
+impl ErrorType for Line {
+    type Error = Infallible;
+}
+
+impl InputPin for Line {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        let bit = (self.now_ns.get() / BIT_NS) as usize;
+        Ok(self.levels.get(bit).copied().unwrap_or(true))
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        self.is_high().map(|high| !high)
+    }
+}
+
+struct Clock(Rc<Cell<u64>>);
+
+impl DelayNs for Clock {
+    fn delay_ns(&mut self, ns: u32) {
+        self.0.set(self.0.get() + ns as u64);
+    }
+}
+
+#[test]
+fn receives_frame_from_gpio() {
+    let now = Rc::new(Cell::new(0));
+    let rx = SoftSerialRx::new(Line::new(&frame(42), now.clone()), Clock(now));
+    let mut sensor = PmsX003Sensor::new(rx);
+
+    assert_eq!(sensor.read().unwrap().pm2_5, 42);
+}
+
+#[test]
+fn commands_are_unsupported() {
+    let now = Rc::new(Cell::new(0));
+    let rx = SoftSerialRx::new(Line::new(&[], now.clone()), Clock(now));
+    let mut sensor = PmsX003Sensor::new(rx);
+
+    assert!(sensor.request().is_err());
+}
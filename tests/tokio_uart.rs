@@ -0,0 +1,51 @@
+#![cfg(feature = "tokio")]
+
+mod common;
+
+use core::future::Future;
+use core::pin::pin;
+use core::task::{Context, Poll, Waker};
+
+use pmsx003::asynch::PmsX003SensorAsync;
+use pmsx003::Command;
+use common::frame;
+
+/// Reads go through embassy-time when `embassy` is enabled, which needs a time driver.
+/// This one's clock stands still, so reads never time out.
+#[cfg(feature = "embassy")]
+mod stopped_clock {
+    struct StoppedClock;
+
+    impl embassy_time_driver::Driver for StoppedClock {
+        fn now(&self) -> u64 {
+            0
+        }
+
+        fn schedule_wake(&self, _at: u64, _waker: &core::task::Waker) {}
+    }
+
+    embassy_time_driver::time_driver_impl!(static DRIVER: StoppedClock = StoppedClock);
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+#[test]
+fn tokio_port_drives_async_sensor() {
+    let rx = frame(31);
+    let port = tokio::io::join(&rx[..], Vec::new());
+    let mut sensor = PmsX003SensorAsync::from_tokio(port);
+
+    assert_eq!(block_on(sensor.read()).unwrap().pm2_5, 31);
+    assert!(block_on(sensor.wake()).is_ok());
+
+    let (_, tx) = sensor.release().into_inner().into_inner();
+    assert_eq!(tx, Command::Wake.encode());
+}
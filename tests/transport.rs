@@ -0,0 +1,319 @@
+mod common;
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use embedded_hal::delay::DelayNs;
+use embedded_io::{ErrorKind, ErrorType, Read, ReadReady, Write, WriteReady};
+use pmsx003::transport::{PolledUart, Rs485Uart, SharedUart, TimeoutError, TimeoutUart};
+use pmsx003::{Error, PmsX003Sensor};
+use common::frame;
+
+/// Blocking driver which would hang when out of data, like esp-idf-hal's UartDriver
+struct BlockingUart {
+    rx: Vec<u8>,
+}
+
+impl ErrorType for BlockingUart {
+    type Error = ErrorKind;
+}
+
+impl Read for BlockingUart {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        assert!(!self.rx.is_empty(), "read would block forever");
+        let n = buf.len().min(self.rx.len());
+        buf[..n].copy_from_slice(&self.rx[..n]);
+        self.rx.drain(..n);
+        Ok(n)
+    }
+}
+
+impl ReadReady for BlockingUart {
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(!self.rx.is_empty())
+    }
+}
+
+impl Write for BlockingUart {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+struct CountingDelay(u32);
+
+impl DelayNs for CountingDelay {
+    fn delay_ns(&mut self, ns: u32) {
+        self.0 += ns / 1_000_000;
+    }
+}
+
+#[test]
+fn timeout_uart_gives_up_when_idle() {
+    let uart = TimeoutUart::new(BlockingUart { rx: frame(8).to_vec() }, CountingDelay(0), 50);
+    let mut sensor = PmsX003Sensor::new(uart);
+
+    assert_eq!(sensor.read().unwrap().pm2_5, 8);
+    assert!(matches!(sensor.read(), Err(Error::Read(embedded_io::ReadExactError::Other(TimeoutError::TimedOut)))));
+}
+
+/// Half-duplex link checking the transceiver drives the bus only while transmitting
+struct Rs485Link {
+    de: Rc<Cell<bool>>,
+    rx: Vec<u8>,
+}
+
+impl ErrorType for Rs485Link {
+    type Error = ErrorKind;
+}
+
+impl Read for Rs485Link {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        assert!(!self.de.get(), "receiving while driving the bus");
+        let n = buf.len().min(self.rx.len());
+        buf[..n].copy_from_slice(&self.rx[..n]);
+        self.rx.drain(..n);
+        Ok(n)
+    }
+}
+
+impl Write for Rs485Link {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        assert!(self.de.get(), "transmitting without driving the bus");
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+struct DePin(Rc<Cell<bool>>);
+
+impl embedded_hal::digital::ErrorType for DePin {
+    type Error = core::convert::Infallible;
+}
+
+impl embedded_hal::digital::OutputPin for DePin {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.0.set(false);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.0.set(true);
+        Ok(())
+    }
+}
+
+#[test]
+fn rs485_drives_bus_only_while_writing() {
+    let de = Rc::new(Cell::new(true));
+    let link = Rs485Link {
+        de: de.clone(),
+        rx: vec![0x42, 0x4D, 0x00, 0x04, 0xE4, 0x00, 0x01, 0x77],
+    };
+    let uart = Rs485Uart::new(link, DePin(de.clone()), CountingDelay(0), 2000).unwrap();
+    let mut sensor = PmsX003Sensor::new(uart);
+
+    sensor.sleep().unwrap();
+    assert!(!de.get());
+}
+
+/// CDC-ACM port receiving USB packets of up to 8 bytes, each only once the device was polled
+struct CdcMock {
+    packets: Vec<Vec<u8>>,
+    received: Vec<u8>,
+}
+
+impl CdcMock {
+    fn poll(&mut self) {
+        if !self.packets.is_empty() {
+            let packet = self.packets.remove(0);
+            self.received.extend(packet);
+        }
+    }
+}
+
+impl ErrorType for CdcMock {
+    type Error = ErrorKind;
+}
+
+impl Read for CdcMock {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        assert!(!self.received.is_empty(), "read would block");
+        let n = buf.len().min(self.received.len());
+        buf[..n].copy_from_slice(&self.received[..n]);
+        self.received.drain(..n);
+        Ok(n)
+    }
+}
+
+impl ReadReady for CdcMock {
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(!self.received.is_empty())
+    }
+}
+
+impl Write for CdcMock {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl WriteReady for CdcMock {
+    fn write_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(true)
+    }
+}
+
+#[test]
+fn polled_uart_reassembles_usb_packets() {
+    let mut packets: Vec<Vec<u8>> = frame(9).chunks(8).map(<[u8]>::to_vec).collect();
+    packets.insert(1, Vec::new());
+    let cdc = CdcMock { packets, received: Vec::new() };
+    let mut sensor = PmsX003Sensor::new(PolledUart::new(cdc, CdcMock::poll));
+
+    assert_eq!(sensor.read().unwrap().pm2_5, 9);
+}
+
+/// Analog switch in front of two sensors, each with its own pending bytes
+struct MuxMock {
+    channel: Rc<Cell<usize>>,
+    rx: [Vec<u8>; 2],
+}
+
+impl ErrorType for MuxMock {
+    type Error = ErrorKind;
+}
+
+impl Read for MuxMock {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let rx = &mut self.rx[self.channel.get()];
+        let n = buf.len().min(rx.len());
+        buf[..n].copy_from_slice(&rx[..n]);
+        rx.drain(..n);
+        Ok(n)
+    }
+}
+
+impl Write for MuxMock {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[test]
+fn sensors_share_uart_behind_mux() {
+    let channel = Rc::new(Cell::new(0));
+    let switches = Cell::new(0);
+    let bus = SharedUart::new(MuxMock {
+        channel: channel.clone(),
+        rx: [[frame(1), frame(2)].concat(), frame(3).to_vec()],
+    });
+    let select = |index| {
+        let channel = channel.clone();
+        let switches = &switches;
+        move || {
+            channel.set(index);
+            switches.set(switches.get() + 1);
+        }
+    };
+    let mut first = PmsX003Sensor::new(bus.channel(0, select(0)));
+    let mut second = PmsX003Sensor::new(bus.channel(1, select(1)));
+
+    assert_eq!(first.read().unwrap().pm2_5, 1);
+    assert_eq!(first.read().unwrap().pm2_5, 2);
+    assert_eq!(second.read().unwrap().pm2_5, 3);
+    assert_eq!(switches.get(), 2);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn io_uart_reads_over_tcp() {
+    use std::io::Write as _;
+    use std::net::{TcpListener, TcpStream};
+
+    use pmsx003::transport::IoUart;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let mut bridge = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (stream, _) = listener.accept().unwrap();
+
+    // Network chunks don't line up with frames
+    let data = [frame(5), frame(6)].concat();
+    bridge.write_all(&data[..20]).unwrap();
+    bridge.write_all(&data[20..]).unwrap();
+    drop(bridge);
+
+    let mut sensor = PmsX003Sensor::new(IoUart::new(stream));
+    assert_eq!(sensor.read().unwrap().pm2_5, 5);
+    assert_eq!(sensor.read().unwrap().pm2_5, 6);
+    assert!(matches!(sensor.read(), Err(Error::Read(embedded_io::ReadExactError::UnexpectedEof))));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn io_uart_connects_to_bridge() {
+    use std::io::{Read as _, Write as _};
+    use std::net::TcpListener;
+
+    use pmsx003::transport::IoUart;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let mut sensor = PmsX003Sensor::new(IoUart::connect(listener.local_addr().unwrap()).unwrap());
+    let (mut bridge, _) = listener.accept().unwrap();
+
+    // Several frames arriving in one chunk
+    bridge.write_all(&[frame(7), frame(8), frame(9)].concat()).unwrap();
+    assert_eq!(sensor.read().unwrap().pm2_5, 7);
+    assert_eq!(sensor.read().unwrap().pm2_5, 8);
+    assert_eq!(sensor.read().unwrap().pm2_5, 9);
+
+    let mut sent = [0u8; 7];
+    bridge.write_all(&[0x42, 0x4D, 0x00, 0x04, 0xE1, 0x00, 0x01, 0x74]).unwrap();
+    sensor.passive().unwrap();
+    bridge.read_exact(&mut sent).unwrap();
+    assert_eq!(sent, pmsx003::Command::SetPassive.encode());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn sensor_reads_from_cursor() {
+    use std::io::Cursor;
+
+    let mut sensor = PmsX003Sensor::from_io(Cursor::new([frame(10), frame(11)].concat()));
+    assert_eq!(sensor.read().unwrap().pm2_5, 10);
+    assert_eq!(sensor.read().unwrap().pm2_5, 11);
+    assert!(matches!(sensor.read(), Err(Error::Read(embedded_io::ReadExactError::UnexpectedEof))));
+}
+
+#[cfg(all(feature = "std", unix))]
+#[test]
+fn sensor_reads_from_serialport() {
+    use std::io::Write as _;
+    use std::time::Duration;
+
+    use serialport::{SerialPort, TTYPort};
+
+    let (mut master, mut slave) = TTYPort::pair().unwrap();
+    slave.set_timeout(Duration::from_millis(100)).unwrap();
+    let mut sensor = PmsX003Sensor::from_serialport(Box::new(slave));
+
+    master.write_all(&frame(9)).unwrap();
+    assert_eq!(sensor.read().unwrap().pm2_5, 9);
+    // Silence ends in a timeout instead of blocking
+    assert_eq!(sensor.read().unwrap_err().transport_kind(), Some(ErrorKind::TimedOut));
+}
@@ -0,0 +1,300 @@
+use embedded_io::{ErrorKind, ErrorType, Read, Write};
+use pmsx003::codec::{FrameCodec, PlantowerCodec};
+use pmsx003::variant::{FromFrameBytes, PmsVariant, Variant};
+#[cfg(feature = "pms5003")]
+use pmsx003::variant::{OutputFrameS, OutputFrameT};
+#[cfg(any(feature = "pms5003st", feature = "pms9003m"))]
+use pmsx003::variant::VariantFrame;
+#[cfg(feature = "pms5003st")]
+use pmsx003::variant::OutputFrameSt;
+#[cfg(all(feature = "pms3003", not(feature = "active-only")))]
+use pmsx003::Command;
+use pmsx003::{OutputFrame, PmsX003Sensor};
+#[cfg(any(feature = "pms5003", all(feature = "pms3003", not(feature = "active-only"))))]
+use pmsx003::Error;
+#[cfg(any(feature = "pms1003", feature = "pms3003", feature = "pms5003", feature = "pms5003st"))]
+use pmsx003::ParseMode;
+
+/// Serial port replaying canned bytes
+struct UartMock {
+    rx: Vec<u8>,
+}
+
+impl ErrorType for UartMock {
+    type Error = ErrorKind;
+}
+
+impl Read for UartMock {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let n = buf.len().min(self.rx.len());
+        buf[..n].copy_from_slice(&self.rx[..n]);
+        self.rx.drain(..n);
+        Ok(n)
+    }
+}
+
+impl Write for UartMock {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// Frame with the given data words, followed by the checksum
+fn frame(data: &[u16]) -> Vec<u8> {
+    let mut bytes = vec![0x42, 0x4D];
+    bytes.extend_from_slice(&(data.len() as u16 * 2 + 2).to_be_bytes());
+    for word in data {
+        bytes.extend_from_slice(&word.to_be_bytes());
+    }
+    let checksum: u16 = bytes.iter().map(|b| *b as u16).sum();
+    bytes.extend_from_slice(&checksum.to_be_bytes());
+    bytes
+}
+
+#[cfg(feature = "pms5003")]
+#[test]
+fn pms5003t_reports_temperature_and_humidity() {
+    let temperature = -35i16 as u16;
+    let data = frame(&[5, 8, 9, 5, 8, 9, 900, 300, 60, 6, temperature, 455, 0]);
+    let mut sensor = PmsX003Sensor::new_pms5003t(UartMock { rx: data.repeat(2) });
+    sensor.set_parse_mode(ParseMode::Strict);
+
+    let frame = sensor.read_t().unwrap();
+    assert_eq!((frame.temperature, frame.humidity), (-35, 455));
+    assert_eq!((frame.particles.pm2_5, frame.particles.beyond_2_5), (8, 6));
+    assert_eq!((frame.particles.beyond_5_0, frame.particles.beyond_10_0), (0, 0));
+    assert_eq!(sensor.last_packet(), data);
+
+    // Plain reads see the particles only
+    assert_eq!(sensor.read().unwrap(), frame.particles);
+}
+
+#[cfg(feature = "pms5003")]
+#[test]
+fn pms5003t_frame_from_buffer() {
+    let mut data: [u8; 32] = frame(&[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 215, 600, 0]).try_into().unwrap();
+    assert_eq!(OutputFrameT::from_buffer::<()>(&data).unwrap().temperature, 215);
+
+    data[25] ^= 1;
+    assert!(matches!(OutputFrameT::from_buffer::<()>(&data), Err(Error::ChecksumError)));
+}
+
+#[cfg(all(feature = "float", feature = "pms5003"))]
+#[test]
+fn pms5003t_scaled_readings() {
+    let data: [u8; 32] = frame(&[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 215, 600, 0]).try_into().unwrap();
+    let frame = OutputFrameT::from_buffer::<()>(&data).unwrap();
+    assert_eq!((frame.temperature_c(), frame.humidity_percent()), (21.5, 60.0));
+}
+
+#[cfg(feature = "pms5003st")]
+#[test]
+fn pms5003st_reads_40_byte_frames() {
+    let data = frame(&[5, 8, 9, 5, 8, 9, 900, 300, 60, 6, 2, 1, 12, 215, 455, 0, 0x91]);
+    assert_eq!(data.len(), 40);
+    let mut sensor = PmsX003Sensor::new_pms5003st(UartMock { rx: data.clone() });
+    sensor.set_parse_mode(ParseMode::Strict);
+
+    let frame = sensor.read_st().unwrap();
+    assert_eq!((frame.hcho, frame.temperature, frame.humidity), (12, 215, 455));
+    assert_eq!((frame.particles.pm10, frame.particles.beyond_10_0), (9, 1));
+    assert_eq!((frame.particles.frame_length, frame.particles.reserved()), (36, 0x91));
+    assert_eq!(OutputFrameSt::from_buffer::<()>(&data.try_into().unwrap()).unwrap(), frame);
+}
+
+#[cfg(feature = "pms5003st")]
+#[test]
+fn pms5003st_skips_32_byte_frames() {
+    let mut rx = frame(&[1; 13]);
+    rx.extend_from_slice(&frame(&[0, 7, 0, 0, 7, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]));
+    let mut sensor = PmsX003Sensor::new_pms5003st(UartMock { rx });
+
+    assert_eq!(sensor.read_st().unwrap().particles.pm2_5, 7);
+    assert_eq!(sensor.sync_stats().skipped_packets, 1);
+}
+
+#[cfg(feature = "pms5003")]
+#[test]
+fn pms5003s_reports_formaldehyde() {
+    let data = frame(&[5, 8, 9, 5, 8, 9, 900, 300, 60, 6, 2, 1, 31]);
+    let mut sensor = PmsX003Sensor::new_pms5003s(UartMock { rx: data.clone() });
+
+    let frame = sensor.read_s().unwrap();
+    assert_eq!(frame.hcho_ug_m3(), 31);
+    assert_eq!((frame.particles.beyond_10_0, frame.particles.reserved()), (1, 0));
+    assert_eq!(OutputFrameS::from_buffer::<()>(&data.try_into().unwrap()).unwrap(), frame);
+}
+
+#[cfg(all(feature = "float", feature = "pms5003"))]
+#[test]
+fn pms5003s_formaldehyde_in_mg() {
+    let data: [u8; 32] = frame(&[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 125]).try_into().unwrap();
+    assert_eq!(OutputFrameS::from_buffer::<()>(&data).unwrap().hcho_mg_m3(), 0.125);
+}
+
+#[cfg(feature = "pms3003")]
+#[test]
+fn pms3003_reads_24_byte_frames() {
+    let data = frame(&[5, 8, 9, 4, 7, 9, 0, 0, 0]);
+    assert_eq!(data.len(), 24);
+    let mut sensor = PmsX003Sensor::new_pms3003(UartMock { rx: data });
+    sensor.set_parse_mode(ParseMode::Strict);
+
+    let frame = sensor.read().unwrap();
+    assert_eq!((frame.pm2_5, frame.pm10_atm, frame.frame_length), (8, 9, 20));
+    assert_eq!(frame.beyond_0_3, 0);
+}
+
+#[cfg(all(feature = "pms3003", not(feature = "active-only")))]
+#[test]
+fn pms3003_rejects_passive_mode() {
+    let mut sensor = PmsX003Sensor::new_pms3003(UartMock { rx: Vec::new() });
+
+    assert!(matches!(sensor.passive(), Err(Error::UnsupportedByVariant(Command::SetPassive))));
+    assert!(matches!(sensor.request(), Err(Error::UnsupportedByVariant(Command::Request))));
+    assert_eq!(sensor.queue_command(Command::Request), Err(Command::Request));
+}
+
+#[cfg(feature = "pms1003")]
+#[test]
+fn pms1003_ignores_count_bins() {
+    let data = frame(&[5, 8, 9, 4, 7, 9, 3, 900, 2, 0, 0, 0, 0]);
+    let mut sensor = PmsX003Sensor::new_pms1003(UartMock { rx: data });
+    sensor.set_parse_mode(ParseMode::Strict);
+
+    let frame = sensor.read().unwrap();
+    assert_eq!((frame.pm2_5, frame.pm10_atm), (8, 9));
+    assert_eq!((frame.beyond_0_3, frame.beyond_0_5), (0, 0));
+}
+
+#[cfg(feature = "pms5003st")]
+#[test]
+fn variant_picks_frame_type() {
+    let data = frame(&[5, 8, 9, 5, 8, 9, 900, 300, 60, 6, 2, 1, 12, 215, 455, 0, 0x91]);
+    let mut sensor = PmsX003Sensor::new_variant(UartMock { rx: data }, Variant::Pms5003ST);
+
+    let frame = sensor.read_variant().unwrap();
+    assert!(matches!(&frame, VariantFrame::St(st) if st.hcho == 12));
+    assert_eq!(frame.particles().pm2_5, 8);
+    assert_eq!(sensor.variant().frame_size(), 40);
+}
+
+#[test]
+fn variant_checks_frame_length() {
+    let mut rx = frame(&[5, 8, 9, 4, 7, 9, 0, 0, 0]);
+    rx.extend_from_slice(&frame(&[0, 3, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0]));
+    let mut sensor = PmsX003Sensor::new_variant(UartMock { rx }, Variant::Pms7003);
+
+    assert_eq!(sensor.read_variant().unwrap().particles().pm2_5, 3);
+    assert_eq!(sensor.sync_stats().skipped_packets, 1);
+}
+
+#[cfg(all(feature = "pms3003", not(feature = "active-only")))]
+#[test]
+fn variant_gates_commands() {
+    let mut sensor = PmsX003Sensor::new_variant(UartMock { rx: Vec::new() }, Variant::Pms3003);
+    assert!(matches!(sensor.passive(), Err(Error::UnsupportedByVariant(Command::SetPassive))));
+}
+
+#[cfg(all(feature = "pms3003", feature = "pms5003st"))]
+#[test]
+fn detect_tells_models_from_frame_length() {
+    let mut rx = vec![0x42, 0x4D, 0x00, 0x04, 0xE1, 0x00, 0x01, 0x74];
+    rx.extend_from_slice(&frame(&[5, 8, 9, 4, 7, 9, 0, 0, 0]));
+    rx.extend_from_slice(&frame(&[5, 8, 9, 4, 7, 9, 0, 0, 0]));
+    let mut sensor = PmsX003Sensor::new_variant(UartMock { rx }, Variant::default());
+
+    assert_eq!(sensor.detect().unwrap(), Variant::Pms3003);
+    assert_eq!(sensor.sync_stats().skipped_packets, 1);
+    assert_eq!(sensor.variant(), Variant::Pms3003);
+    assert_eq!(sensor.read().unwrap().pm2_5, 8);
+
+    for (data, variant) in [
+        (frame(&[0; 13]), Variant::Pms5003),
+        (frame(&[0; 17]), Variant::Pms5003ST),
+    ] {
+        let mut sensor = PmsX003Sensor::new_variant(UartMock { rx: data }, Variant::Pms3003);
+        assert_eq!(sensor.detect().unwrap(), variant);
+    }
+}
+
+#[cfg(feature = "pms3003")]
+#[test]
+fn detect_keeps_probed_frame_as_last_packet() {
+    let probed = frame(&[5, 8, 9, 4, 7, 9, 0, 0, 0]);
+    let mut rx = vec![0x00, 0x42, 0x00, 0x42, 0x4D, 0x00, 0x04, 0xE1, 0x00, 0x01, 0x74];
+    rx.extend_from_slice(&probed);
+    let mut sensor = PmsX003Sensor::new_variant(UartMock { rx }, Variant::default());
+
+    assert_eq!(sensor.detect().unwrap(), Variant::Pms3003);
+    assert_eq!(sensor.last_packet(), &probed[..]);
+}
+
+/// Made-up model reporting a noise level after the particle counts
+#[derive(Default)]
+struct NoisyCodec;
+
+struct NoisyFrame {
+    particles: OutputFrame,
+    noise_db: u16,
+}
+
+impl FromFrameBytes for NoisyFrame {
+    fn from_frame_bytes(particles: OutputFrame, frame: &[u8]) -> Self {
+        Self { particles, noise_db: u16::from_be_bytes([frame[30], frame[31]]) }
+    }
+}
+
+impl FrameCodec for NoisyCodec {
+    fn frame_length(&self) -> u16 {
+        30
+    }
+
+    fn decode(&self, frame: &[u8]) -> OutputFrame {
+        PlantowerCodec.decode(&frame[..32])
+    }
+}
+
+impl PmsVariant for NoisyCodec {
+    const FRAME_SIZE: usize = 34;
+    type Frame = NoisyFrame;
+}
+
+#[test]
+fn new_models_implement_pms_variant() {
+    let mut sensor = PmsX003Sensor::with_codec(UartMock { rx: frame(&[0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 42]) }, NoisyCodec);
+
+    let frame = sensor.read_frame().unwrap();
+    assert_eq!((frame.particles.pm2_5, frame.noise_db), (4, 42));
+}
+
+#[cfg(feature = "pms5003")]
+#[test]
+fn read_frame_returns_model_frame_type() {
+    let data = frame(&[5, 8, 9, 5, 8, 9, 900, 300, 60, 6, 215, 455, 0]);
+    let mut sensor = PmsX003Sensor::new_pms5003t(UartMock { rx: data.clone() });
+    let frame: OutputFrameT = sensor.read_frame().unwrap();
+    assert_eq!(frame.temperature, 215);
+
+    let mut sensor = PmsX003Sensor::new(UartMock { rx: data });
+    let frame: OutputFrame = sensor.read_frame().unwrap();
+    assert_eq!(frame.beyond_5_0, 215);
+}
+
+#[cfg(feature = "pms9003m")]
+#[test]
+fn m_series_reports_validity_instead_of_faults() {
+    let mut rx = frame(&[5, 8, 9, 5, 8, 9, 900, 300, 60, 6, 2, 1, 0x0103]);
+    rx.extend_from_slice(&frame(&[5, 8, 9, 5, 8, 9, 900, 300, 60, 6, 2, 1, 0x0100]));
+    let mut sensor = PmsX003Sensor::new_variant(UartMock { rx }, Variant::Pms9103M);
+
+    let VariantFrame::M(frame) = sensor.read_variant().unwrap() else { panic!() };
+    assert!(!frame.valid);
+    assert_eq!(frame.particles.fault(), None);
+    let VariantFrame::M(frame) = sensor.read_variant().unwrap() else { panic!() };
+    assert!(frame.valid);
+}